@@ -18,6 +18,16 @@ impl ApiController {
   async fn info(user_agent: String, authorization: String) -> String {
     format!("User-Agent: {}, Auth: {}", user_agent, authorization)
   }
+
+  #[get("/token", extract(token = BearerToken))]
+  async fn token(token: String) -> String {
+    format!("Bearer token: {}", token)
+  }
+
+  #[get("/login", extract(creds = BasicAuth))]
+  async fn login(creds: (String, String)) -> String {
+    format!("Basic auth user: {}", creds.0)
+  }
 }
 
 #[tokio::main]
@@ -34,6 +44,8 @@ async fn main() {
   println!(
     "  curl http://localhost:3000/api/info -H 'authorization: Bearer token123' -H 'user-agent: MyApp/1.0'"
   );
+  println!("  curl http://localhost:3000/api/token -H 'authorization: Bearer token123'");
+  println!("  curl -u alice:secret http://localhost:3000/api/login");
 
   axum::serve(listener, app).await.unwrap();
 }
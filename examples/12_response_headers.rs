@@ -11,6 +11,11 @@ struct ApiResponse {
   message: String,
 }
 
+// Computed per request from the request parts (stateless controllers receive `&()`)
+fn echo_method(parts: &axum::http::request::Parts, _state: &()) -> String {
+  parts.method.to_string()
+}
+
 struct ApiController;
 
 // Controller-level headers apply to all routes
@@ -45,6 +50,16 @@ impl ApiController {
     r#"<?xml version="1.0"?><response><message>Hello XML</message></response>"#.to_string()
   }
 
+  // Header values from expressions and functions
+  #[get(
+    "/dynamic",
+    header("x-crate-version", env!("CARGO_PKG_VERSION")),
+    header("x-method", with = echo_method)
+  )]
+  async fn dynamic_headers() -> &'static str {
+    "Headers computed from an expression and from the request"
+  }
+
   // Content type with custom headers
   #[post(
     "/data",
@@ -74,6 +89,8 @@ async fn main() {
   println!("    -> Should see: x-api-version: 2.0 (overridden), x-request-id: abc-123");
   println!("  curl -i http://localhost:3000/api/xml");
   println!("    -> Should see: content-type: application/xml, controller headers");
+  println!("  curl -i http://localhost:3000/api/dynamic");
+  println!("    -> Should see: x-crate-version: <crate version>, x-method: GET");
   println!("  curl -i -X POST http://localhost:3000/api/data");
   println!("    -> Should see: x-api-version: 3.0 (overridden), content-type: application/json");
 
@@ -1,13 +1,30 @@
 //! Controller implementation logic
 
 use proc_macro::TokenStream;
-use syn::{ItemImpl, parse_macro_input};
+use proc_macro_error::emit_call_site_error;
+use syn::{ImplItem, ItemImpl, parse_macro_input};
 
 use crate::generator;
 use crate::parser;
 
 pub fn controller_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
-  let impl_block = parse_macro_input!(item as ItemImpl);
+  expand(attr, item, false)
+}
+
+/// `#[rpc_controller]`: the `#[rpc]` methods are served by a JSON-RPC dispatcher registered in
+/// place of the routes, under the same middleware, CORS, prefix and header layers
+pub fn rpc_controller_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
+  expand(attr, item, true)
+}
+
+fn expand(attr: TokenStream, item: TokenStream, rpc: bool) -> TokenStream {
+  let mut impl_block = parse_macro_input!(item as ItemImpl);
+
+  // Controller-wide #[requires(...)] attributes are consumed here rather than expanded
+  let requirements = parser::parse_requirements(&impl_block.attrs);
+  impl_block
+    .attrs
+    .retain(|attr| !parser::is_requires_attr(attr));
   let name = &impl_block.self_ty;
 
   log_verbose!(
@@ -15,10 +32,29 @@ pub fn controller_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
     quote::quote! { #name }.to_string()
   );
 
-  let config = parser::parse_controller_attributes(&attr);
+  let mut config = parser::parse_controller_attributes(&attr);
+  config.requirements = requirements;
+  config.catch_handler = find_catch_handler(&impl_block);
 
-  let route_registrations = generator::generate_route_registrations(&impl_block, &config);
+  let (route_registrations, rpc_functions) = if rpc {
+    if !config.requirements.is_empty() {
+      emit_call_site_error!(
+        "#[requires] is not supported on #[rpc_controller]. Check access in a middleware instead"
+      );
+    }
+    (
+      vec![generator::generate_rpc_registration(&config)],
+      Some(generator::generate_rpc_functions(&impl_block, &config)),
+    )
+  } else {
+    reject_rpc_methods(&impl_block);
+    (
+      generator::generate_route_registrations(&impl_block, &config),
+      None,
+    )
+  };
   let base_router = generator::generate_base_router(&route_registrations);
+  let rpc_impl = rpc_functions.map(|functions| quote::quote! { impl #name { #functions } });
 
   if route_registrations.is_empty() {
     log_info!("Warning: No routes found in controller");
@@ -31,13 +67,52 @@ pub fn controller_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
   }
 
   let router_with_middleware = generator::apply_middlewares(base_router, &config.middlewares);
-  let final_router =
-    generator::apply_route_prefix(router_with_middleware, config.route_prefix.as_ref());
-
-  TokenStream::from(generator::generate_router_impl(
-    &impl_block,
-    name,
-    final_router,
-    &config,
-  ))
+  let router_with_cors = generator::apply_cors(router_with_middleware, &config);
+  let router_with_prefix =
+    generator::apply_route_prefix(router_with_cors, config.route_prefix.as_ref());
+  let final_router = generator::apply_controller_headers(router_with_prefix, &config);
+
+  let router_impl = generator::generate_router_impl(&impl_block, name, final_router, &config);
+  TokenStream::from(quote::quote! {
+    #router_impl
+    #rpc_impl
+  })
+}
+
+/// `#[rpc]` methods are only dispatched by an `#[rpc_controller]`
+fn reject_rpc_methods(impl_block: &ItemImpl) {
+  for item in &impl_block.items {
+    if let ImplItem::Fn(method) = item {
+      if method.attrs.iter().any(|attr| attr.path().is_ident("rpc")) {
+        emit_call_site_error!(
+          "'{}' is marked #[rpc], which needs #[rpc_controller(...)] instead of #[controller(...)]",
+          method.sig.ident
+        );
+      }
+    }
+  }
+}
+
+/// Finds the method marked `#[catch]`, which maps extractor rejections to responses
+fn find_catch_handler(impl_block: &ItemImpl) -> Option<syn::Ident> {
+  let mut handlers = impl_block.items.iter().filter_map(|item| match item {
+    ImplItem::Fn(method)
+      if method
+        .attrs
+        .iter()
+        .any(|attr| attr.path().is_ident("catch")) =>
+    {
+      Some(method.sig.ident.clone())
+    }
+    _ => None,
+  });
+
+  let handler = handlers.next();
+  if handlers.next().is_some() {
+    emit_call_site_error!("Only one method per controller can be marked #[catch]");
+  }
+  if let Some(handler) = &handler {
+    log_verbose!("Using rejection handler: [{}]", handler);
+  }
+  handler
 }
@@ -0,0 +1,1053 @@
+//! Runtime helper functions emitted into controllers whose routes need them
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{ImplItem, ItemImpl};
+
+use crate::parser::{
+  Consumes, ControllerConfig, CorsConfig, ExtractorType, HeaderPolicy, HeaderValueSource,
+  ResponseFormat, RouteInfo, SecurityHeader,
+};
+
+/// Returns true when any route in the controller uses one of the given extractors
+fn uses_extractor(impl_block: &ItemImpl, wanted: &[ExtractorType]) -> bool {
+  impl_block.items.iter().any(|item| match item {
+    ImplItem::Fn(method) => crate::parser::extract_route_from_attrs(&method.attrs)
+      .map(|route_info| {
+        route_info
+          .extractors
+          .values()
+          .any(|ext| wanted.contains(ext))
+      })
+      .unwrap_or(false),
+    _ => false,
+  })
+}
+
+pub fn generate_helper_functions(
+  impl_block: &ItemImpl,
+  controller_config: &ControllerConfig,
+) -> Vec<TokenStream> {
+  let mut helpers = Vec::new();
+
+  // Security headers disabled by some route are skipped when the route marks its response
+  let mut skippable: Vec<&str> = Vec::new();
+  let mut negotiates = false;
+  let mut locates = false;
+  let mut serializes_xml = false;
+  for item in &impl_block.items {
+    let ImplItem::Fn(method) = item else {
+      continue;
+    };
+    let Some(route_info) = crate::parser::extract_route_from_attrs(&method.attrs) else {
+      continue;
+    };
+
+    let (consumes, produces) = media_constraints(&route_info, controller_config);
+    if consumes.is_some() || produces.is_some() {
+      log_verbose!("Adding media type checks for: [{}]", method.sig.ident);
+      helpers.push(negotiation_layer(
+        &method.sig.ident,
+        consumes,
+        produces.as_deref(),
+        crate::parser::has_optional_body(&method.sig, &route_info.extractors),
+      ));
+      negotiates = true;
+    }
+    // respond = ... builds its response with __route_controller_encoded
+    negotiates |= route_info.respond.is_some();
+    serializes_xml |= route_info.respond == Some(ResponseFormat::Xml)
+      || negotiated_formats(&route_info, controller_config).contains(&ResponseFormat::Xml);
+    locates |= route_info.location.is_some();
+
+    if route_info.security_headers.is_empty() {
+      continue;
+    }
+    log_verbose!("Adding security header layer for: [{}]", method.sig.ident);
+    helpers.push(route_security_layer(
+      &method.sig.ident,
+      &route_info.security_headers,
+    ));
+    for header in &route_info.security_headers {
+      if header.value.is_none() && !skippable.contains(&header.name) {
+        skippable.push(header.name);
+      }
+    }
+  }
+
+  if negotiates {
+    helpers.push(negotiation_helpers());
+  }
+
+  if locates {
+    helpers.push(location_helper());
+  }
+
+  if serializes_xml {
+    log_verbose!("Adding XML serialization helper");
+    helpers.push(xml_helper());
+  }
+
+  if has_layered_headers(controller_config) {
+    log_verbose!("Adding controller header layer");
+    helpers.push(header_layer(controller_config, &skippable));
+  }
+
+  if let Some(cors) = &controller_config.cors {
+    // methods = auto allows exactly the methods the controller declares routes for
+    let methods = cors.methods.clone().unwrap_or_else(|| {
+      let mut methods: Vec<String> = Vec::new();
+      for item in &impl_block.items {
+        if let ImplItem::Fn(method) = item {
+          // The JSON-RPC dispatcher answers POST only
+          let name =
+            if let Some(route_info) = crate::parser::extract_route_from_attrs(&method.attrs) {
+              route_info.method.to_ascii_uppercase()
+            } else if method.attrs.iter().any(|attr| attr.path().is_ident("rpc")) {
+              "POST".to_string()
+            } else {
+              continue;
+            };
+          if !methods.contains(&name) {
+            methods.push(name);
+          }
+        }
+      }
+      methods
+    });
+    log_verbose!("Adding CORS layer: [Methods:{}]", methods.join(", "));
+    helpers.push(cors_layer(cors, &methods));
+  }
+
+  if controller_config.content_type.is_some() {
+    log_verbose!("Adding own content type marker");
+    helpers.push(own_content_type_layer());
+  }
+
+  if uses_extractor(
+    impl_block,
+    &[
+      ExtractorType::BearerToken,
+      ExtractorType::BasicAuth,
+      ExtractorType::Jwt,
+    ],
+  ) {
+    log_verbose!("Adding authorization header helpers");
+    helpers.push(authorization_helpers());
+  }
+
+  if uses_extractor(impl_block, &[ExtractorType::BasicAuth]) {
+    log_verbose!("Adding Basic credentials decoder");
+    helpers.push(basic_auth_helper());
+  }
+
+  if uses_extractor(
+    impl_block,
+    &[
+      ExtractorType::XmlBody,
+      ExtractorType::MsgPack,
+      ExtractorType::Cbor,
+      ExtractorType::MergePatch,
+      ExtractorType::JsonPatch,
+      ExtractorType::Csv,
+    ],
+  ) {
+    log_verbose!("Adding body decoding helpers");
+    helpers.push(body_decoding_helpers());
+  }
+
+  if uses_extractor(impl_block, &[ExtractorType::Csv]) {
+    log_verbose!("Adding CSV body helpers");
+    helpers.push(csv_helpers());
+  }
+
+  if uses_extractor(impl_block, &[ExtractorType::MergePatch]) {
+    log_verbose!("Adding merge patch helper");
+    helpers.push(merge_patch_helper());
+  }
+
+  if uses_extractor(impl_block, &[ExtractorType::JsonPatch]) {
+    log_verbose!("Adding JSON patch helper");
+    helpers.push(json_patch_helper());
+  }
+
+  if uses_extractor(impl_block, &[ExtractorType::Jwt]) {
+    log_verbose!("Adding JWT helpers");
+    helpers.push(jwt_helpers());
+  }
+
+  helpers
+}
+
+/// Returns true when the controller has headers or a content type applied by
+/// `__route_controller_headers`
+pub(super) fn has_layered_headers(controller_config: &ControllerConfig) -> bool {
+  !controller_config.response_headers.is_empty() || controller_config.content_type.is_some()
+}
+
+/// Returns the request media types a route accepts and the response media types it offers,
+/// after applying the controller's `consumes` and `produces`. Negotiating routes offer the
+/// media types of their formats unless they set `produces` themselves
+pub(super) fn media_constraints<'a>(
+  route_info: &'a RouteInfo,
+  controller_config: &'a ControllerConfig,
+) -> (Option<&'a [String]>, Option<Vec<&'a str>>) {
+  let consumes = match &route_info.consumes {
+    Consumes::Only(media_types) => Some(media_types.as_slice()),
+    Consumes::Any => None,
+    Consumes::Inherit => controller_config.consumes.as_deref().filter(|_| {
+      route_info
+        .extractors
+        .values()
+        .any(|ext| ext.is_body_extractor())
+    }),
+  };
+  let formats = negotiated_formats(route_info, controller_config);
+  let negotiated = (!formats.is_empty()).then(|| {
+    formats
+      .iter()
+      .flat_map(|format| format.media_types().iter().copied())
+      .collect()
+  });
+  let listed = |media_types: &'a [String]| media_types.iter().map(String::as_str).collect();
+  let produces = route_info
+    .produces
+    .as_deref()
+    .map(listed)
+    .or(negotiated)
+    .or_else(|| controller_config.produces.as_deref().map(listed));
+  (consumes, produces)
+}
+
+/// Returns the formats a route serializes its return value into by `Accept`, empty when it does
+/// not negotiate. A route-level `respond` or `stream`, `#[sse]` and `#[ws]` replace the controller's
+/// formats
+pub(super) fn negotiated_formats<'a>(
+  route_info: &'a RouteInfo,
+  controller_config: &'a ControllerConfig,
+) -> &'a [ResponseFormat] {
+  if route_info.respond.is_some()
+    || route_info.stream.is_some()
+    || route_info.sse.is_some()
+    || route_info.ws.is_some()
+  {
+    return &[];
+  }
+  route_info
+    .negotiate
+    .as_deref()
+    .unwrap_or(&controller_config.negotiate)
+}
+
+/// Inserts `value` under `name` according to the header policy
+fn insert_header(name: &str, value: TokenStream, policy: HeaderPolicy) -> TokenStream {
+  let name = quote! { axum::http::header::HeaderName::from_static(#name) };
+  match policy {
+    HeaderPolicy::Override => quote! { headers.insert(#name, #value); },
+    HeaderPolicy::IfAbsent => quote! { headers.entry(#name).or_insert(#value); },
+  }
+}
+
+/// Response extension listing the security headers a route disabled. `Self` keeps the marker
+/// private to the controller when routers are nested
+fn skipped_headers_type() -> TokenStream {
+  quote! { (::std::marker::PhantomData<fn() -> Self>, &'static [&'static str]) }
+}
+
+/// Response extension marking responses of routes that set their own content type or serialize
+/// into a format, which the controller's `content_type(...)` leaves alone
+fn own_content_type_type() -> TokenStream {
+  quote! { ::std::marker::PhantomData<fn() -> (Self, axum::http::HeaderValue)> }
+}
+
+/// `map_response` function applying the controller's static and expression headers and its
+/// content type to every response of the router, including rejections, 404/405s and middleware
+/// short-circuits. Headers in `skippable` are left out of responses whose route disabled them
+fn header_layer(controller_config: &ControllerConfig, skippable: &[&str]) -> TokenStream {
+  let inserts = controller_config.response_headers.iter().map(|header| {
+    let policy = header.policy.unwrap_or(HeaderPolicy::IfAbsent);
+    let insert = match &header.value {
+      HeaderValueSource::Static(value) => insert_header(
+        &header.name,
+        quote! { axum::http::HeaderValue::from_static(#value) },
+        policy,
+      ),
+      HeaderValueSource::Expr(expr) => {
+        let insert = insert_header(&header.name, quote! { value }, policy);
+        quote! {
+          match <axum::http::HeaderValue as ::std::convert::TryFrom<_>>::try_from(#expr) {
+            Ok(value) => { #insert }
+            Err(_) => {
+              return axum::response::IntoResponse::into_response(
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+              );
+            }
+          }
+        }
+      }
+      // Rejected on controllers, as the layer has no router state
+      HeaderValueSource::With(_) => quote! {},
+    };
+    let name = &header.name;
+    if skippable.contains(&name.as_str()) {
+      quote! { if !skipped.contains(&#name) { #insert } }
+    } else {
+      insert
+    }
+  });
+
+  let skipped = (!skippable.is_empty()).then(|| {
+    let marker = skipped_headers_type();
+    quote! {
+      let skipped: &[&str] = response
+        .extensions()
+        .get::<#marker>()
+        .map_or(&[], |(_, names)| *names);
+    }
+  });
+
+  let content_type = controller_config.content_type.as_ref().map(|content_type| {
+    let marker = own_content_type_type();
+    let insert = insert_header(
+      "content-type",
+      quote! { axum::http::HeaderValue::from_static(#content_type) },
+      controller_config.content_type_policy,
+    );
+    quote! {
+      if response.extensions().get::<#marker>().is_none() {
+        let headers = response.headers_mut();
+        #insert
+      }
+    }
+  });
+
+  quote! {
+    #[doc(hidden)]
+    async fn __route_controller_headers(
+      mut response: axum::response::Response,
+    ) -> axum::response::Response {
+      #content_type
+      #skipped
+      let headers = response.headers_mut();
+      #(#inserts)*
+      response
+    }
+  }
+}
+
+/// `map_response` function layered onto a route with its own `security_headers(...)`: sets the
+/// replaced values and marks the disabled ones for `__route_controller_headers`
+fn route_security_layer(handler: &syn::Ident, entries: &[SecurityHeader]) -> TokenStream {
+  let fn_name = quote::format_ident!("__route_controller_security_headers_{}", handler);
+  let inserts = entries.iter().filter_map(|entry| {
+    let value = entry.value.as_ref()?;
+    Some(insert_header(
+      entry.name,
+      quote! { axum::http::HeaderValue::from_static(#value) },
+      HeaderPolicy::IfAbsent,
+    ))
+  });
+  let disabled: Vec<_> = entries
+    .iter()
+    .filter(|entry| entry.value.is_none())
+    .map(|entry| entry.name)
+    .collect();
+  let mark = (!disabled.is_empty()).then(|| {
+    let marker = skipped_headers_type();
+    quote! {
+      response
+        .extensions_mut()
+        .insert::<#marker>((::std::marker::PhantomData, &[#(#disabled),*]));
+    }
+  });
+
+  quote! {
+    #[doc(hidden)]
+    async fn #fn_name(mut response: axum::response::Response) -> axum::response::Response {
+      let headers = response.headers_mut();
+      #(#inserts)*
+      #mark
+      response
+    }
+  }
+}
+
+/// `map_response` function route-layered onto routes with their own content type, marking their
+/// responses for `__route_controller_headers`. Their 405 answers stay unmarked
+fn own_content_type_layer() -> TokenStream {
+  let marker = own_content_type_type();
+  quote! {
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    async fn __route_controller_own_content_type(
+      mut response: axum::response::Response,
+    ) -> axum::response::Response {
+      response
+        .extensions_mut()
+        .insert::<#marker>(::std::marker::PhantomData);
+      response
+    }
+  }
+}
+
+/// `from_fn` middleware answering CORS preflights and adding the CORS response headers for
+/// allowed origins. Layered outside the controller middleware, so preflights never reach it
+fn cors_layer(cors: &CorsConfig, methods: &[String]) -> TokenStream {
+  let allowed = match &cors.origins {
+    Some(origins) => quote! { [#(#origins),*].iter().any(|allowed| origin == *allowed) },
+    None => quote! { true },
+  };
+  // A listed origin is echoed back, so caches must key responses on it
+  let allow_origin = match &cors.origins {
+    Some(_) => quote! {
+      headers.insert(axum::http::header::ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+    },
+    None => quote! {
+      headers.insert(
+        axum::http::header::ACCESS_CONTROL_ALLOW_ORIGIN,
+        axum::http::HeaderValue::from_static("*"),
+      );
+    },
+  };
+  let vary = cors.origins.is_some().then(|| {
+    quote! {
+      headers.append(axum::http::header::VARY, axum::http::HeaderValue::from_static("origin"));
+    }
+  });
+  let credentials = cors.credentials.then(|| {
+    quote! {
+      headers.insert(
+        axum::http::header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+        axum::http::HeaderValue::from_static("true"),
+      );
+    }
+  });
+  let methods = methods.join(", ");
+  let allow_headers = match &cors.headers {
+    Some(names) if names.is_empty() => quote! {},
+    Some(names) => {
+      let names = names.join(", ");
+      quote! {
+        headers.insert(
+          axum::http::header::ACCESS_CONTROL_ALLOW_HEADERS,
+          axum::http::HeaderValue::from_static(#names),
+        );
+      }
+    }
+    None => quote! {
+      let requested = request
+        .headers()
+        .get(axum::http::header::ACCESS_CONTROL_REQUEST_HEADERS);
+      if let Some(requested) = requested {
+        headers.insert(axum::http::header::ACCESS_CONTROL_ALLOW_HEADERS, requested.clone());
+      }
+    },
+  };
+  let max_age = cors.max_age.map(|seconds| {
+    let seconds = seconds.to_string();
+    quote! {
+      headers.insert(
+        axum::http::header::ACCESS_CONTROL_MAX_AGE,
+        axum::http::HeaderValue::from_static(#seconds),
+      );
+    }
+  });
+  let expose = (!cors.expose.is_empty()).then(|| {
+    let names = cors.expose.join(", ");
+    quote! {
+      headers.insert(
+        axum::http::header::ACCESS_CONTROL_EXPOSE_HEADERS,
+        axum::http::HeaderValue::from_static(#names),
+      );
+    }
+  });
+
+  quote! {
+    #[doc(hidden)]
+    async fn __route_controller_cors(
+      request: axum::extract::Request,
+      next: axum::middleware::Next,
+    ) -> axum::response::Response {
+      let Some(origin) = request.headers().get(axum::http::header::ORIGIN).cloned() else {
+        return next.run(request).await;
+      };
+      let allowed = #allowed;
+
+      if request.method() == axum::http::Method::OPTIONS
+        && request
+          .headers()
+          .contains_key(axum::http::header::ACCESS_CONTROL_REQUEST_METHOD)
+      {
+        if !allowed {
+          return axum::response::IntoResponse::into_response(axum::http::StatusCode::FORBIDDEN);
+        }
+        let mut response =
+          axum::response::IntoResponse::into_response(axum::http::StatusCode::NO_CONTENT);
+        let headers = response.headers_mut();
+        #allow_origin
+        #vary
+        #credentials
+        headers.insert(
+          axum::http::header::ACCESS_CONTROL_ALLOW_METHODS,
+          axum::http::HeaderValue::from_static(#methods),
+        );
+        #allow_headers
+        #max_age
+        return response;
+      }
+
+      let mut response = next.run(request).await;
+      let headers = response.headers_mut();
+      #vary
+      if allowed {
+        #allow_origin
+        #credentials
+        #expose
+      }
+      response
+    }
+  }
+}
+
+/// `from_fn` middleware layered onto a route with media type constraints. Answers
+/// `415 Unsupported Media Type` and `406 Not Acceptable` before the body is extracted. Routes
+/// with an `Option<T>` body also take requests without a `Content-Type`
+fn negotiation_layer(
+  handler: &syn::Ident,
+  consumes: Option<&[String]>,
+  produces: Option<&[&str]>,
+  optional_body: bool,
+) -> TokenStream {
+  let fn_name = quote::format_ident!("__route_controller_negotiate_{}", handler);
+  let consumes = consumes.map(|media_types| {
+    let unchecked = optional_body.then(|| {
+      quote! { !request.headers().contains_key(axum::http::header::CONTENT_TYPE) || }
+    });
+    quote! {
+      if !(#unchecked Self::__route_controller_consumes(request.headers(), &[#(#media_types),*])) {
+        return axum::response::IntoResponse::into_response(
+          axum::http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        );
+      }
+    }
+  });
+  let produces = produces.map(|media_types| {
+    quote! {
+      if !Self::__route_controller_produces(request.headers(), &[#(#media_types),*]) {
+        return axum::response::IntoResponse::into_response(
+          axum::http::StatusCode::NOT_ACCEPTABLE,
+        );
+      }
+    }
+  });
+
+  quote! {
+    #[doc(hidden)]
+    async fn #fn_name(
+      request: axum::extract::Request,
+      next: axum::middleware::Next,
+    ) -> axum::response::Response {
+      #consumes
+      #produces
+      next.run(request).await
+    }
+  }
+}
+
+/// Media type matching for `consumes`, `produces` and `negotiate`. Emitted as a group, so a controller may
+/// leave some of them unused.
+fn negotiation_helpers() -> TokenStream {
+  quote! {
+    /// Returns how specifically `range` (`*/*`, `type/*` or `type/subtype`) matches `media`.
+    /// Parameters such as `; charset=utf-8` are ignored on both sides
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn __route_controller_media_match(range: &str, media: &str) -> Option<u8> {
+      fn essence(value: &str) -> &str {
+        value.split(';').next().unwrap_or_default().trim()
+      }
+      let (range_type, range_subtype) = essence(range).split_once('/')?;
+      let (media_type, media_subtype) = essence(media).split_once('/')?;
+      if range_type == "*" && range_subtype == "*" {
+        return Some(0);
+      }
+      if !range_type.eq_ignore_ascii_case(media_type) {
+        return None;
+      }
+      if range_subtype == "*" {
+        return Some(1);
+      }
+      range_subtype.eq_ignore_ascii_case(media_subtype).then_some(2)
+    }
+
+    /// True when the request's `Content-Type` is one of `accepted`
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn __route_controller_consumes(headers: &axum::http::HeaderMap, accepted: &[&str]) -> bool {
+      let Some(content_type) = headers
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+      else {
+        return false;
+      };
+      let essence = content_type.split(';').next().unwrap_or_default();
+      accepted
+        .iter()
+        .any(|range| Self::__route_controller_media_match(range, essence).is_some())
+    }
+
+    /// True when `Accept` allows one of `offered`
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn __route_controller_produces(headers: &axum::http::HeaderMap, offered: &[&str]) -> bool {
+      Self::__route_controller_preferred(headers, offered).is_some()
+    }
+
+    /// Returns the index of the entry of `offered` that `Accept` prefers
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn __route_controller_preferred(
+      headers: &axum::http::HeaderMap,
+      offered: &[&str],
+    ) -> Option<usize> {
+      Self::__route_controller_ranked(headers, offered).first().copied()
+    }
+
+    /// Returns the indices of the entries of `offered` that `Accept` allows, most preferred
+    /// first. Each is weighed by the quality of its most specific matching range so `q=0` can
+    /// exclude a type. Ties keep the order of `offered`, and a missing header allows them all
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn __route_controller_ranked(headers: &axum::http::HeaderMap, offered: &[&str]) -> Vec<usize> {
+      let ranges: Vec<(&str, f32)> = headers
+        .get_all(axum::http::header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter(|range| !range.trim().is_empty())
+        .map(|range| {
+          let mut params = range.split(';');
+          let media = params.next().unwrap_or_default();
+          let quality = params
+            .find_map(|param| param.trim().strip_prefix("q="))
+            .and_then(|q| q.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+          (media, quality)
+        })
+        .collect();
+      if ranges.is_empty() {
+        return (0..offered.len()).collect();
+      }
+      let mut acceptable: Vec<(usize, f32)> = offered
+        .iter()
+        .enumerate()
+        .map(|(index, offered)| {
+          let quality = ranges
+            .iter()
+            .filter_map(|(range, quality)| {
+              Self::__route_controller_media_match(range, offered).map(|rank| (rank, *quality))
+            })
+            .max_by_key(|(rank, _)| *rank)
+            .map_or(0.0, |(_, quality)| quality);
+          (index, quality)
+        })
+        .filter(|(_, quality)| *quality > 0.0)
+        .collect();
+      // A stable sort, so ties keep their order
+      acceptable.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+      acceptable.into_iter().map(|(index, _)| index).collect()
+    }
+
+    /// Builds the response of a serialized format, or `None` when serialization failed
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn __route_controller_encoded<B, E>(
+      media_type: &'static str,
+      body: ::std::result::Result<B, E>,
+    ) -> Option<axum::response::Response>
+    where
+      B: ::std::convert::Into<axum::body::Body>,
+    {
+      let body = body.ok()?;
+      Some(axum::response::IntoResponse::into_response((
+        [(axum::http::header::CONTENT_TYPE, media_type)],
+        ::std::convert::Into::<axum::body::Body>::into(body),
+      )))
+    }
+
+    /// Answers 500 when the return value could not be serialized as `media_type`
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn __route_controller_unserializable(media_type: &str) -> axum::response::Response {
+      axum::response::IntoResponse::into_response((
+        axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+        format!("failed to serialize response as {}", media_type),
+      ))
+    }
+  }
+}
+
+/// Serializes XML responses. A top-level sequence has no element name of its own, so it is
+/// wrapped in a `root` element holding one `item` element per entry
+fn xml_helper() -> TokenStream {
+  quote! {
+    #[doc(hidden)]
+    fn __route_controller_xml<T: serde::Serialize + ?Sized>(
+      value: &T,
+      root: &'static str,
+    ) -> ::std::result::Result<String, quick_xml::SeError> {
+      struct Items<'a, T: ?Sized>(&'a T);
+
+      impl<T: serde::Serialize + ?Sized> serde::Serialize for Items<'_, T> {
+        fn serialize<S: serde::Serializer>(
+          &self,
+          serializer: S,
+        ) -> ::std::result::Result<S::Ok, S::Error> {
+          use serde::ser::SerializeStruct;
+          let mut items = serializer.serialize_struct("items", 1)?;
+          items.serialize_field("item", self.0)?;
+          items.end()
+        }
+      }
+
+      // quick-xml only serializes sequences and maps inside an element
+      quick_xml::se::to_string(value)
+        .or_else(|_| quick_xml::se::to_string_with_root(root, &Items(value)))
+    }
+  }
+}
+
+/// Error response for typed bodies (`XmlBody`, `MsgPack`, `Cbor`, `MergePatch`, `JsonPatch`,
+/// `Csv`) that fail to deserialize. `Csv` reports through `__route_controller_invalid_csv`, so a
+/// controller may leave `__route_controller_invalid_body` unused
+fn body_decoding_helpers() -> TokenStream {
+  quote! {
+    /// Escapes text for a JSON string literal
+    #[doc(hidden)]
+    fn __route_controller_json_escape(text: &str) -> String {
+      let mut escaped = String::with_capacity(text.len());
+      for ch in text.chars() {
+        match ch {
+          '"' => escaped.push_str("\\\""),
+          '\\' => escaped.push_str("\\\\"),
+          ch if ch.is_control() => escaped.push_str(&format!("\\u{:04x}", ch as u32)),
+          ch => escaped.push(ch),
+        }
+      }
+      escaped
+    }
+
+    /// Answers `400 Bad Request` with a JSON body naming the format and the parser's message
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn __route_controller_invalid_body(
+      format: &'static str,
+      error: &dyn ::std::fmt::Display,
+    ) -> axum::response::Response {
+      axum::response::IntoResponse::into_response((
+        axum::http::StatusCode::BAD_REQUEST,
+        [(axum::http::header::CONTENT_TYPE, "application/json")],
+        format!(
+          "{{\"error\":\"invalid_body\",\"format\":\"{}\",\"message\":\"{}\"}}",
+          format,
+          Self::__route_controller_json_escape(&error.to_string())
+        ),
+      ))
+    }
+  }
+}
+
+/// Reads the rows of a `Csv` body, answering `400 Bad Request` with the row and column of the
+/// first record that fails to deserialize unless the controller has a `#[catch]` method
+fn csv_helpers() -> TokenStream {
+  quote! {
+    /// Deserializes every record after the header row. Errors carry the header of the failing
+    /// column when the failure is tied to one
+    #[doc(hidden)]
+    fn __route_controller_csv_rows<T: serde::de::DeserializeOwned>(
+      body: &[u8],
+    ) -> ::std::result::Result<::std::vec::Vec<T>, (csv::Error, ::std::option::Option<String>)> {
+      let mut reader = csv::Reader::from_reader(body);
+      let headers = reader.headers().cloned().map_err(|error| (error, None))?;
+      reader
+        .deserialize()
+        .collect::<::std::result::Result<::std::vec::Vec<T>, csv::Error>>()
+        .map_err(|error| {
+          let column = match error.kind() {
+            csv::ErrorKind::Deserialize { err, .. } => err
+              .field()
+              .and_then(|index| headers.get(index as usize))
+              .map(::std::string::ToString::to_string),
+            _ => None,
+          };
+          (error, column)
+        })
+    }
+
+    /// `row` counts records after the header row from 1 and `line` counts lines of the body;
+    /// both are null when the error has no position, and `column` when it has no field
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn __route_controller_invalid_csv(
+      error: &csv::Error,
+      column: ::std::option::Option<&str>,
+    ) -> axum::response::Response {
+      let (row, line) = match error.position() {
+        Some(position) => (position.record().to_string(), position.line().to_string()),
+        None => ("null".to_string(), "null".to_string()),
+      };
+      let column = match column {
+        Some(column) => format!("\"{}\"", Self::__route_controller_json_escape(column)),
+        None => "null".to_string(),
+      };
+      axum::response::IntoResponse::into_response((
+        axum::http::StatusCode::BAD_REQUEST,
+        [(axum::http::header::CONTENT_TYPE, "application/json")],
+        format!(
+          "{{\"error\":\"invalid_body\",\"format\":\"csv\",\"message\":\"{}\",\"row\":{},\"line\":{},\"column\":{}}}",
+          Self::__route_controller_json_escape(&error.to_string()),
+          row,
+          line,
+          column
+        ),
+      ))
+    }
+  }
+}
+
+/// `apply_merge_patch`, applying an RFC 7386 merge patch received by a `MergePatch` extractor.
+/// Public, so code outside the controller can apply the patch documents it is handed
+fn merge_patch_helper() -> TokenStream {
+  quote! {
+    /// Applies an RFC 7386 JSON merge patch to `target` and returns the patched value. Fails
+    /// with `422 Unprocessable Entity` when the result no longer deserializes into `T`
+    #[allow(dead_code)]
+    pub fn apply_merge_patch<T>(
+      target: &T,
+      patch: &serde_json::Value,
+    ) -> ::std::result::Result<T, (axum::http::StatusCode, ::std::string::String)>
+    where
+      T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+      fn merge(target: &mut serde_json::Value, patch: &serde_json::Value) {
+        let serde_json::Value::Object(patch) = patch else {
+          *target = patch.clone();
+          return;
+        };
+        if !target.is_object() {
+          *target = serde_json::Value::Object(serde_json::Map::new());
+        }
+        if let serde_json::Value::Object(target) = target {
+          for (key, value) in patch {
+            if value.is_null() {
+              target.remove(key);
+            } else {
+              merge(target.entry(key.clone()).or_insert(serde_json::Value::Null), value);
+            }
+          }
+        }
+      }
+
+      let mut document = serde_json::to_value(target)
+        .map_err(|error| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, error.to_string()))?;
+      merge(&mut document, patch);
+      serde_json::from_value(document)
+        .map_err(|error| (axum::http::StatusCode::UNPROCESSABLE_ENTITY, error.to_string()))
+    }
+  }
+}
+
+/// `apply_json_patch`, applying the RFC 6902 operations received by a `JsonPatch` extractor
+fn json_patch_helper() -> TokenStream {
+  quote! {
+    /// Applies RFC 6902 JSON patch operations to `target` and returns the patched value. The
+    /// operations apply atomically: a failed `test` answers `409 Conflict`, any other failing
+    /// operation or a result that no longer deserializes into `T` `422 Unprocessable Entity`
+    #[allow(dead_code)]
+    pub fn apply_json_patch<T>(
+      target: &T,
+      patch: &json_patch::Patch,
+    ) -> ::std::result::Result<T, (axum::http::StatusCode, ::std::string::String)>
+    where
+      T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+      let mut document = serde_json::to_value(target)
+        .map_err(|error| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, error.to_string()))?;
+      json_patch::patch(&mut document, patch).map_err(|error| {
+        let status = match error.kind {
+          json_patch::PatchErrorKind::TestFailed => axum::http::StatusCode::CONFLICT,
+          _ => axum::http::StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        (status, error.to_string())
+      })?;
+      serde_json::from_value(document)
+        .map_err(|error| (axum::http::StatusCode::UNPROCESSABLE_ENTITY, error.to_string()))
+    }
+  }
+}
+
+/// Percent-encodes a value interpolated into a `location` template as a path segment, so ids
+/// with `/`, `?`, `#` or non-ASCII characters yield the intended, valid header value
+fn location_helper() -> TokenStream {
+  quote! {
+    #[doc(hidden)]
+    fn __route_controller_path_segment(value: impl ::std::fmt::Display) -> String {
+      let value = value.to_string();
+      let mut encoded = String::with_capacity(value.len());
+      for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+          encoded.push(byte as char);
+        } else {
+          encoded.push_str(&format!("%{:02X}", byte));
+        }
+      }
+      encoded
+    }
+  }
+}
+
+/// `Authorization` header parsing shared by `BearerToken` (RFC 6750), `BasicAuth` (RFC 7617) and
+/// `Jwt`. Each returns `Ok(None)` when the header is absent and `Err(())` when it is malformed.
+/// Emitted as a group, so a controller may leave some of them unused.
+fn authorization_helpers() -> TokenStream {
+  quote! {
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn __route_controller_unauthorized(challenge: &'static str) -> axum::response::Response {
+      axum::response::IntoResponse::into_response((
+        axum::http::StatusCode::UNAUTHORIZED,
+        [(axum::http::header::WWW_AUTHENTICATE, challenge)],
+      ))
+    }
+
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn __route_controller_authorization<'a>(
+      headers: &'a axum::http::HeaderMap,
+      expected_scheme: &str,
+    ) -> ::std::result::Result<::std::option::Option<&'a str>, ()> {
+      let Some(value) = headers.get(axum::http::header::AUTHORIZATION) else {
+        return Ok(None);
+      };
+      let value = value.to_str().map_err(|_| ())?.trim();
+      let (scheme, credentials) = value.split_once(' ').ok_or(())?;
+      if !scheme.eq_ignore_ascii_case(expected_scheme) {
+        return Err(());
+      }
+      let credentials = credentials.trim();
+      // token68 = 1*( ALPHA / DIGIT / "-" / "." / "_" / "~" / "+" / "/" ) *"="
+      let body = credentials.trim_end_matches('=');
+      let is_token68 = !body.is_empty()
+        && body
+          .bytes()
+          .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~' | b'+' | b'/'));
+      if !is_token68 {
+        return Err(());
+      }
+      Ok(Some(credentials))
+    }
+
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn __route_controller_bearer_token(
+      headers: &axum::http::HeaderMap,
+    ) -> ::std::result::Result<::std::option::Option<String>, ()> {
+      Ok(Self::__route_controller_authorization(headers, "Bearer")?.map(str::to_string))
+    }
+  }
+}
+
+/// Decodes `Basic` credentials. A proc-macro crate cannot export runtime functions, so the
+/// decoder is emitted into each controller with a `BasicAuth` parameter instead of pulling a
+/// base64 crate into every application
+fn basic_auth_helper() -> TokenStream {
+  quote! {
+    /// Splits standard, padded base64 `user-id:password` credentials. Padding is only accepted
+    /// at the end, and unpadded or invalid input is rejected
+    #[doc(hidden)]
+    fn __route_controller_basic_auth(
+      headers: &axum::http::HeaderMap,
+    ) -> ::std::result::Result<::std::option::Option<(String, String)>, ()> {
+      let Some(encoded) = Self::__route_controller_authorization(headers, "Basic")? else {
+        return Ok(None);
+      };
+      let encoded = encoded.as_bytes();
+      if encoded.len() % 4 != 0 {
+        return Err(());
+      }
+      let mut decoded = Vec::with_capacity(encoded.len() / 4 * 3);
+      let chunk_count = encoded.len() / 4;
+      for (index, chunk) in encoded.chunks(4).enumerate() {
+        let mut bits: u32 = 0;
+        let mut padding = 0;
+        for (position, &byte) in chunk.iter().enumerate() {
+          let sextet = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' if position >= 2 && index + 1 == chunk_count => {
+              padding += 1;
+              0
+            }
+            _ => return Err(()),
+          };
+          if padding > 0 && byte != b'=' {
+            return Err(());
+          }
+          bits = (bits << 6) | u32::from(sextet);
+        }
+        decoded.extend_from_slice(&bits.to_be_bytes()[1..4 - padding]);
+      }
+      let decoded = String::from_utf8(decoded).map_err(|_| ())?;
+      let (user_id, password) = decoded.split_once(':').ok_or(())?;
+      Ok(Some((user_id.to_string(), password.to_string())))
+    }
+  }
+}
+
+/// Structured `401 Unauthorized` answers for rejected JSON Web Tokens
+fn jwt_helpers() -> TokenStream {
+  quote! {
+    #[doc(hidden)]
+    fn __route_controller_jwt_unauthorized(
+      error: ::std::option::Option<&'static str>,
+      description: &'static str,
+    ) -> axum::response::Response {
+      let challenge = match error {
+        Some(code) => format!("Bearer error=\"{}\", error_description=\"{}\"", code, description),
+        None => "Bearer".to_string(),
+      };
+      let body = format!(
+        "{{\"error\":\"{}\",\"error_description\":\"{}\"}}",
+        error.unwrap_or("unauthorized"),
+        description
+      );
+      axum::response::IntoResponse::into_response((
+        axum::http::StatusCode::UNAUTHORIZED,
+        [
+          (axum::http::header::WWW_AUTHENTICATE, challenge),
+          (axum::http::header::CONTENT_TYPE, "application/json".to_string()),
+        ],
+        body,
+      ))
+    }
+
+    #[doc(hidden)]
+    fn __route_controller_jwt_error(error: &jsonwebtoken::errors::Error) -> axum::response::Response {
+      use jsonwebtoken::errors::ErrorKind;
+      let description = match error.kind() {
+        ErrorKind::ExpiredSignature => "Token has expired",
+        ErrorKind::ImmatureSignature => "Token is not valid yet",
+        ErrorKind::InvalidAudience => "Token audience is not accepted",
+        ErrorKind::InvalidIssuer => "Token issuer is not accepted",
+        ErrorKind::InvalidSignature => "Token signature is invalid",
+        ErrorKind::InvalidAlgorithm => "Token algorithm is not accepted",
+        ErrorKind::MissingRequiredClaim(_) => "Token is missing a required claim",
+        _ => "Token is malformed",
+      };
+      Self::__route_controller_jwt_unauthorized(Some("invalid_token"), description)
+    }
+  }
+}
@@ -4,6 +4,8 @@ use proc_macro2::TokenStream;
 use quote::quote;
 use syn::Path;
 
+use crate::parser::ControllerConfig;
+
 pub fn apply_middlewares(base_router: TokenStream, middlewares: &[Path]) -> TokenStream {
   if middlewares.is_empty() {
     log_verbose!("No middlewares to apply");
@@ -44,3 +46,27 @@ pub fn apply_route_prefix(router: TokenStream, prefix: Option<&String>) -> Token
     router
   }
 }
+
+/// Applies the controller-level headers as the outermost layer, so they also reach rejections,
+/// 404/405 responses and middleware short-circuits
+pub fn apply_controller_headers(router: TokenStream, config: &ControllerConfig) -> TokenStream {
+  if !super::helpers::has_layered_headers(config) {
+    return router;
+  }
+  log_verbose!("Adding controller header layer");
+  quote! {
+    #router.layer(axum::middleware::map_response(Self::__route_controller_headers))
+  }
+}
+
+/// Applies CORS outside the controller middleware, so preflight requests are answered before
+/// any of it runs
+pub fn apply_cors(router: TokenStream, config: &ControllerConfig) -> TokenStream {
+  if config.cors.is_none() {
+    return router;
+  }
+  log_verbose!("Adding CORS layer");
+  quote! {
+    #router.layer(axum::middleware::from_fn(Self::__route_controller_cors))
+  }
+}
@@ -1,9 +1,12 @@
 //! Generating Axum router code from controllers
 
+mod helpers;
 mod middleware;
 mod router;
+mod rpc;
 mod wrappers;
 
 // Re-export public functions
-pub use middleware::{apply_middlewares, apply_route_prefix};
+pub use middleware::{apply_controller_headers, apply_cors, apply_middlewares, apply_route_prefix};
 pub use router::{generate_base_router, generate_route_registrations, generate_router_impl};
+pub use rpc::{generate_rpc_functions, generate_rpc_registration};
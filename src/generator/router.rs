@@ -1,5 +1,6 @@
 //! Router generation from parsed controller information
 
+use proc_macro_error::emit_call_site_warning;
 use proc_macro2::TokenStream;
 use quote::quote;
 use syn::{ImplItem, ItemImpl, Type};
@@ -23,16 +24,63 @@ pub fn generate_route_registrations(
 
         // Analyze parameters with explicit extractors
         let params = crate::parser::analyze_params(&method.sig, &route_info.extractors);
-        let needs_wrapper = params
-          .iter()
-          .any(|p| p.extractor_type != crate::parser::ExtractorType::None);
 
-        let has_response_headers = !route_info.response_headers.is_empty()
-          || route_info.content_type.is_some()
-          || !controller_config.response_headers.is_empty()
-          || controller_config.content_type.is_some();
+        // The controller content type is applied around the router, except to routes that set
+        // their own or serialize into a format
+        let own_content_type = route_info.content_type.is_some()
+          || route_info.respond.is_some()
+          || route_info.stream.is_some()
+          || route_info.sse.is_some()
+          || route_info.ws.is_some()
+          || !super::helpers::negotiated_formats(&route_info, controller_config).is_empty();
+        let content_type_layer = (controller_config.content_type.is_some() && own_content_type)
+          .then(|| {
+            quote! { .route_layer(axum::middleware::map_response(Self::__route_controller_own_content_type)) }
+          });
 
-        if needs_wrapper || has_response_headers {
+        // DefaultBodyLimit on the method router caps the body extractors of this route only
+        let body_limit = route_info.body_limit.or(controller_config.body_limit);
+        if body_limit.is_none()
+          && route_info
+            .extractors
+            .values()
+            .any(|ext| *ext == crate::parser::ExtractorType::BodyStream)
+        {
+          emit_call_site_warning!(
+            "BodyStream on '{}' has no body_limit, so the stream errors after axum's 2 MB default. \
+             Set body_limit = \"10GB\" on the route or controller for larger uploads",
+            handler_name
+          );
+        }
+        let body_limit_layer =
+          body_limit.map(|limit| quote! { .layer(axum::extract::DefaultBodyLimit::max(#limit)) });
+
+        // consumes/produces are checked before extraction, leaving 405 answers untouched
+        let (consumes, produces) =
+          super::helpers::media_constraints(&route_info, controller_config);
+        let negotiation_layer = (consumes.is_some() || produces.is_some()).then(|| {
+          let layer_name = quote::format_ident!("__route_controller_negotiate_{}", handler_name);
+          quote! { .route_layer(axum::middleware::from_fn(Self::#layer_name)) }
+        });
+
+        // Route-level security_headers(...) adjust the controller preset in a layer of their own
+        let security_layer = (!route_info.security_headers.is_empty()).then(|| {
+          if !controller_config.security_headers {
+            emit_call_site_warning!(
+              "security_headers(...) on route '{}' adjusts the controller's preset, but the controller sets no security_headers",
+              route_path
+            );
+          }
+          let layer_name = quote::format_ident!("__route_controller_security_headers_{}", handler_name);
+          quote! { .layer(axum::middleware::map_response(Self::#layer_name)) }
+        });
+
+        if super::wrappers::route_needs_wrapper(
+          &method.sig,
+          &route_info,
+          &params,
+          controller_config,
+        ) {
           // Generate a wrapper function that handles extraction
           let wrapper_name = syn::Ident::new(
             &format!("{}_wrapper", handler_name),
@@ -40,7 +88,7 @@ pub fn generate_route_registrations(
           );
 
           route_registrations.push(quote! {
-              .route(#route_path, axum::routing::#handler_ident(Self::#wrapper_name))
+              .route(#route_path, axum::routing::#handler_ident(Self::#wrapper_name)#security_layer #body_limit_layer #negotiation_layer #content_type_layer)
           });
 
           log_verbose!(
@@ -51,7 +99,7 @@ pub fn generate_route_registrations(
           );
         } else {
           route_registrations.push(quote! {
-              .route(#route_path, axum::routing::#handler_ident(Self::#handler_name))
+              .route(#route_path, axum::routing::#handler_ident(Self::#handler_name)#security_layer #body_limit_layer #negotiation_layer #content_type_layer)
           });
 
           log_verbose!(
@@ -68,6 +116,33 @@ pub fn generate_route_registrations(
   route_registrations
 }
 
+/// Returns the router state type: the controller's `state = ...` attribute, otherwise the type
+/// of the first `State` extractor in the controller's routes or `#[rpc]` methods
+pub(super) fn find_state_type<'a>(
+  impl_block: &'a ItemImpl,
+  controller_config: &'a ControllerConfig,
+) -> Option<&'a Type> {
+  if let Some(state_ty) = &controller_config.state {
+    return Some(state_ty);
+  }
+  impl_block
+    .items
+    .iter()
+    .find_map(|item| {
+      if let syn::ImplItem::Fn(method) = item {
+        if let Some(route_info) = crate::parser::extract_route_from_attrs(&method.attrs) {
+          let params = crate::parser::analyze_params(&method.sig, &route_info.extractors);
+          return params
+            .iter()
+            .find(|p| p.extractor_type == crate::parser::ExtractorType::State)
+            .map(|p| p.ty);
+        }
+      }
+      None
+    })
+    .or_else(|| super::rpc::find_rpc_state_type(impl_block))
+}
+
 pub fn generate_base_router(route_registrations: &[TokenStream]) -> TokenStream {
   quote! {
       axum::Router::new()
@@ -86,24 +161,18 @@ pub fn generate_router_impl(
     super::wrappers::generate_wrapper_functions(impl_block, controller_config);
 
   // Check if any handler uses State extractor and get the state type
-  let state_type: Option<&Type> = impl_block.items.iter().find_map(|item| {
-    if let syn::ImplItem::Fn(method) = item {
-      if let Some(route_info) = crate::parser::extract_route_from_attrs(&method.attrs) {
-        let params = crate::parser::analyze_params(&method.sig, &route_info.extractors);
-        return params
-          .iter()
-          .find(|p| p.extractor_type == crate::parser::ExtractorType::State)
-          .map(|p| p.ty);
-      }
-    }
-    None
-  });
+  let state_type = find_state_type(impl_block, controller_config);
+
+  let requirements_metadata = generate_requirements_metadata(impl_block, controller_config);
+  let helper_functions = super::helpers::generate_helper_functions(impl_block, controller_config);
 
   if let Some(state_ty) = state_type {
     quote! {
         #impl_block
         impl #name {
             #(#wrapper_functions)*
+            #(#helper_functions)*
+            #requirements_metadata
 
             pub fn router() -> axum::Router<#state_ty> {
                 #final_router
@@ -115,6 +184,8 @@ pub fn generate_router_impl(
         #impl_block
         impl #name {
             #(#wrapper_functions)*
+            #(#helper_functions)*
+            #requirements_metadata
 
             pub fn router() -> axum::Router {
                 #final_router
@@ -123,3 +194,56 @@ pub fn generate_router_impl(
     }
   }
 }
+
+/// Generates `ROUTE_REQUIREMENTS`, listing `(method, path, roles, permissions)` for every
+/// protected route so access rules can be audited. Emitted only when `#[requires]` is used.
+fn generate_requirements_metadata(
+  impl_block: &ItemImpl,
+  controller_config: &ControllerConfig,
+) -> TokenStream {
+  let prefix = controller_config
+    .route_prefix
+    .as_deref()
+    .unwrap_or("")
+    .trim_end_matches('/');
+
+  let entries: Vec<_> = impl_block
+    .items
+    .iter()
+    .filter_map(|item| match item {
+      ImplItem::Fn(method) => crate::parser::extract_route_from_attrs(&method.attrs),
+      _ => None,
+    })
+    .filter_map(|route_info| {
+      let requirements = controller_config
+        .requirements
+        .merged_with(&route_info.requirements);
+      if requirements.is_empty() {
+        return None;
+      }
+      let method = route_info.method.to_uppercase();
+      let path = if route_info.path == "/" && !prefix.is_empty() {
+        prefix.to_string()
+      } else {
+        format!("{}{}", prefix, route_info.path)
+      };
+      let roles = &requirements.roles;
+      let permissions = &requirements.permissions;
+      Some(quote! { (#method, #path, &[#(#roles),*], &[#(#permissions),*]) })
+    })
+    .collect();
+
+  if entries.is_empty() {
+    return quote! {};
+  }
+
+  quote! {
+    /// Protected routes as `(method, path, roles, permissions)`
+    pub const ROUTE_REQUIREMENTS: &'static [(
+      &'static str,
+      &'static str,
+      &'static [&'static str],
+      &'static [&'static str],
+    )] = &[#(#entries),*];
+  }
+}
@@ -0,0 +1,414 @@
+//! JSON-RPC 2.0 dispatcher generation for `#[rpc_controller]`
+
+use proc_macro_error::emit_call_site_error;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use std::collections::HashSet;
+use syn::{FnArg, ImplItem, ItemImpl, Pat, Type};
+
+use crate::parser::{ControllerConfig, ExtractorType, RpcMethod};
+
+/// Returns the `#[rpc]` methods of the controller with their handlers
+fn rpc_methods(impl_block: &ItemImpl) -> Vec<(&syn::ImplItemFn, RpcMethod)> {
+  impl_block
+    .items
+    .iter()
+    .filter_map(|item| match item {
+      ImplItem::Fn(method) => {
+        crate::parser::extract_rpc_from_attrs(&method.attrs, &method.sig.ident.to_string())
+          .map(|rpc| (method, rpc))
+      }
+      _ => None,
+    })
+    .collect()
+}
+
+/// Returns the type of the first `State` parameter of an `#[rpc]` method
+pub(super) fn find_rpc_state_type(impl_block: &ItemImpl) -> Option<&Type> {
+  impl_block.items.iter().find_map(|item| {
+    let ImplItem::Fn(method) = item else {
+      return None;
+    };
+    let rpc = crate::parser::extract_rpc_from_attrs(&method.attrs, &method.sig.ident.to_string())?;
+    method.sig.inputs.iter().find_map(|input| match input {
+      FnArg::Typed(pat_type) => match &*pat_type.pat {
+        Pat::Ident(pat_ident)
+          if rpc.extractors.get(&pat_ident.ident.to_string()) == Some(&ExtractorType::State) =>
+        {
+          Some(&*pat_type.ty)
+        }
+        _ => None,
+      },
+      FnArg::Receiver(_) => None,
+    })
+  })
+}
+
+/// Registers the dispatcher as the controller's only route, answering `POST` on its path
+pub fn generate_rpc_registration(controller_config: &ControllerConfig) -> TokenStream {
+  let body_limit_layer = controller_config
+    .body_limit
+    .map(|limit| quote! { .layer(axum::extract::DefaultBodyLimit::max(#limit)) });
+  quote! {
+    .route("/", axum::routing::post(Self::__route_controller_rpc)#body_limit_layer)
+  }
+}
+
+/// Generates the dispatcher, one adapter per `#[rpc]` method and the helpers they share
+pub fn generate_rpc_functions(
+  impl_block: &ItemImpl,
+  controller_config: &ControllerConfig,
+) -> TokenStream {
+  let methods = rpc_methods(impl_block);
+  if methods.is_empty() {
+    emit_call_site_error!("#[rpc_controller] has no #[rpc] methods");
+  }
+  for item in &impl_block.items {
+    if let ImplItem::Fn(method) = item {
+      if crate::parser::extract_route_from_attrs(&method.attrs).is_some() {
+        emit_call_site_error!(
+          "'{}' has a route attribute, but #[rpc_controller] serves a single JSON-RPC endpoint. \
+           Mark it #[rpc] or move it to a #[controller]",
+          method.sig.ident
+        );
+      }
+    }
+  }
+
+  let state_ty = match super::router::find_state_type(impl_block, controller_config) {
+    Some(ty) => quote! { #ty },
+    None => quote! { () },
+  };
+
+  let mut names = HashSet::with_capacity(methods.len());
+  let mut arms = Vec::with_capacity(methods.len());
+  let mut adapters = Vec::with_capacity(methods.len());
+  for (method, rpc) in &methods {
+    if !names.insert(rpc.name.as_str()) {
+      emit_call_site_error!("JSON-RPC method name '{}' is used twice", rpc.name);
+    }
+    let adapter_name = format_ident!("__route_controller_rpc_{}", method.sig.ident);
+    let rpc_name = &rpc.name;
+    arms.push(quote! {
+      #rpc_name => Self::#adapter_name(state, parts, params).await,
+    });
+    adapters.push(rpc_adapter(method, rpc, &adapter_name, &state_ty));
+
+    log_verbose!(
+      "Registering JSON-RPC method: [Method:{}] [Handler:{}]",
+      rpc_name,
+      method.sig.ident
+    );
+  }
+
+  let helpers = rpc_helpers();
+  quote! {
+    /// Answers a JSON-RPC request or batch. Notifications get no reply, so a request made up of
+    /// them only is answered with `204 No Content`
+    async fn __route_controller_rpc(
+      axum::extract::State(__state): axum::extract::State<#state_ty>,
+      mut __parts: axum::http::request::Parts,
+      __body: axum::body::Bytes,
+    ) -> axum::response::Response {
+      let __reply = match serde_json::from_slice::<serde_json::Value>(&__body) {
+        Err(error) => Some(Self::__route_controller_rpc_reply(
+          serde_json::Value::Null,
+          Err(Self::__route_controller_rpc_error(-32700, "Parse error", Some(error.to_string().into()))),
+        )),
+        Ok(serde_json::Value::Array(calls)) if calls.is_empty() => Some(Self::__route_controller_rpc_reply(
+          serde_json::Value::Null,
+          Err(Self::__route_controller_rpc_error(-32600, "Invalid Request", Some("empty batch".into()))),
+        )),
+        Ok(serde_json::Value::Array(calls)) => {
+          let mut replies = ::std::vec::Vec::with_capacity(calls.len());
+          for call in calls {
+            replies.extend(Self::__route_controller_rpc_call(&__state, &mut __parts, call).await);
+          }
+          (!replies.is_empty()).then_some(serde_json::Value::Array(replies))
+        }
+        Ok(call) => Self::__route_controller_rpc_call(&__state, &mut __parts, call).await,
+      };
+      match __reply {
+        Some(reply) => axum::response::IntoResponse::into_response(axum::Json(reply)),
+        None => axum::response::IntoResponse::into_response(axum::http::StatusCode::NO_CONTENT),
+      }
+    }
+
+    /// Runs one call of a request, returning its reply unless it is a notification
+    async fn __route_controller_rpc_call(
+      state: &#state_ty,
+      parts: &mut axum::http::request::Parts,
+      call: serde_json::Value,
+    ) -> ::std::option::Option<serde_json::Value> {
+      let serde_json::Value::Object(mut call) = call else {
+        return Some(Self::__route_controller_rpc_reply(
+          serde_json::Value::Null,
+          Err(Self::__route_controller_rpc_error(-32600, "Invalid Request", Some("expected an object".into()))),
+        ));
+      };
+      let id = call.remove("id");
+      let valid_id = matches!(
+        id,
+        None | Some(serde_json::Value::Null | serde_json::Value::String(_) | serde_json::Value::Number(_))
+      );
+      let params = call.remove("params");
+      let method = match call.remove("method") {
+        Some(serde_json::Value::String(method)) => Some(method),
+        _ => None,
+      };
+      let version = call.remove("jsonrpc");
+      let problem = if !valid_id {
+        Some("id must be a string, a number or null")
+      } else if version.as_ref().and_then(serde_json::Value::as_str) != Some("2.0") {
+        Some("jsonrpc must be \"2.0\"")
+      } else if method.is_none() {
+        Some("method must be a string")
+      } else if !matches!(params, None | Some(serde_json::Value::Array(_) | serde_json::Value::Object(_))) {
+        Some("params must be an array or an object")
+      } else {
+        None
+      };
+      // Malformed requests are answered even without an id
+      if let Some(problem) = problem {
+        let id = if valid_id { id.unwrap_or_default() } else { serde_json::Value::Null };
+        return Some(Self::__route_controller_rpc_reply(
+          id,
+          Err(Self::__route_controller_rpc_error(-32600, "Invalid Request", Some(problem.into()))),
+        ));
+      }
+      let method = method.unwrap_or_default();
+      let result = match method.as_str() {
+        #(#arms)*
+        _ => Err(Self::__route_controller_rpc_error(-32601, "Method not found", Some(method.into()))),
+      };
+      // Notifications are never answered, not even with an error
+      Some(Self::__route_controller_rpc_reply(id?, result))
+    }
+
+    #(#adapters)*
+
+    #helpers
+  }
+}
+
+/// Generates the adapter that reads a method's params and extractors, calls the handler and
+/// serializes its result
+fn rpc_adapter(
+  method: &syn::ImplItemFn,
+  rpc: &RpcMethod,
+  adapter_name: &syn::Ident,
+  state_ty: &TokenStream,
+) -> TokenStream {
+  let handler_name = &method.sig.ident;
+  let mut param_names = Vec::new();
+  let mut bindings = Vec::new();
+  let mut call_args = Vec::new();
+  let mut seen = HashSet::new();
+  let mut request_parts = Vec::new();
+
+  for (index, input) in method.sig.inputs.iter().enumerate() {
+    let FnArg::Typed(pat_type) = input else {
+      emit_call_site_error!(
+        "#[rpc] method '{}' may not take self. Controllers are called as associated functions",
+        handler_name
+      );
+      continue;
+    };
+    let ty = &*pat_type.ty;
+    let Pat::Ident(pat_ident) = &*pat_type.pat else {
+      emit_call_site_error!(
+        "Parameters of #[rpc] method '{}' must be plain identifiers, as params are matched by name",
+        handler_name
+      );
+      continue;
+    };
+    let name = pat_ident.ident.to_string();
+    seen.insert(name.clone());
+    let arg = format_ident!("__arg{}", index);
+    call_args.push(quote! { #arg });
+
+    let extractor_type = rpc
+      .extractors
+      .get(&name)
+      .copied()
+      .unwrap_or(ExtractorType::None);
+    // Request parts are read as on routes, from the headers, cookies or session bound below
+    let parts_value = super::wrappers::request_parts_value(extractor_type, &pat_ident.ident, ty);
+    let value = if extractor_type == ExtractorType::State {
+      quote! {
+        <#ty as axum::extract::FromRef<#state_ty>>::from_ref(__state)
+      }
+    } else if let Some(value) = parts_value {
+      request_parts.push(extractor_type);
+      value
+    } else {
+      // Everything else is read from the call's params, by position or by name
+      let position = param_names.len();
+      param_names.push(name.clone());
+      quote! {
+        match serde_json::from_value(::std::mem::take(&mut __args[#position])) {
+          Ok(value) => value,
+          Err(error) => {
+            return Err(Self::__route_controller_rpc_error(
+              -32602,
+              "Invalid params",
+              Some(format!("{}: {}", #name, error).into()),
+            ));
+          }
+        }
+      }
+    };
+    bindings.push(quote! { let #arg: #ty = #value; });
+  }
+
+  for name in rpc.extractors.keys() {
+    if !seen.contains(name) {
+      emit_call_site_error!(
+        "Extractor specified for parameter '{}' but #[rpc] method '{}' has no such parameter",
+        name,
+        handler_name
+      );
+    }
+  }
+
+  // The session needs the parts mutably, so it is resolved before the headers are borrowed
+  let session = request_parts.contains(&ExtractorType::SessionParam).then(|| {
+    quote! {
+      let session = match <tower_sessions::Session as axum::extract::FromRequestParts<#state_ty>>::from_request_parts(__parts, __state).await {
+        Ok(session) => session,
+        Err((_, message)) => {
+          return Err(Self::__route_controller_rpc_error(-32603, "Internal error", Some(message.into())));
+        }
+      };
+    }
+  });
+  let headers = request_parts
+    .contains(&ExtractorType::HeaderParam)
+    .then(|| quote! { let headers = &__parts.headers; });
+  let cookies = request_parts
+    .contains(&ExtractorType::CookieParam)
+    .then(|| {
+      quote! { let cookies = axum_extra::extract::CookieJar::from_headers(&__parts.headers); }
+    });
+
+  let await_token = method.sig.asyncness.map(|_| quote! { .await });
+  let call = quote! { Self::#handler_name(#(#call_args),*)#await_token };
+  // Err values become the call's error object
+  let result = if crate::parser::returns_result(&method.sig) {
+    quote! {
+      match #call {
+        Ok(value) => Self::__route_controller_rpc_result(serde_json::to_value(&value)),
+        Err(error) => Err(Self::__route_controller_rpc_failure(serde_json::to_value(&error))),
+      }
+    }
+  } else {
+    quote! { Self::__route_controller_rpc_result(serde_json::to_value(&#call)) }
+  };
+
+  quote! {
+    #[allow(unused_variables, unused_mut)]
+    async fn #adapter_name(
+      __state: &#state_ty,
+      __parts: &mut axum::http::request::Parts,
+      __params: ::std::option::Option<serde_json::Value>,
+    ) -> ::std::result::Result<serde_json::Value, serde_json::Value> {
+      let mut __args = Self::__route_controller_rpc_arguments(__params, &[#(#param_names),*])?;
+      #session
+      #headers
+      #cookies
+      #(#bindings)*
+      #result
+    }
+  }
+}
+
+/// Error objects, replies and the mapping of params onto parameters
+fn rpc_helpers() -> TokenStream {
+  quote! {
+    /// Builds the error object for one of the codes defined by JSON-RPC 2.0
+    fn __route_controller_rpc_error(
+      code: i64,
+      message: &str,
+      data: ::std::option::Option<serde_json::Value>,
+    ) -> serde_json::Value {
+      let mut error = serde_json::json!({ "code": code, "message": message });
+      if let Some(data) = data {
+        error["data"] = data;
+      }
+      error
+    }
+
+    fn __route_controller_rpc_reply(
+      id: serde_json::Value,
+      result: ::std::result::Result<serde_json::Value, serde_json::Value>,
+    ) -> serde_json::Value {
+      match result {
+        Ok(result) => serde_json::json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+        Err(error) => serde_json::json!({ "jsonrpc": "2.0", "error": error, "id": id }),
+      }
+    }
+
+    fn __route_controller_rpc_result(
+      value: ::std::result::Result<serde_json::Value, serde_json::Error>,
+    ) -> ::std::result::Result<serde_json::Value, serde_json::Value> {
+      value.map_err(|error| {
+        Self::__route_controller_rpc_error(-32603, "Internal error", Some(error.to_string().into()))
+      })
+    }
+
+    /// Uses a handler's error as the error object when it has an integer `code` and a string
+    /// `message`, otherwise sends it as the `data` of a -32000 server error
+    fn __route_controller_rpc_failure(
+      error: ::std::result::Result<serde_json::Value, serde_json::Error>,
+    ) -> serde_json::Value {
+      match error {
+        Ok(serde_json::Value::Object(error))
+          if error.get("code").is_some_and(serde_json::Value::is_i64)
+            && error.get("message").is_some_and(serde_json::Value::is_string) =>
+        {
+          serde_json::Value::Object(error)
+        }
+        Ok(data) => Self::__route_controller_rpc_error(-32000, "Server error", Some(data)),
+        Err(error) => {
+          Self::__route_controller_rpc_error(-32603, "Internal error", Some(error.to_string().into()))
+        }
+      }
+    }
+
+    /// Lines up positional or named params with the method's parameters. Missing params are
+    /// null, which `Option` parameters accept
+    fn __route_controller_rpc_arguments(
+      params: ::std::option::Option<serde_json::Value>,
+      names: &[&str],
+    ) -> ::std::result::Result<::std::vec::Vec<serde_json::Value>, serde_json::Value> {
+      let invalid = |detail: ::std::string::String| {
+        Self::__route_controller_rpc_error(-32602, "Invalid params", Some(detail.into()))
+      };
+      match params {
+        None => Ok(::std::vec![serde_json::Value::Null; names.len()]),
+        Some(serde_json::Value::Array(mut values)) => {
+          if values.len() > names.len() {
+            return Err(invalid(format!(
+              "expected at most {} params, got {}",
+              names.len(),
+              values.len()
+            )));
+          }
+          values.resize(names.len(), serde_json::Value::Null);
+          Ok(values)
+        }
+        Some(serde_json::Value::Object(mut values)) => {
+          let arguments = names
+            .iter()
+            .map(|name| values.remove(*name).unwrap_or_default())
+            .collect();
+          match values.keys().next() {
+            Some(unknown) => Err(invalid(format!("unknown param '{}'", unknown))),
+            None => Ok(arguments),
+          }
+        }
+        Some(_) => Err(invalid("params must be an array or an object".to_string())),
+      }
+    }
+  }
+}
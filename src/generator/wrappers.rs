@@ -1,22 +1,72 @@
 //! Wrapper function generation for route handlers
-use crate::parser::ControllerConfig;
+use crate::parser::{
+  BodyTarget, ControllerConfig, HeaderPolicy, HeaderValueSource, LocationTemplate, ParamInfo,
+  ResponseFormat, ResponseHeader, RouteInfo, SseConfig, SseField, StreamFormat, WsConfig,
+};
+use proc_macro_error::{abort_call_site, emit_call_site_error, emit_call_site_warning};
 use proc_macro2::TokenStream;
 use quote::quote;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use syn::ItemImpl;
 
+/// Returns true when the route is registered through a generated wrapper instead of the handler
+pub(super) fn route_needs_wrapper(
+  sig: &syn::Signature,
+  route_info: &RouteInfo,
+  params: &[ParamInfo],
+  controller_config: &ControllerConfig,
+) -> bool {
+  let has_extractors = params
+    .iter()
+    .any(|p| p.extractor_type != crate::parser::ExtractorType::None);
+
+  let has_requirements =
+    !route_info.requirements.is_empty() || !controller_config.requirements.is_empty();
+
+  has_extractors
+    || route_info.ws.is_some()
+    || decorates_success(route_info, controller_config)
+    || has_requirements
+    || (crate::parser::returns_result(sig)
+      && has_error_response_config(route_info, controller_config))
+}
+
+/// Returns true when successful responses get a status code or headers added, or are serialized
+/// into a negotiated format. Controller-level headers are applied by a router layer
+fn decorates_success(route_info: &RouteInfo, controller_config: &ControllerConfig) -> bool {
+  !super::helpers::negotiated_formats(route_info, controller_config).is_empty()
+    || route_info.respond.is_some()
+    || route_info.stream.is_some()
+    || route_info.sse.is_some()
+    || !route_info.response_headers.is_empty()
+    || route_info.content_type.is_some()
+    || route_info.filename.is_some()
+    || route_info.status.is_some()
+    || route_info.location.is_some()
+}
+
+/// Returns true when `Err` values need converting or decorating before they are returned
+fn has_error_response_config(route_info: &RouteInfo, controller_config: &ControllerConfig) -> bool {
+  route_info.error.is_some()
+    || controller_config.error.is_some()
+    || !route_info.error_headers.is_empty()
+    || !controller_config.error_headers.is_empty()
+    || route_info.error_status.is_some()
+    || controller_config.error_status.is_some()
+}
+
 pub fn generate_wrapper_functions(
   impl_block: &ItemImpl,
   controller_config: &ControllerConfig,
 ) -> Vec<TokenStream> {
   let mut wrappers = Vec::with_capacity(impl_block.items.len());
+  let state_type = super::router::find_state_type(impl_block, controller_config);
 
   for item in &impl_block.items {
     if let syn::ImplItem::Fn(method) = item {
       if let Some(route_info) = crate::parser::extract_route_from_attrs(&method.attrs) {
         let params = crate::parser::analyze_params(&method.sig, &route_info.extractors);
 
-        let mut needs_wrapper = false;
         // Group Path parameters for tuple extraction
         let mut path_types: Vec<_> = vec![];
         let mut path_names: Vec<_> = vec![];
@@ -25,7 +75,6 @@ pub fn generate_wrapper_functions(
           match p.extractor_type {
             crate::parser::ExtractorType::None => {}
             _ => {
-              needs_wrapper = true;
               // Path extractors need special handling
               // Collect all path types and names to combine into a tuple
               if p.extractor_type == crate::parser::ExtractorType::Path {
@@ -38,12 +87,11 @@ pub fn generate_wrapper_functions(
           }
         }
 
-        let has_response_headers = !route_info.response_headers.is_empty()
-          || route_info.content_type.is_some()
-          || !controller_config.response_headers.is_empty()
-          || controller_config.content_type.is_some();
+        let requirements = controller_config
+          .requirements
+          .merged_with(&route_info.requirements);
 
-        if needs_wrapper || has_response_headers {
+        if route_needs_wrapper(&method.sig, &route_info, &params, controller_config) {
           let handler_name = &method.sig.ident;
           let wrapper_name = syn::Ident::new(
             &format!("{}_wrapper", handler_name),
@@ -64,17 +112,8 @@ pub fn generate_wrapper_functions(
 
           let return_type = &method.sig.output;
 
-          // Determine if we need to wrap the return type with headers
-          let needs_header_wrapping = !route_info.response_headers.is_empty()
-            || route_info.content_type.is_some()
-            || !controller_config.response_headers.is_empty()
-            || controller_config.content_type.is_some();
-
-          let wrapper_return_type = if needs_header_wrapping {
-            quote! { -> impl axum::response::IntoResponse }
-          } else {
-            quote! { #return_type }
-          };
+          // Determine if we need to wrap the return type with a status or headers
+          let needs_header_wrapping = decorates_success(&route_info, controller_config);
 
           // Build wrapper parameters
           // Axum requires extractors in a specific order:
@@ -92,25 +131,66 @@ pub fn generate_wrapper_functions(
           let mut request_parts_params = HashSet::with_capacity(3);
           let mut body_params = Vec::with_capacity(2); // Most routes have 0-2 body params
           let mut other_params = Vec::with_capacity(1);
+          // Statements run before the handler that may answer early (auth checks, guards)
+          let mut prelude = Vec::new();
+          // WebSocket parameter of a #[ws] handler
+          let mut socket = None;
+          // Jwt keys are bound once, however many parameters decode the token
+          let mut jwt_state_bound = false;
+
+          // Extractor rejections are converted by the route or controller rejection type,
+          // falling back to the controller's #[catch] method
+          let rejection_mapper = route_info
+            .rejection
+            .as_ref()
+            .or(controller_config.rejection.as_ref())
+            .map(|error_ty| {
+              quote! {
+                axum::response::IntoResponse::into_response(
+                  <#error_ty as ::std::convert::From<_>>::from(rejection)
+                )
+              }
+            })
+            .or_else(|| {
+              controller_config.catch_handler.as_ref().map(|catch_fn| {
+                quote! {
+                  axum::response::IntoResponse::into_response(
+                    Self::#catch_fn(rejection.status(), rejection.body_text())
+                  )
+                }
+              })
+            });
 
           // Handle Path extractors (must be first and combined into tuple if multiple)
-          if !path_types.is_empty() {
+          if let (Some(mapper), false) = (&rejection_mapper, path_types.is_empty()) {
+            let (path_ty, pattern) = if path_types.len() > 1 {
+              (quote! { (#(#path_types),*) }, quote! { (#(#path_names),*) })
+            } else {
+              let ty = &path_types[0];
+              let name = path_names[0];
+              (quote! { #ty }, quote! { #name })
+            };
+            let (param, unwrap) = mapped_extraction(
+              &syn::Ident::new("__path", proc_macro2::Span::call_site()),
+              quote! { axum::extract::Path },
+              path_ty,
+              pattern,
+              quote! { axum::extract::rejection::PathRejection },
+              mapper,
+            );
+            wrapper_params.push(param);
+            prelude.push(unwrap);
+          } else if !path_types.is_empty() {
             if path_types.len() > 1 {
               // Multiple paths
               wrapper_params.push(quote! {
                 axum::extract::Path((#(#path_names),*)): axum::extract::Path<(#(#path_types),*)>
               });
-
-              // Add individual path args to call_args
-              for name in &path_names {
-                call_args.push(quote! { #name });
-              }
             } else {
               // Single path: extract normally
               let name = path_names[0];
               let ty = &path_types[0];
               wrapper_params.push(quote! { axum::extract::Path(#name): axum::extract::Path<#ty> });
-              call_args.push(quote! { #name });
             }
           }
 
@@ -121,7 +201,11 @@ pub fn generate_wrapper_functions(
 
             match &p.extractor_type {
               crate::parser::ExtractorType::Path => {
-                // Already handled above
+                // Extracted above; passed in the parameter's position
+                if let syn::Pat::Ident(pat_ident) = pat {
+                  let name = &pat_ident.ident;
+                  call_args.push(quote! { #name });
+                }
               }
               crate::parser::ExtractorType::State => {
                 // Extract state and pass it through
@@ -129,74 +213,262 @@ pub fn generate_wrapper_functions(
                 state_params.push(quote! { state: axum::extract::State<#ty> });
               }
               crate::parser::ExtractorType::HeaderParam => {
+                if let syn::Pat::Ident(pat_ident) = pat {
+                  call_args.extend(request_parts_value(p.extractor_type, &pat_ident.ident, ty));
+                  request_parts_params.insert("HeaderParam");
+                }
+              }
+              crate::parser::ExtractorType::LastEventId => {
+                // Sent by EventSource clients reconnecting after the last event they received
+                let value = quote! {
+                  headers.get("last-event-id")
+                    .and_then(|v| v.to_str().ok())
+                    .map(::std::string::ToString::to_string)
+                };
+                if crate::parser::option_inner_type(ty).is_some() {
+                  call_args.push(value);
+                } else {
+                  call_args.push(quote! { #value.unwrap_or_default() });
+                }
+                request_parts_params.insert("HeaderParam");
+              }
+              crate::parser::ExtractorType::BearerToken
+              | crate::parser::ExtractorType::BasicAuth => {
                 if let syn::Pat::Ident(pat_ident) = pat {
                   let name = &pat_ident.ident;
-                  let name_str = name.to_string();
-                  // Convert snake_case to kebab-case for header names (e.g., content_type -> content-type)
-                  let header_name = name_str.replace('_', "-");
-                  call_args.push(quote! {
-                    headers.get(#header_name)
-                      .and_then(|v| v.to_str().ok())
-                      .unwrap_or("")
-                      .to_string()
+                  let is_optional = crate::parser::option_inner_type(ty).is_some();
+                  let (parse_fn, challenge, malformed_challenge) = if p.extractor_type
+                    == crate::parser::ExtractorType::BearerToken
+                  {
+                    (
+                        quote! { __route_controller_bearer_token },
+                        "Bearer".to_string(),
+                        "Bearer error=\"invalid_token\", error_description=\"Malformed bearer token\"".to_string(),
+                      )
+                  } else {
+                    let realm = route_info
+                      .realm
+                      .as_deref()
+                      .or(controller_config.realm.as_deref())
+                      .unwrap_or("api");
+                    let challenge = format!("Basic realm=\"{}\", charset=\"UTF-8\"", realm);
+                    (
+                      quote! { __route_controller_basic_auth },
+                      challenge.clone(),
+                      challenge,
+                    )
+                  };
+                  let (present, missing) = if is_optional {
+                    (quote! { Some(credentials) }, quote! { None })
+                  } else {
+                    (
+                      quote! { credentials },
+                      quote! { return Self::__route_controller_unauthorized(#challenge) },
+                    )
+                  };
+                  prelude.push(quote! {
+                    let #name = match Self::#parse_fn(&headers) {
+                      Ok(Some(credentials)) => #present,
+                      Ok(None) => #missing,
+                      Err(()) => return Self::__route_controller_unauthorized(#malformed_challenge),
+                    };
                   });
+                  call_args.push(quote! { #name });
                   request_parts_params.insert("HeaderParam");
                 }
               }
-              crate::parser::ExtractorType::CookieParam => {
+              crate::parser::ExtractorType::Jwt => {
                 if let syn::Pat::Ident(pat_ident) = pat {
                   let name = &pat_ident.ident;
-                  let name_str = name.to_string();
-                  call_args.push(quote! {
-                    cookies.get(#name_str)
-                      .map(|c| c.value().to_string())
-                      .unwrap_or_default()
+                  let optional_inner = crate::parser::option_inner_type(ty);
+                  let claims_ty = optional_inner.unwrap_or(ty);
+                  let (present, missing) = if optional_inner.is_some() {
+                    (quote! { Some(data.claims) }, quote! { None })
+                  } else {
+                    (
+                      quote! { data.claims },
+                      quote! {
+                        return Self::__route_controller_jwt_unauthorized(None, "Missing bearer token")
+                      },
+                    )
+                  };
+                  // Keys and validation rules are resolved from the router state via FromRef
+                  if !jwt_state_bound {
+                    state_params.push(quote! {
+                      axum::extract::State(__jwt_key): axum::extract::State<jsonwebtoken::DecodingKey>
+                    });
+                    state_params.push(quote! {
+                      axum::extract::State(__jwt_validation): axum::extract::State<jsonwebtoken::Validation>
+                    });
+                    jwt_state_bound = true;
+                  }
+                  prelude.push(quote! {
+                    let #name = match Self::__route_controller_authorization(&headers, "Bearer") {
+                      Ok(Some(token)) => {
+                        match jsonwebtoken::decode::<#claims_ty>(token, &__jwt_key, &__jwt_validation) {
+                          Ok(data) => #present,
+                          Err(error) => return Self::__route_controller_jwt_error(&error),
+                        }
+                      }
+                      Ok(None) => #missing,
+                      Err(()) => {
+                        return Self::__route_controller_jwt_unauthorized(
+                          Some("invalid_request"),
+                          "Malformed Authorization header",
+                        )
+                      }
+                    };
                   });
+                  call_args.push(quote! { #name });
+                  request_parts_params.insert("HeaderParam");
+                }
+              }
+              crate::parser::ExtractorType::CookieParam => {
+                if let syn::Pat::Ident(pat_ident) = pat {
+                  call_args.extend(request_parts_value(p.extractor_type, &pat_ident.ident, ty));
                   request_parts_params.insert("CookieParam");
                 }
               }
               crate::parser::ExtractorType::SessionParam => {
                 if let syn::Pat::Ident(pat_ident) = pat {
-                  let name = &pat_ident.ident;
-                  let name_str = name.to_string();
-                  // Session.get() returns a Future, so we need to await it
-                  call_args.push(quote! {
-                    session.get::<#ty>(#name_str)
-                      .await
-                      .ok()
-                      .flatten()
-                      .unwrap_or_default()
-                  });
+                  call_args.extend(request_parts_value(p.extractor_type, &pat_ident.ident, ty));
                   request_parts_params.insert("SessionParam");
                 }
               }
+              crate::parser::ExtractorType::Json | crate::parser::ExtractorType::Form
+                if p.optional =>
+              {
+                if let syn::Pat::Ident(pat_ident) = pat {
+                  let name = &pat_ident.ident;
+                  let inner_ty = p.body_ty();
+                  let (extractor, rejection_ty) =
+                    if p.extractor_type == crate::parser::ExtractorType::Json {
+                      (
+                        quote! { axum::Json },
+                        quote! { axum::extract::rejection::JsonRejection },
+                      )
+                    } else {
+                      (
+                        quote! { axum::Form },
+                        quote! { axum::extract::rejection::FormRejection },
+                      )
+                    };
+                  let on_rejection = rejection_mapper.clone().unwrap_or_else(
+                    || quote! { axum::response::IntoResponse::into_response(rejection) },
+                  );
+                  let (param, extraction) = optional_body_extraction(
+                    name,
+                    quote! { #extractor<#inner_ty> },
+                    quote! { #extractor(value) },
+                    quote! {
+                      {
+                        let rejection = <#rejection_ty as ::std::convert::From<_>>::from(rejection);
+                        #on_rejection
+                      }
+                    },
+                    on_rejection,
+                  );
+                  body_params.push(param);
+                  prelude.push(extraction);
+                  call_args.push(quote! { #name });
+                }
+              }
               crate::parser::ExtractorType::Json => {
                 if let syn::Pat::Ident(pat_ident) = pat {
                   let name = &pat_ident.ident;
-                  body_params.push(quote! { axum::Json(#name): axum::Json<#ty> });
+                  if let Some(mapper) = &rejection_mapper {
+                    let (param, unwrap) = mapped_extraction(
+                      name,
+                      quote! { axum::Json },
+                      quote! { #ty },
+                      quote! { #name },
+                      quote! { axum::extract::rejection::JsonRejection },
+                      mapper,
+                    );
+                    body_params.push(param);
+                    prelude.push(unwrap);
+                  } else {
+                    body_params.push(quote! { axum::Json(#name): axum::Json<#ty> });
+                  }
                   call_args.push(quote! { #name });
                 }
               }
               crate::parser::ExtractorType::Form => {
                 if let syn::Pat::Ident(pat_ident) = pat {
                   let name = &pat_ident.ident;
-                  body_params.push(quote! { axum::Form(#name): axum::Form<#ty> });
+                  if let Some(mapper) = &rejection_mapper {
+                    let (param, unwrap) = mapped_extraction(
+                      name,
+                      quote! { axum::Form },
+                      quote! { #ty },
+                      quote! { #name },
+                      quote! { axum::extract::rejection::FormRejection },
+                      mapper,
+                    );
+                    body_params.push(param);
+                    prelude.push(unwrap);
+                  } else {
+                    body_params.push(quote! { axum::Form(#name): axum::Form<#ty> });
+                  }
                   call_args.push(quote! { #name });
                 }
               }
               crate::parser::ExtractorType::Query => {
                 if let syn::Pat::Ident(pat_ident) = pat {
                   let name = &pat_ident.ident;
-                  body_params
-                    .push(quote! { axum::extract::Query(#name): axum::extract::Query<#ty> });
+                  if let Some(mapper) = &rejection_mapper {
+                    let (param, unwrap) = mapped_extraction(
+                      name,
+                      quote! { axum::extract::Query },
+                      quote! { #ty },
+                      quote! { #name },
+                      quote! { axum::extract::rejection::QueryRejection },
+                      mapper,
+                    );
+                    body_params.push(param);
+                    prelude.push(unwrap);
+                  } else {
+                    body_params
+                      .push(quote! { axum::extract::Query(#name): axum::extract::Query<#ty> });
+                  }
                   call_args.push(quote! { #name });
                 }
               }
               crate::parser::ExtractorType::Bytes => {
                 if let syn::Pat::Ident(pat_ident) = pat {
                   let name = &pat_ident.ident;
+                  if p.target == BodyTarget::Owned
+                    && crate::parser::is_type_named(p.body_ty(), "Box")
+                  {
+                    emit_call_site_error!(
+                      "Bytes parameter '{}' must be Vec<u8>, Box<[u8]> or axum::body::Bytes, not {}",
+                      name,
+                      quote! { #ty }.to_string().replace(' ', "")
+                    );
+                  }
                   body_params.push(quote! { #name: axum::body::Bytes });
-                  call_args.push(quote! { #name.to_vec() });
+                  // A `Bytes` parameter takes the buffer as is. Converting into a Vec reuses the
+                  // allocation when the buffer is not shared
+                  call_args.push(match p.target {
+                    BodyTarget::Bytes => quote! { #name },
+                    BodyTarget::BoxedSlice => {
+                      quote! { ::std::vec::Vec::<u8>::from(#name).into_boxed_slice() }
+                    }
+                    _ => quote! { ::std::vec::Vec::<u8>::from(#name) },
+                  });
+                }
+              }
+              crate::parser::ExtractorType::BodyStream => {
+                if let syn::Pat::Ident(pat_ident) = pat {
+                  let name = &pat_ident.ident;
+                  // The limited body errors once it exceeds the route's DefaultBodyLimit
+                  body_params.push(quote! { #name: axum::extract::Request });
+                  let body = quote! { axum::RequestExt::with_limited_body(#name).into_body() };
+                  if crate::parser::is_type_named(ty, "Body") {
+                    call_args.push(body);
+                  } else {
+                    call_args.push(quote! { #body.into_data_stream() });
+                  }
                 }
               }
               crate::parser::ExtractorType::Text
@@ -205,7 +477,112 @@ pub fn generate_wrapper_functions(
               | crate::parser::ExtractorType::JavaScript => {
                 if let syn::Pat::Ident(pat_ident) = pat {
                   let name = &pat_ident.ident;
-                  body_params.push(quote! { #name: String });
+                  if p.target == BodyTarget::Owned
+                    && ["Cow", "Arc"]
+                      .iter()
+                      .any(|wrapper| crate::parser::is_type_named(p.body_ty(), wrapper))
+                  {
+                    emit_call_site_error!(
+                      "Text parameter '{}' must be String, Cow<str> or Arc<str>, not {}",
+                      name,
+                      quote! { #ty }.to_string().replace(' ', "")
+                    );
+                  }
+                  let converted = |value: TokenStream| match p.target {
+                    BodyTarget::Cow => quote! { ::std::borrow::Cow::<str>::Owned(#value) },
+                    BodyTarget::Arc => quote! { ::std::sync::Arc::<str>::from(#value) },
+                    _ => value,
+                  };
+                  if p.optional {
+                    // The body is buffered first, so a failure is answered as is
+                    let (param, extraction) = optional_body_extraction(
+                      name,
+                      quote! { String },
+                      quote! { value },
+                      quote! { axum::response::IntoResponse::into_response(rejection) },
+                      quote! { axum::response::IntoResponse::into_response(rejection) },
+                    );
+                    body_params.push(param);
+                    prelude.push(extraction);
+                    let value = converted(quote! { value });
+                    call_args.push(quote! { #name.map(|value| #value) });
+                  } else {
+                    body_params.push(quote! { #name: String });
+                    call_args.push(converted(quote! { #name }));
+                  }
+                }
+              }
+              crate::parser::ExtractorType::XmlBody
+              | crate::parser::ExtractorType::MsgPack
+              | crate::parser::ExtractorType::Cbor
+              | crate::parser::ExtractorType::MergePatch
+              | crate::parser::ExtractorType::JsonPatch => {
+                if let syn::Pat::Ident(pat_ident) = pat {
+                  let name = &pat_ident.ident;
+                  body_params.push(quote! { #name: axum::body::Bytes });
+                  let (format, decoded) = match p.extractor_type {
+                    crate::parser::ExtractorType::XmlBody => {
+                      ("xml", quote! { quick_xml::de::from_reader(&#name[..]) })
+                    }
+                    crate::parser::ExtractorType::MsgPack => {
+                      ("msgpack", quote! { rmp_serde::from_slice(&#name) })
+                    }
+                    crate::parser::ExtractorType::Cbor => {
+                      ("cbor", quote! { ciborium::from_reader(&#name[..]) })
+                    }
+                    crate::parser::ExtractorType::MergePatch => {
+                      ("merge-patch", quote! { serde_json::from_slice(&#name) })
+                    }
+                    _ => ("json-patch", quote! { serde_json::from_slice(&#name) }),
+                  };
+                  let invalid = match &controller_config.catch_handler {
+                    Some(catch_fn) => quote! {
+                      axum::response::IntoResponse::into_response(
+                        Self::#catch_fn(axum::http::StatusCode::BAD_REQUEST, error.to_string())
+                      )
+                    },
+                    None => quote! { Self::__route_controller_invalid_body(#format, &error) },
+                  };
+                  prelude.push(quote! {
+                    let #name: #ty = match #decoded {
+                      Ok(value) => value,
+                      Err(error) => return #invalid,
+                    };
+                  });
+                  call_args.push(quote! { #name });
+                }
+              }
+              // Rows are deserialized from the buffered body, reporting the failing row and column
+              crate::parser::ExtractorType::Csv => {
+                if let syn::Pat::Ident(pat_ident) = pat {
+                  let name = &pat_ident.ident;
+                  body_params.push(quote! { #name: axum::body::Bytes });
+                  let invalid = match &controller_config.catch_handler {
+                    Some(catch_fn) => quote! {
+                      axum::response::IntoResponse::into_response(
+                        Self::#catch_fn(axum::http::StatusCode::BAD_REQUEST, error.0.to_string())
+                      )
+                    },
+                    None => {
+                      quote! { Self::__route_controller_invalid_csv(&error.0, error.1.as_deref()) }
+                    }
+                  };
+                  prelude.push(quote! {
+                    let #name: #ty = match Self::__route_controller_csv_rows(&#name) {
+                      Ok(rows) => rows,
+                      Err(error) => return #invalid,
+                    };
+                  });
+                  call_args.push(quote! { #name });
+                }
+              }
+              // The upgraded connection is passed in by the #[ws] wrapper
+              crate::parser::ExtractorType::None
+                if route_info.ws.is_some() && crate::parser::is_type_named(ty, "WebSocket") =>
+              {
+                if let syn::Pat::Ident(pat_ident) = pat {
+                  let name = &pat_ident.ident;
+                  socket = Some(name);
                   call_args.push(quote! { #name });
                 }
               }
@@ -216,77 +593,278 @@ pub fn generate_wrapper_functions(
             }
           }
 
+          // Validate extracted values after deserialization
+          for p in params.iter() {
+            if let syn::Pat::Ident(pat_ident) = &p.pat {
+              let name = &pat_ident.ident;
+              if route_info.validated.contains(&name.to_string()) {
+                let rejection = match &controller_config.validation_error {
+                  Some(handler) => quote! {
+                    axum::response::IntoResponse::into_response(#handler(errors))
+                  },
+                  None => quote! {
+                    axum::response::IntoResponse::into_response((
+                      axum::http::StatusCode::UNPROCESSABLE_ENTITY,
+                      axum::Json(errors),
+                    ))
+                  },
+                };
+                // An optional body is validated when present
+                prelude.push(if p.optional {
+                  quote! {
+                    if let Some(Err(errors)) = #name.as_ref().map(validator::Validate::validate) {
+                      return #rejection;
+                    }
+                  }
+                } else {
+                  quote! {
+                    if let Err(errors) = validator::Validate::validate(&#name) {
+                      return #rejection;
+                    }
+                  }
+                });
+              }
+            }
+          }
+
+          // Resolve the principal for role/permission checks
+          if !requirements.is_empty() {
+            let Some(principal_ty) = &controller_config.principal else {
+              abort_call_site!(
+                "Route '{}' uses #[requires] but the controller has no principal type. \
+                 Add 'principal = YourPrincipal' to the #[controller(...)] attribute",
+                route_info.path
+              );
+            };
+            let state_ty = match state_type {
+              Some(ty) => quote! { #ty },
+              None => quote! { () },
+            };
+            state_params.push(quote! {
+              __principal: ::std::result::Result<
+                #principal_ty,
+                <#principal_ty as axum::extract::FromRequestParts<#state_ty>>::Rejection,
+              >
+            });
+            let checks: Vec<_> = requirements
+              .roles
+              .iter()
+              .map(|role| quote! { __principal.has_role(#role) })
+              .chain(
+                requirements
+                  .permissions
+                  .iter()
+                  .map(|permission| quote! { __principal.has_permission(#permission) }),
+              )
+              .collect();
+            // Authorization runs before any other early answer
+            prelude.insert(0, quote! {
+              let __principal = match __principal {
+                Ok(principal) => principal,
+                Err(_) => {
+                  return axum::response::IntoResponse::into_response(axum::http::StatusCode::UNAUTHORIZED);
+                }
+              };
+              if !(#(#checks)&&*) {
+                return axum::response::IntoResponse::into_response(axum::http::StatusCode::FORBIDDEN);
+              }
+            });
+          }
+
+          // Result handlers are matched so success and error responses are decorated separately
+          let splits_result = crate::parser::returns_result(&method.sig)
+            && (needs_header_wrapping || has_error_response_config(&route_info, controller_config));
+
+          // Route-level headers override controller-level ones. Computed values are resolved
+          // before the handler runs
+          for header in &route_info.response_headers {
+            let overridden = controller_config.response_headers.iter().any(|controller| {
+              controller.name == header.name
+                && controller.policy == Some(HeaderPolicy::Override)
+                && !matches!(controller.value, HeaderValueSource::With(_))
+            });
+            if overridden {
+              emit_call_site_warning!(
+                "Header '{}' on '{}' is replaced by the controller's override policy for the same header",
+                header.name,
+                handler_name
+              );
+            }
+          }
+          let mut success_headers = HeaderParts::merge(&route_info.response_headers, "__header");
+          let error_headers = if splits_result {
+            HeaderParts::merge(
+              controller_config
+                .error_headers
+                .iter()
+                .chain(&route_info.error_headers),
+              "__error_header",
+            )
+          } else {
+            HeaderParts::default()
+          };
+
+          // The controller content_type is applied by the header layer around the router
+          if let Some(ct) = &route_info.content_type {
+            success_headers
+              .static_pairs
+              .insert(0, quote! { (axum::http::header::CONTENT_TYPE, #ct) });
+          }
+          if let Some(filename) = &route_info.filename {
+            let disposition = format!("attachment; filename=\"{}\"", filename);
+            success_headers
+              .static_pairs
+              .push(quote! { (axum::http::header::CONTENT_DISPOSITION, #disposition) });
+          }
+
+          // Serialized return values need a concrete type. The negotiated format is chosen
+          // from the Accept header
+          let negotiates =
+            !super::helpers::negotiated_formats(&route_info, controller_config).is_empty();
+          if negotiates || route_info.respond.is_some() {
+            if let Some(syn::Type::ImplTrait(_)) = crate::parser::success_type(&method.sig) {
+              emit_call_site_error!(
+                "negotiate and respond on '{}' require a concrete return type that implements serde::Serialize, not impl Trait",
+                handler_name
+              );
+            }
+          }
+          if negotiates {
+            request_parts_params.insert("HeaderParam");
+          }
+          let xml_root = route_info.xml_root.as_deref().unwrap_or("items");
+          if route_info.xml_root.is_some()
+            && route_info.respond != Some(ResponseFormat::Xml)
+            && !super::helpers::negotiated_formats(&route_info, controller_config)
+              .contains(&ResponseFormat::Xml)
+          {
+            emit_call_site_error!(
+              "xml_root on '{}' has no effect: the route does not respond with xml",
+              handler_name
+            );
+          }
+
+          if success_headers.uses_request || error_headers.uses_request {
+            let state_ty = state_type.map_or_else(|| quote! { () }, |ty| quote! { #ty });
+            state_params.push(quote! {
+              axum::extract::State(__state): axum::extract::State<#state_ty>
+            });
+            request_parts_params.insert("RequestParts");
+          }
+          prelude.append(&mut success_headers.statements);
+          prelude.extend(error_headers.statements.iter().cloned());
+
           // Add parameters in the correct order for axum
           wrapper_params.extend(state_params);
           wrapper_params.extend(request_parts_params.iter().map(|s| match *s {
             "HeaderParam" => quote! { headers: axum::http::HeaderMap },
             "CookieParam" => quote! { cookies: axum_extra::extract::CookieJar },
             "SessionParam" => quote! { mut session: tower_sessions::Session },
+            "RequestParts" => quote! { __parts: axum::http::request::Parts },
             _ => quote! {},
           }));
           wrapper_params.extend(body_params);
           wrapper_params.extend(other_params);
+          if route_info.ws.is_some() {
+            wrapper_params.push(quote! { __upgrade: axum::extract::ws::WebSocketUpgrade });
+          }
+
+          // Early answers from the prelude force a concrete response type
+          let returns_response = !prelude.is_empty() || splits_result || route_info.ws.is_some();
+          let wrapper_return_type = if returns_response {
+            quote! { -> axum::response::Response }
+          } else if needs_header_wrapping {
+            quote! { -> impl axum::response::IntoResponse }
+          } else {
+            quote! { #return_type }
+          };
 
           let wrapper_signature = quote! {
             #async_token fn #wrapper_name(#(#wrapper_params),*) #wrapper_return_type
           };
 
-          // Merge controller and route headers (route-level overrides controller-level)
-          let mut merged_headers: HashMap<&str, &str> = HashMap::with_capacity(
-            controller_config.response_headers.len() + route_info.response_headers.len(),
-          );
+          let call = quote! { Self::#handler_name(#(#call_args),*)#await_token };
 
-          // Add controller headers first
-          for (name, value) in &controller_config.response_headers {
-            merged_headers.insert(name.as_str(), value.as_str());
-          }
-
-          // Route-level headers override controller-level
-          for (name, value) in &route_info.response_headers {
-            merged_headers.insert(name.as_str(), value.as_str());
-          }
-
-          // Build header additions from merged headers
-          let header_additions: Vec<_> = merged_headers
-            .iter()
-            .map(|(name, value)| {
-              quote! { (axum::http::header::HeaderName::from_static(#name), #value) }
-            })
-            .collect();
-
-          // Use route content_type if specified, otherwise use controller content_type
-          let final_content_type = route_info
-            .content_type
+          // A Location header defaults the success status to 201 Created
+          let success_status = route_info
+            .status
+            .or(route_info.location.as_ref().map(|_| 201));
+          let location = route_info
+            .location
             .as_ref()
-            .or(controller_config.content_type.as_ref());
-
-          let wrapper_body = if let Some(ref ct) = final_content_type {
-            if !header_additions.is_empty() {
+            .map(|template| location_statement(template, &method.sig));
+          // Event and streaming routes write items as they arrive and negotiating routes
+          // serialize the value by Accept. Otherwise a bare id (which may not
+          // implement IntoResponse) is sent back as text
+          let formats = super::helpers::negotiated_formats(&route_info, controller_config);
+          let ok_value = match (
+            &location,
+            route_info.respond,
+            route_info.stream,
+            &route_info.sse,
+          ) {
+            (_, _, _, Some(sse)) => sse_response(sse, crate::parser::stream_item_type(&method.sig)),
+            (_, _, Some(format), _) => streamed_response(format),
+            (_, Some(format), _, _) => {
+              let media_type = format.media_types()[0];
+              let attempt = serialized_response(format, media_type, xml_root);
               quote! {
-                let response = Self::#handler_name(#(#call_args),*)#await_token;
-                (
-                  [
-                    (axum::http::header::CONTENT_TYPE, #ct),
-                    #(#header_additions),*
-                  ],
-                  response
-                )
+                #attempt.unwrap_or_else(|| Self::__route_controller_unserializable(#media_type))
               }
-            } else {
-              quote! {
-                let response = Self::#handler_name(#(#call_args),*)#await_token;
-                ([(axum::http::header::CONTENT_TYPE, #ct)], response)
+            }
+            _ if !formats.is_empty() => negotiated_response(formats, xml_root),
+            (Some((_, true)), _, _, _) => quote! { ::std::string::ToString::to_string(&value) },
+            _ => quote! { value },
+          };
+          let location = location.map(|(statement, _)| statement);
+          let mut success_parts = success_headers.parts();
+          if location.is_some() {
+            success_parts.push(quote! { [(axum::http::header::LOCATION, __location)] });
+          }
+          let ok_response = decorate_response(ok_value, success_status, &success_parts);
+
+          let wrapper_body = if let Some(ws) = &route_info.ws {
+            ws_upgrade(ws, socket, &call, &method.sig)
+          } else if splits_result {
+            let error_status = route_info.error_status.or(controller_config.error_status);
+            let convert_error = route_info
+              .error
+              .as_ref()
+              .or(controller_config.error.as_ref())
+              .map(
+                |error_ty| quote! { let error = ::std::convert::Into::<#error_ty>::into(error); },
+              );
+            let err_response =
+              decorate_response(quote! { error }, error_status, &error_headers.parts());
+            quote! {
+              match #call {
+                Ok(value) => {
+                  #location
+                  axum::response::IntoResponse::into_response(#ok_response)
+                }
+                Err(error) => {
+                  #convert_error
+                  axum::response::IntoResponse::into_response(#err_response)
+                }
               }
             }
-          } else if !header_additions.is_empty() {
+          } else if needs_header_wrapping {
             quote! {
-              let response = Self::#handler_name(#(#call_args),*)#await_token;
-              ([#(#header_additions),*], response)
+              let value = #call;
+              #location
+              #ok_response
             }
           } else {
+            call
+          };
+
+          let wrapper_body = if returns_response {
             quote! {
-              Self::#handler_name(#(#call_args),*)#await_token
+              #(#prelude)*
+              axum::response::IntoResponse::into_response({ #wrapper_body })
             }
+          } else {
+            wrapper_body
           };
 
           wrappers.push(quote! {
@@ -307,3 +885,486 @@ pub fn generate_wrapper_functions(
 
   wrappers
 }
+
+/// Serializes `value` into the format among `formats` that `Accept` prefers, moving on to the
+/// next acceptable one when serialization fails. Answers 406 when no format is acceptable and
+/// 500 when all of them failed. Adds `Vary: Accept`, as the body depends on that header
+fn negotiated_response(formats: &[ResponseFormat], xml_root: &str) -> TokenStream {
+  let offered: Vec<&str> = formats
+    .iter()
+    .flat_map(|format| format.media_types().iter().copied())
+    .collect();
+  let mut next_index = 0;
+  let arms = formats.iter().map(|format| {
+    let indices: Vec<usize> = (next_index..next_index + format.media_types().len()).collect();
+    next_index += indices.len();
+    let attempt = serialized_response(*format, quote! { __OFFERED[index] }, xml_root);
+    quote! { index @ (#(#indices)|*) => #attempt, }
+  });
+
+  quote! {
+    {
+      const __OFFERED: &[&str] = &[#(#offered),*];
+      let mut __failed = None;
+      let mut __response = None;
+      for index in Self::__route_controller_ranked(&headers, __OFFERED) {
+        __response = match index {
+          #(#arms)*
+          _ => None,
+        };
+        if __response.is_some() {
+          break;
+        }
+        __failed.get_or_insert(__OFFERED[index]);
+      }
+      let mut __response = __response.unwrap_or_else(|| match __failed {
+        Some(media_type) => Self::__route_controller_unserializable(media_type),
+        None => axum::response::IntoResponse::into_response(axum::http::StatusCode::NOT_ACCEPTABLE),
+      });
+      __response.headers_mut().append(
+        axum::http::header::VARY,
+        axum::http::HeaderValue::from_static("accept"),
+      );
+      __response
+    }
+  }
+}
+
+/// Serializes `value` into `format`, sent with the `media_type` expression as `Content-Type`.
+/// Evaluates to `None` when serialization fails
+fn serialized_response(
+  format: ResponseFormat,
+  media_type: impl quote::ToTokens,
+  xml_root: &str,
+) -> TokenStream {
+  let serialized = match format {
+    // axum answers 500 itself when JSON serialization fails
+    ResponseFormat::Json => {
+      return quote! {
+        {
+          let response = axum::response::IntoResponse::into_response(axum::Json(&value));
+          (!response.status().is_server_error()).then_some(response)
+        }
+      };
+    }
+    ResponseFormat::Xml => quote! { Self::__route_controller_xml(&value, #xml_root) },
+    ResponseFormat::MsgPack => quote! { rmp_serde::to_vec_named(&value) },
+    ResponseFormat::Cbor => quote! {
+      {
+        let mut buffer = ::std::vec::Vec::new();
+        ciborium::into_writer(&value, &mut buffer).map(|()| buffer)
+      }
+    },
+    // CSV writes one record per item of a collection
+    ResponseFormat::Csv => quote! {
+      {
+        let mut writer = csv::Writer::from_writer(::std::vec::Vec::new());
+        ::std::iter::IntoIterator::into_iter(&value)
+          .try_for_each(|record| writer.serialize(record))
+          .map_err(|_| ())
+          .and_then(|()| writer.into_inner().map_err(|_| ()))
+      }
+    },
+  };
+  quote! { Self::__route_controller_encoded(#media_type, #serialized) }
+}
+
+/// Buffers the request body of an `Option<T>` parameter, leaving it `None` when the body is
+/// empty. Otherwise the request is rebuilt around the buffer for the extractor, so content type
+/// checks and rejections match the required parameter
+fn optional_body_extraction(
+  binding: &syn::Ident,
+  extracted_ty: TokenStream,
+  pattern: TokenStream,
+  on_buffer_rejection: TokenStream,
+  on_rejection: TokenStream,
+) -> (TokenStream, TokenStream) {
+  let param = quote! { #binding: axum::extract::Request };
+  let extraction = quote! {
+    let #binding = {
+      let (parts, body) = #binding.into_parts();
+      let buffered = <axum::body::Bytes as axum::extract::FromRequest<()>>::from_request(
+        axum::extract::Request::from_parts(parts.clone(), body),
+        &(),
+      )
+      .await;
+      let bytes = match buffered {
+        Ok(bytes) => bytes,
+        Err(rejection) => return #on_buffer_rejection,
+      };
+      if bytes.is_empty() {
+        None
+      } else {
+        let request = axum::extract::Request::from_parts(parts, axum::body::Body::from(bytes));
+        match <#extracted_ty as axum::extract::FromRequest<()>>::from_request(request, &()).await {
+          Ok(#pattern) => Some(value),
+          Err(rejection) => return #on_rejection,
+        }
+      }
+    };
+  };
+  (param, extraction)
+}
+
+/// Builds the value of a `HeaderParam`, `CookieParam` or `SessionParam` parameter from the
+/// `headers`, `cookies` or `session` in scope. Route wrappers and `#[rpc]` adapters share it, so
+/// both map names and missing values alike: an `Option<...>` parameter receives `None`, others
+/// their default. Returns `None` for other extractors
+pub(super) fn request_parts_value(
+  extractor_type: crate::parser::ExtractorType,
+  name: &syn::Ident,
+  ty: &syn::Type,
+) -> Option<TokenStream> {
+  let optional = crate::parser::option_inner_type(ty).is_some();
+  let value = match extractor_type {
+    crate::parser::ExtractorType::HeaderParam => {
+      // Convert snake_case to kebab-case for header names (e.g., content_type -> content-type)
+      let header_name = name.to_string().replace('_', "-");
+      quote! {
+        headers.get(#header_name)
+          .and_then(|v| v.to_str().ok())
+          .map(::std::string::ToString::to_string)
+      }
+    }
+    crate::parser::ExtractorType::CookieParam => {
+      let name_str = name.to_string();
+      quote! { cookies.get(#name_str).map(|c| c.value().to_string()) }
+    }
+    crate::parser::ExtractorType::SessionParam => {
+      // Session.get() returns a Future, so we need to await it
+      let name_str = name.to_string();
+      let value_ty = crate::parser::option_inner_type(ty).unwrap_or(ty);
+      quote! { session.get::<#value_ty>(#name_str).await.ok().flatten() }
+    }
+    _ => return None,
+  };
+  Some(if optional {
+    value
+  } else {
+    quote! { #value.unwrap_or_default() }
+  })
+}
+
+/// Completes the WebSocket handshake once the extractors and checks have passed, then runs the
+/// handler on the upgraded socket
+fn ws_upgrade(
+  ws: &WsConfig,
+  socket: Option<&syn::Ident>,
+  call: &TokenStream,
+  sig: &syn::Signature,
+) -> TokenStream {
+  let handler_name = &sig.ident;
+  // The handler runs after the 101 answer, so a returned value (such as an Err) has nowhere to go
+  let returns_unit = match &sig.output {
+    syn::ReturnType::Default => true,
+    syn::ReturnType::Type(_, ty) => {
+      matches!(&**ty, syn::Type::Tuple(tuple) if tuple.elems.is_empty())
+    }
+  };
+  if !returns_unit {
+    emit_call_site_error!(
+      "#[ws] handler '{}' must return (): it runs after the upgrade, when no response can be sent. \
+       Handle errors inside the handler, e.g. by closing the socket",
+      handler_name
+    );
+  }
+  let Some(socket) = socket else {
+    emit_call_site_error!(
+      "#[ws] handler '{}' needs a parameter of type axum::extract::ws::WebSocket for the upgraded connection",
+      handler_name
+    );
+    return quote! { axum::http::StatusCode::INTERNAL_SERVER_ERROR };
+  };
+  let protocols = (!ws.protocols.is_empty()).then(|| {
+    let protocols = &ws.protocols;
+    quote! { .protocols([#(#protocols),*]) }
+  });
+  let max_message_size = ws
+    .max_message_size
+    .map(|size| quote! { .max_message_size(#size) });
+  quote! {
+    __upgrade #protocols #max_message_size.on_upgrade(move |#socket| async move {
+      #call;
+    })
+  }
+}
+
+/// Sends the items of the returned stream as Server-Sent Events. `Event` items are passed on;
+/// other items become JSON `data:` fields, with `event:` and `id:` read from the item
+fn sse_response(config: &SseConfig, item_ty: Option<&syn::Type>) -> TokenStream {
+  let events = if item_ty.is_some_and(|ty| crate::parser::is_type_named(ty, "Event")) {
+    if config.event.is_some() || config.id.is_some() {
+      emit_call_site_error!(
+        "sse event and id are read from serialized items. Set them on the returned Event values instead"
+      );
+    }
+    quote! {
+      futures_util::StreamExt::map(value, ::std::result::Result::<_, ::std::convert::Infallible>::Ok)
+    }
+  } else {
+    // Values read from items are checked first: the Event setters panic on line breaks (and on
+    // NUL in ids), so an invalid value ends the stream with an error instead
+    let set_field = |field: &SseField, setter: &str, forbidden: &[char]| {
+      let setter = syn::Ident::new(setter, proc_macro2::Span::call_site());
+      match field {
+        SseField::Constant(constant) => quote! { let event = event.#setter(#constant); },
+        SseField::Field(segments) => {
+          let accessors = field_accessors(segments);
+          let message = format!("sse {} read from the item contains {:?}", setter, forbidden);
+          quote! {
+            let value = ::std::string::ToString::to_string(&item #(#accessors)*);
+            if value.contains([#(#forbidden),*]) {
+              return Err(axum::Error::new(#message));
+            }
+            let event = event.#setter(value);
+          }
+        }
+      }
+    };
+    let event_name = config
+      .event
+      .as_ref()
+      .map(|field| set_field(field, "event", &['\r', '\n']));
+    let id = config
+      .id
+      .as_ref()
+      .map(|field| set_field(field, "id", &['\r', '\n', '\0']));
+    quote! {
+      futures_util::StreamExt::map(value, |item| {
+        let event = axum::response::sse::Event::default();
+        #event_name
+        #id
+        event.json_data(&item)
+      })
+    }
+  };
+  let keep_alive = config.keep_alive_ms.map(|ms| {
+    quote! {
+      .keep_alive(
+        axum::response::sse::KeepAlive::new().interval(::std::time::Duration::from_millis(#ms)),
+      )
+    }
+  });
+  quote! {
+    axum::response::IntoResponse::into_response(axum::response::sse::Sse::new(#events)#keep_alive)
+  }
+}
+
+/// Writes the items of the returned stream into the body as they arrive, one chunk per item.
+/// A serialization error ends the body early
+fn streamed_response(format: StreamFormat) -> TokenStream {
+  let media_type = format.media_type();
+  let chunks = match format {
+    StreamFormat::Ndjson => quote! {
+      futures_util::StreamExt::map(value, |item| {
+        serde_json::to_vec(&item).map(|mut line| {
+          line.push(b'\n');
+          line
+        })
+      })
+    },
+    // Elements after the first are prefixed with a comma, between the brackets
+    StreamFormat::JsonArray => quote! {
+      {
+        let elements = futures_util::StreamExt::map(
+          futures_util::StreamExt::enumerate(value),
+          |(index, item)| {
+            serde_json::to_vec(&item).map(|element| {
+              if index == 0 {
+                element
+              } else {
+                [b",".as_slice(), &element].concat()
+              }
+            })
+          },
+        );
+        let open = futures_util::stream::once(::std::future::ready(
+          ::std::result::Result::<_, serde_json::Error>::Ok(b"[".to_vec()),
+        ));
+        let close = futures_util::stream::once(::std::future::ready(
+          ::std::result::Result::<_, serde_json::Error>::Ok(b"]".to_vec()),
+        ));
+        futures_util::StreamExt::chain(futures_util::StreamExt::chain(open, elements), close)
+      }
+    },
+    // The header row is written with the first record
+    StreamFormat::Csv => quote! {
+      futures_util::StreamExt::map(
+        futures_util::StreamExt::enumerate(value),
+        |(index, item)| -> ::std::result::Result<::std::vec::Vec<u8>, axum::BoxError> {
+          let mut writer = csv::WriterBuilder::new()
+            .has_headers(index == 0)
+            .from_writer(::std::vec::Vec::new());
+          writer.serialize(&item)?;
+          writer.into_inner().map_err(|error| error.into_error().into())
+        },
+      )
+    },
+  };
+  quote! {
+    axum::response::IntoResponse::into_response((
+      [(axum::http::header::CONTENT_TYPE, #media_type)],
+      axum::body::Body::from_stream(#chunks),
+    ))
+  }
+}
+
+/// Extracts `Result<Extractor<T>, Rejection>` and returns the wrapper parameter together with a
+/// prelude statement that unwraps it, answering with the mapped rejection on failure
+fn mapped_extraction(
+  binding: &syn::Ident,
+  extractor: TokenStream,
+  inner_ty: TokenStream,
+  pattern: TokenStream,
+  rejection_ty: TokenStream,
+  mapper: &TokenStream,
+) -> (TokenStream, TokenStream) {
+  let param = quote! {
+    #binding: ::std::result::Result<#extractor<#inner_ty>, #rejection_ty>
+  };
+  let unwrap = quote! {
+    let #extractor(#pattern) = match #binding {
+      Ok(value) => value,
+      Err(rejection) => return #mapper,
+    };
+  };
+  (param, unwrap)
+}
+
+/// Response headers split into `IntoResponseParts` arrays: static `(HeaderName, &str)` pairs
+/// and `(HeaderName, HeaderValue)` pairs bound by `statements` from expressions and `with` fns
+#[derive(Default)]
+struct HeaderParts {
+  static_pairs: Vec<TokenStream>,
+  dynamic_pairs: Vec<TokenStream>,
+  statements: Vec<TokenStream>,
+  /// A `with = ...` fn needs the request parts and router state
+  uses_request: bool,
+}
+
+impl HeaderParts {
+  /// Merges headers given controller-level first (later entries with the same name win),
+  /// binding computed values to `{prefix}_{index}` variables
+  fn merge<'a>(headers: impl IntoIterator<Item = &'a ResponseHeader>, prefix: &str) -> Self {
+    let mut merged: Vec<(&str, &HeaderValueSource)> = Vec::new();
+    for header in headers {
+      match merged
+        .iter_mut()
+        .find(|(existing, _)| *existing == header.name.as_str())
+      {
+        Some(entry) => entry.1 = &header.value,
+        None => merged.push((header.name.as_str(), &header.value)),
+      }
+    }
+
+    let mut parts = HeaderParts::default();
+    for (index, (name, value)) in merged.into_iter().enumerate() {
+      let header_name = quote! { axum::http::header::HeaderName::from_static(#name) };
+      let computed = match value {
+        HeaderValueSource::Static(value) => {
+          parts.static_pairs.push(quote! { (#header_name, #value) });
+          continue;
+        }
+        HeaderValueSource::Expr(expr) => quote! { #expr },
+        HeaderValueSource::With(func) => {
+          parts.uses_request = true;
+          quote! { #func(&__parts, &__state) }
+        }
+      };
+      let binding = syn::Ident::new(
+        &format!("{}_{}", prefix, index),
+        proc_macro2::Span::call_site(),
+      );
+      parts.statements.push(quote! {
+        let #binding = match <axum::http::HeaderValue as ::std::convert::TryFrom<_>>::try_from(#computed) {
+          Ok(value) => value,
+          Err(_) => {
+            return axum::response::IntoResponse::into_response(axum::http::StatusCode::INTERNAL_SERVER_ERROR);
+          }
+        };
+      });
+      parts
+        .dynamic_pairs
+        .push(quote! { (#header_name, #binding) });
+    }
+    parts
+  }
+
+  /// Returns the non-empty header arrays as response parts
+  fn parts(&self) -> Vec<TokenStream> {
+    [&self.static_pairs, &self.dynamic_pairs]
+      .into_iter()
+      .filter(|pairs| !pairs.is_empty())
+      .map(|pairs| quote! { [#(#pairs),*] })
+      .collect()
+  }
+}
+
+/// Wraps a response value in a `(status, parts..., value)` tuple, omitting empty parts
+fn decorate_response(
+  value: TokenStream,
+  status: Option<u16>,
+  parts: &[TokenStream],
+) -> TokenStream {
+  let status = status.map(|code| {
+    quote! { axum::http::StatusCode::from_u16(#code).expect("status code validated at compile time"), }
+  });
+  quote! { (#status #(#parts,)* #value) }
+}
+
+/// Builds the `__location` binding by formatting the template with fields of the returned
+/// `value`, or with the value itself when the handler returns a bare id, each percent-encoded as
+/// a path segment. The flag is true in the latter case
+fn location_statement(template: &LocationTemplate, sig: &syn::Signature) -> (TokenStream, bool) {
+  let format = &template.format;
+  let returns_id = match crate::parser::success_type(sig) {
+    Some(syn::Type::ImplTrait(_)) | None => {
+      emit_call_site_error!(
+        "location on '{}' requires a concrete return type to read placeholders from, not impl Trait",
+        sig.ident
+      );
+      return (quote! {}, false);
+    }
+    Some(ty) => crate::parser::is_scalar_type(ty),
+  };
+
+  let args: Vec<TokenStream> = if returns_id {
+    if template.fields.len() != 1 {
+      emit_call_site_error!(
+        "location on '{}' returns a bare id, so the template must have exactly one placeholder",
+        sig.ident
+      );
+    }
+    vec![quote! { Self::__route_controller_path_segment(&value) }]
+  } else {
+    template
+      .fields
+      .iter()
+      .map(|segments| {
+        let accessors = field_accessors(segments);
+        quote! { Self::__route_controller_path_segment(&value #(#accessors)*) }
+      })
+      .collect()
+  };
+
+  (
+    quote! { let __location = format!(#format, #(#args),*); },
+    returns_id,
+  )
+}
+
+/// `.field` and `.0` accessors for a field path such as `["user", "id"]`
+fn field_accessors(segments: &[String]) -> impl Iterator<Item = TokenStream> + '_ {
+  segments
+    .iter()
+    .map(|segment| match segment.parse::<usize>() {
+      Ok(index) => {
+        let index = syn::Index::from(index);
+        quote! { .#index }
+      }
+      Err(_) => {
+        let field = syn::Ident::new(segment, proc_macro2::Span::call_site());
+        quote! { .#field }
+      }
+    })
+}
@@ -82,21 +82,38 @@
 //! - **Built-in extractors**: `Path`, `Query`, `Json`, `Form`, `Bytes`, `Text`, `Html`, `Xml`, `JavaScript`, `State`
 //! - **Response headers**: `header()` and `content_type()` attributes for custom response headers
 //! - **Middleware support**: Apply middleware at the controller level
+//! - **CORS**: `cors(...)` on the controller, with allowed methods derived from its routes
+//! - **JSON-RPC 2.0**: `#[rpc_controller]` serves its `#[rpc]` methods from a single POST endpoint
 //! - **Feature-gated extractors**:
-//!   - `headers` - Enable `HeaderParam` extractor (extracts from request headers)
+//!   - `headers` - Enable `HeaderParam`, `BearerToken` and `BasicAuth` extractors (extract from request headers)
 //!   - `cookies` - Enable `CookieParam` extractor (requires axum-extra with cookie feature)
 //!   - `sessions` - Enable `SessionParam` extractor (requires tower-sessions)
+//!   - `jwt` - Enable `Jwt` extractor (requires jsonwebtoken)
+//!   - `xml` - Enable `XmlBody` extractor (requires quick-xml with the serialize feature)
+//!   - `msgpack` / `cbor` - Enable `MsgPack` / `Cbor` extractors (require rmp-serde / ciborium)
+//!   - `json_patch` - Enable `JsonPatch` extractor (requires json-patch)
+//!   - `csv` - Enable `Csv` extractor (requires csv)
+//! - **Response formats**: `xml`, `msgpack`, `cbor` and `csv` features add formats to `negotiate`,
+//!   `respond` and `stream = csv` (requires quick-xml, rmp-serde, ciborium and csv)
 //!
 //! ## Extractor Types
 //!
 //! ### Request Body Extractors
 //! - **`Json`** - Extract JSON request body: `extract(data = Json)`
 //! - **`Form`** - Extract form data: `extract(data = Form)`
-//! - **`Bytes`** - Extract raw binary data: `extract(data = Bytes)` → `Vec<u8>`
+//! - **`Bytes`** - Extract raw binary data: `extract(data = Bytes)` → `Vec<u8>`, `Box<[u8]>` or
+//!   `axum::body::Bytes`
+//! - **`BodyStream`** - Stream the body: `extract(body = BodyStream)` → `axum::body::BodyDataStream`
+//!   or `axum::body::Body`, limited by `body_limit` (axum's 2 MB default without one)
 //! - **`Text`** - Extract plain text: `extract(content = Text)` → `String`
 //! - **`Html`** - Extract HTML content: `extract(content = Html)` → `String`
 //! - **`Xml`** - Extract XML content: `extract(content = Xml)` → `String`
 //! - **`JavaScript`** - Extract JavaScript content: `extract(code = JavaScript)` → `String`
+//! - **`MergePatch`** - Extract an `application/merge-patch+json` body (RFC 7386):
+//!   `extract(changes = MergePatch)` → `serde_json::Value`, applied with `Self::apply_merge_patch`
+//!
+//! Text extractors also accept `Cow<str>` and `Arc<str>`. `Json`, `Form` and text parameters
+//! written as `Option<...>` receive `None` for an empty body.
 //!
 //! ### URL Extractors
 //! - **`Path`** - Extract path parameters: `extract(id = Path)`
@@ -104,6 +121,8 @@
 //!
 //! ### Other Extractors
 //! - **`State`** - Extract application state: `extract(state = State)`
+//! - **`LastEventId`** - The `Last-Event-ID` header of a reconnecting `EventSource`:
+//!   `extract(last_id = LastEventId)` → `Option<String>` or `String`
 //!
 //! ### Feature-Gated Extractors
 //!
@@ -112,6 +131,22 @@
 //! converted to kebab-case (e.g., `user_agent` becomes `user-agent`).
 //! No additional dependencies required.
 //!
+//! #### BearerToken and BasicAuth (requires `headers` feature)
+//! Parse the `Authorization` header. `BearerToken` yields the token as a `String`,
+//! `BasicAuth` yields a `(user_id, password)` tuple. Missing or malformed credentials
+//! answer `401 Unauthorized` with a `WWW-Authenticate` challenge; declare the parameter
+//! as `Option<...>` to make authentication optional. `realm = "admin"` on the route or
+//! controller replaces the Basic challenge's default `realm="api"`.
+//!
+//! #### Jwt (requires `jwt` feature)
+//! Validates a bearer JSON Web Token and passes its deserialized claims to the handler.
+//! Requires adding `jsonwebtoken` and implementing `FromRef` for `jsonwebtoken::DecodingKey`
+//! and `jsonwebtoken::Validation` on the router state (declare it with `state = AppState`
+//! when no handler extracts it directly):
+//! ```toml
+//! jsonwebtoken = "9"
+//! ```
+//!
 //! #### CookieParam (requires `cookies` feature)
 //! Extracts values from cookies. Requires adding `axum-extra` with the `cookie` feature:
 //! ```toml
@@ -128,9 +163,119 @@
 //! Note: SessionParam requires the session middleware layer to be applied to your router.
 //! Refer to tower-sessions documentation for proper setup.
 //!
+//! #### MsgPack and Cbor (require `msgpack` and `cbor` features)
+//! Deserialize MessagePack (`application/msgpack`) and CBOR (`application/cbor`) bodies, with
+//! the same `400`/`415` rejections as `XmlBody`:
+//! ```toml
+//! rmp-serde = "1.3"
+//! ciborium = "0.2"
+//! ```
+//!
+//! #### JsonPatch (requires `json_patch` feature)
+//! Extracts RFC 6902 operations from an `application/json-patch+json` body into a
+//! `json_patch::Patch`. The controller gets `apply_json_patch(&target, &ops)`, which applies them
+//! atomically and answers `409 Conflict` for a failed `test` and `422 Unprocessable Entity` for
+//! other failures:
+//! ```toml
+//! json-patch = "4.2"
+//! ```
+//!
+//! #### Csv (requires `csv` feature)
+//! Deserializes a `text/csv` body into `Vec<T>`, one item per record after the header row.
+//! Failures answer `400 Bad Request` with a JSON body giving the `row`, `line` and `column`:
+//! ```toml
+//! csv = "1.3"
+//! ```
+//!
+//! #### XmlBody (requires `xml` feature)
+//! Deserializes an `application/xml` or `text/xml` body into a serde type, answering
+//! `400 Bad Request` with a JSON error body when parsing fails:
+//! ```toml
+//! quick-xml = { version = "0.37", features = ["serialize"] }
+//! ```
+//!
+//! ## Media Type Constraints
+//!
+//! `consumes = [...]` answers `415 Unsupported Media Type` for other request content types and
+//! `produces = [...]` answers `406 Not Acceptable` when `Accept` allows none of the listed types.
+//! The text extractors check their own media type unless marked `any_content_type`:
+//!
+//! ```ignore
+//! #[post("/import", extract(data = Bytes), consumes = ["application/xml"], produces = ["text/csv"])]
+//! async fn import(data: Vec<u8>) -> String { /* ... */ }
+//!
+//! #[post("/raw", extract(content = Text(any_content_type)))]
+//! async fn raw(content: String) -> String { content }
+//! ```
+//!
+//! ## Content Negotiation
+//!
+//! `negotiate` serializes the handler's return value into the format `Accept` prefers, among
+//! JSON and the enabled `xml`, `msgpack`, `cbor` and `csv` features. Responses carry `Vary: Accept`:
+//!
+//! ```ignore
+//! #[get("/{id}", extract(id = Path), negotiate)]
+//! async fn show(id: u32) -> Book { /* ... */ }
+//!
+//! #[get("/", negotiate(csv, json))]
+//! async fn list() -> Vec<Book> { /* ... */ }
+//! ```
+//!
+//! `respond = xml` always serializes into one format, ignoring `Accept`. XML wraps a returned
+//! sequence in an `<items>` element, renamed with `xml_root = "books"`.
+//!
+//! ## Streaming Responses
+//!
+//! `stream = ndjson`, `stream = json_array` or `stream = csv` sends the items of a returned
+//! `impl Stream<Item = T>` one chunk at a time (requires futures-util and serde_json):
+//!
+//! ```ignore
+//! #[get("/export", stream = ndjson)]
+//! async fn export() -> impl Stream<Item = Row> { /* ... */ }
+//! ```
+//!
+//! `filename = "export.csv"` on any route, such as a `stream = csv` one, sends the response as an
+//! attachment with that `Content-Disposition` filename.
+//!
+//! ## Server-Sent Events
+//!
+//! `#[sse("/events")]` registers a GET route sending the items of a returned
+//! `impl Stream<Item = T>` as events. `Event` items are sent as they are; other items become
+//! JSON `data:` with `event = field` and `id = field` read from them. `keep_alive = "30s"` sets
+//! the keep-alive interval, `keep_alive = false` disables it:
+//!
+//! ```ignore
+//! #[sse("/{room}", extract(room = Path, last_id = LastEventId), event = kind, id = seq)]
+//! async fn events(room: String, last_id: Option<String>) -> impl Stream<Item = Message> { /* ... */ }
+//! ```
+//!
+//! ## WebSockets
+//!
+//! `#[ws("/chat/{room}")]` registers a GET route that runs the declared extractors, then upgrades
+//! the connection and calls the handler with the `WebSocket` (requires axum's `ws` feature).
+//! `protocols = ["chat.v1"]` selects a subprotocol and `max_message_size = "64KB"` caps messages:
+//!
+//! ```ignore
+//! #[ws("/chat/{room}", extract(room = Path, user = SessionParam), protocols = ["chat.v1"])]
+//! async fn chat(socket: WebSocket, room: String, user: String) { /* ... */ }
+//! ```
+//!
+//! ## Body Size Limits
+//!
+//! `body_limit = "50MB"` on a route, or on the controller as a default, replaces axum's 2 MB
+//! limit for body extractors:
+//!
+//! ```ignore
+//! #[post("/upload", extract(data = Bytes), body_limit = "50MB")]
+//! async fn upload(data: Vec<u8>) -> String {
+//!     format!("Received {} bytes", data.len())
+//! }
+//! ```
+//!
 //! ## Response Headers
 //!
-//! Add custom headers to your responses:
+//! Add custom headers to your responses. Header names are lowercased automatically; invalid
+//! names, values and content types are compile errors:
 //!
 //! ```ignore
 //! #[get("/data", header("x-api-version", "1.0"))]
@@ -148,11 +293,27 @@
 //!     "Info with multiple headers".to_string()
 //! }
 //!
+//! // Constants, expressions and per-request functions of (&Parts, &State)
+//! #[get(
+//!     "/meta",
+//!     header("x-crate-version", env!("CARGO_PKG_VERSION")),
+//!     header("x-request-id", with = request_id)
+//! )]
+//! async fn get_meta() -> String {
+//!     "Meta".to_string()
+//! }
+//!
 //! // Custom content type
 //! #[get("/xml", content_type("application/xml"))]
 //! async fn get_xml() -> String {
 //!     r#"<?xml version="1.0"?><response>Hello</response>"#.to_string()
 //! }
+//!
+//! // 201 Created with a Location header filled in from the returned value
+//! #[post("/", extract(user = Json), location = "/users/{id}")]
+//! async fn create(user: NewUser) -> Json<User> {
+//!     Json(save(user))
+//! }
 //! ```
 //!
 //! ## Middleware
@@ -174,6 +335,102 @@
 //! }
 //! ```
 //!
+//! ## CORS
+//!
+//! `cors(...)` answers preflight requests before the controller middleware runs. With
+//! `methods = auto`, the allowed methods are those the controller declares routes for:
+//!
+//! ```ignore
+//! #[controller(
+//!     path = "/api",
+//!     middleware = require_auth,
+//!     cors(origins = ["https://app.example"], methods = auto, credentials)
+//! )]
+//! impl ApiController { /* ... */ }
+//! ```
+//!
+//! ## JSON-RPC
+//!
+//! `#[rpc_controller(path = "/rpc")]` dispatches JSON-RPC 2.0 calls, single or batched, to its
+//! `#[rpc]` methods by name. Parameters without an extractor are read from `params` by position
+//! or by name; `State`, `HeaderParam`, `CookieParam` and `SessionParam` work as on routes:
+//!
+//! ```ignore
+//! #[rpc_controller(path = "/rpc", state = AppState)]
+//! impl AccountRpc {
+//!     #[rpc(name = "accounts.balance", extract(state = State))]
+//!     async fn balance(state: AppState, account: String) -> Result<Balance, RpcError> {
+//!         /* ... */
+//!     }
+//! }
+//! ```
+//!
+//! Return values become the `result`. `Err` values that serialize to an object with an integer
+//! `code` and a string `message` become the `error`; others are sent as the `data` of a `-32000`
+//! server error. Notifications are never answered.
+//!
+//! ## Request Validation
+//!
+//! Run `validator::Validate` on extracted `Json`, `Form` and `Query` values. Failures answer
+//! `422 Unprocessable Entity` with field-by-field errors, or the response produced by the
+//! controller's `validation_error = ...` function:
+//!
+//! ```ignore
+//! #[post("/", extract(user = Json(validate)))]
+//! async fn create(user: NewUser) -> String {
+//!     format!("Created {}", user.name)
+//! }
+//! ```
+//!
+//! ## Rejection Mapping
+//!
+//! Convert `Json`, `Form`, `Query` and `Path` rejections into your own error type, which must
+//! implement `IntoResponse` and `From` for each rejection. A route-level `rejection = ...`
+//! overrides the controller-level one; a method marked `#[catch]` can be used instead:
+//!
+//! ```ignore
+//! #[controller(path = "/items", rejection = ApiError)]
+//! impl ItemController {
+//!     #[post("/", extract(item = Json))]
+//!     async fn create(item: Item) -> String {
+//!         format!("Created {}", item.name)
+//!     }
+//! }
+//! ```
+//!
+//! ## Error Handling
+//!
+//! With `#[controller(error = ApiError)]`, handlers returning `Result<T, E>` have their errors
+//! converted with `Into<ApiError>`. Route-level headers apply to `Ok` responses only; use
+//! `error_header(...)` and `error_status = ...` to decorate `Err` responses:
+//!
+//! ```ignore
+//! #[controller(path = "/items", error = ApiError, error_header("cache-control", "no-store"))]
+//! impl ItemController {
+//!     #[get("/{id}", extract(id = Path))]
+//!     async fn show(id: u32) -> Result<String, NotFound> {
+//!         find_name(id).ok_or(NotFound(id))
+//!     }
+//! }
+//! ```
+//!
+//! ## Authorization
+//!
+//! Require roles or permissions on handlers, resolved from a principal type that implements
+//! `FromRequestParts` and provides `has_role`/`has_permission`:
+//!
+//! ```ignore
+//! #[controller(path = "/admin", principal = CurrentUser)]
+//! #[requires(role = "admin")]
+//! impl AdminController {
+//!     #[get("/audit")]
+//!     #[requires(permission = "audit:read")]
+//!     async fn audit() -> &'static str {
+//!         "audit log"
+//!     }
+//! }
+//! ```
+//!
 //! ## Examples
 //!
 //! The crate includes comprehensive examples demonstrating different features:
@@ -240,6 +497,34 @@ pub fn controller(attr: TokenStream, item: TokenStream) -> TokenStream {
   controller::controller_impl(attr, item)
 }
 
+/// Turns an impl block into a JSON-RPC 2.0 endpoint: every `#[rpc]` method is one JSON-RPC
+/// method, dispatched by name from a single `POST` route at the controller's path.
+///
+/// ```ignore
+/// #[rpc_controller(path = "/rpc", state = AppState)]
+/// impl MathRpc {
+///     #[rpc]
+///     async fn add(a: i64, b: i64) -> i64 {
+///         a + b
+///     }
+///
+///     #[rpc(name = "users.get", extract(state = State, tenant = HeaderParam))]
+///     async fn get_user(state: AppState, tenant: String, id: u64) -> Result<User, RpcError> { /* ... */ }
+/// }
+/// ```
+#[proc_macro_attribute]
+#[proc_macro_error]
+pub fn rpc_controller(attr: TokenStream, item: TokenStream) -> TokenStream {
+  controller::rpc_controller_impl(attr, item)
+}
+
+/// Marks a method of an `#[rpc_controller]` as a JSON-RPC method. Parameters without an
+/// extractor are read from the call's `params`, by position or by name
+#[proc_macro_attribute]
+pub fn rpc(_: TokenStream, item: TokenStream) -> TokenStream {
+  item
+}
+
 #[proc_macro_attribute]
 pub fn get(_: TokenStream, item: TokenStream) -> TokenStream {
   item
@@ -279,3 +564,79 @@ pub fn put(_: TokenStream, item: TokenStream) -> TokenStream {
 pub fn trace(_: TokenStream, item: TokenStream) -> TokenStream {
   item
 }
+
+/// Registers a GET route that sends the items of the handler's `impl Stream` as Server-Sent
+/// Events. `Event` items are sent as they are; other items are serialized into JSON `data:`
+/// fields, with `event = ...` and `id = ...` read from item fields or given as strings:
+///
+/// ```ignore
+/// #[sse("/rooms/{room}/events", extract(room = Path, last_id = LastEventId), id = seq, keep_alive = "30s")]
+/// async fn events(room: String, last_id: Option<String>) -> impl Stream<Item = Message> { /* ... */ }
+/// ```
+#[proc_macro_attribute]
+pub fn sse(_: TokenStream, item: TokenStream) -> TokenStream {
+  item
+}
+
+/// Registers a GET route that upgrades the connection to a WebSocket. The declared extractors
+/// and checks run before the handshake; the handler then receives the upgraded socket:
+///
+/// ```ignore
+/// #[ws("/chat/{room}", extract(room = Path, user = SessionParam), protocols = ["chat.v1"], max_message_size = "64KB")]
+/// async fn chat(socket: WebSocket, room: String, user: String) { /* ... */ }
+/// ```
+#[proc_macro_attribute]
+pub fn ws(_: TokenStream, item: TokenStream) -> TokenStream {
+  item
+}
+
+/// Marks a controller method as the mapper for extractor rejections. The method receives the
+/// rejection's status code and message and returns any `IntoResponse`:
+///
+/// ```ignore
+/// #[catch]
+/// fn on_rejection(status: StatusCode, message: String) -> ApiError {
+///     ApiError::new(status, message)
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn catch(_: TokenStream, item: TokenStream) -> TokenStream {
+  item
+}
+
+/// Validates every `Json`, `Form` and `Query` parameter of a handler with `validator::Validate`
+/// after extraction. Equivalent to writing `Json(validate)` for each of them in `extract(...)`.
+#[proc_macro_attribute]
+pub fn validate(_: TokenStream, item: TokenStream) -> TokenStream {
+  item
+}
+
+/// Restricts a handler (or, placed below `#[controller]`, every handler) to principals
+/// holding the given roles and permissions.
+///
+/// The principal type is declared with `#[controller(principal = CurrentUser)]`. It must
+/// implement `FromRequestParts` and provide `has_role(&self, &str) -> bool` and
+/// `has_permission(&self, &str) -> bool`. A failed extraction answers `401 Unauthorized`,
+/// a failed check answers `403 Forbidden`.
+///
+/// ```ignore
+/// #[controller(path = "/orders", principal = CurrentUser)]
+/// #[requires(role = "staff")]
+/// impl OrderController {
+///     #[post("/", extract(order = Json))]
+///     #[requires(permission = "orders:write")]
+///     async fn create(order: Order) -> String {
+///         "created".to_string()
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+#[proc_macro_error]
+pub fn requires(_: TokenStream, item: TokenStream) -> TokenStream {
+  if syn::parse::<syn::ItemImpl>(item.clone()).is_ok() {
+    proc_macro_error::abort_call_site!(
+      "#[requires] on a controller must be placed below #[controller(...)] so the controller can read it"
+    );
+  }
+  item
+}
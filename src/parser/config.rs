@@ -2,142 +2,225 @@
 
 use proc_macro::TokenStream;
 use proc_macro_error::{emit_call_site_error, emit_call_site_warning};
-use syn::Path;
+use syn::{Path, Type};
+
+use super::cors::{CorsConfig, parse_cors};
+use super::http::{HeaderPolicy, HeaderValueSource, ResponseHeader};
+use super::negotiate::{ResponseFormat, parse_negotiate};
+use super::requirements::Requirements;
+use super::security::parse_security_headers;
+use super::utils::{
+  named_arg, parse_byte_size, parse_content_type, parse_header_calls, parse_media_types,
+  parse_realm, parse_status_code, reject_header_policies, split_top_level, string_value,
+};
 
 pub struct ControllerConfig {
   pub route_prefix: Option<String>,
   pub middlewares: Vec<Path>,
-  pub response_headers: Vec<(String, String)>,
+  /// Applied to every response by a layer
+  pub response_headers: Vec<ResponseHeader>,
   pub content_type: Option<String>,
+  /// Defaults to `Override`, replacing the content type set by the handler
+  pub content_type_policy: HeaderPolicy,
+  /// True when `security_headers` added a preset to `response_headers`
+  pub security_headers: bool,
+  /// Answers preflights and decorates cross-origin responses outside the controller middleware
+  pub cors: Option<CorsConfig>,
+  pub principal: Option<Path>,
+  /// Realm sent in the Basic challenge of routes without their own, `api` when unset
+  pub realm: Option<String>,
+  /// Maps `validator::ValidationErrors` to a response for `validate`d extractors
+  pub validation_error: Option<Path>,
+  /// Error type that extractor rejections are converted into
+  pub rejection: Option<Type>,
+  /// Controller method marked `#[catch]`, used to map rejections when no type is given
+  pub catch_handler: Option<syn::Ident>,
+  /// Error type that `Err` values of `Result` handlers are converted into
+  pub error: Option<Type>,
+  /// Headers added only to error responses of `Result` handlers
+  pub error_headers: Vec<ResponseHeader>,
+  /// Status code that replaces the error type's own status
+  pub error_status: Option<u16>,
+  /// Request media types accepted by routes with a body extractor and no `consumes` of their own
+  pub consumes: Option<Vec<String>>,
+  /// Response media types offered by routes without their own `produces`
+  pub produces: Option<Vec<String>>,
+  /// Formats routes serialize their return value into by `Accept`, unless they set `negotiate`
+  pub negotiate: Vec<ResponseFormat>,
+  /// Maximum request body size in bytes for routes without their own `body_limit`
+  pub body_limit: Option<usize>,
+  /// Router state type, when it cannot be inferred from a `State` extractor
+  pub state: Option<Type>,
+  /// Filled from `#[requires(...)]` attributes placed on the impl block
+  pub requirements: Requirements,
 }
 
 pub fn parse_controller_attributes(attr: &TokenStream) -> ControllerConfig {
-  let mut route_prefix: Option<String> = None;
-  let mut middlewares: Vec<Path> = Vec::with_capacity(2); // Most controllers have 0-2 middlewares
-  let mut response_headers: Vec<(String, String)> = Vec::with_capacity(4); // Typical controllers have 0-4 headers
-  let mut content_type: Option<String> = None;
-
   let attr_str = attr.to_string();
+  let args = split_top_level(&attr_str);
 
-  // Parse path and middleware using split
-  for arg in attr_str.split(',').map(str::trim) {
-    if arg.starts_with("path") {
-      let parts: Vec<&str> = arg.split("=").collect();
-      if parts.len() == 2 {
-        let mut value = parts[1].trim().replace("\"", "");
-        if value.is_empty() {
-          emit_call_site_warning!("Empty path value in controller attribute");
-        }
-        if !value.starts_with('/') {
-          value = format!("/{}", value);
-        }
-        log_verbose!("Parsed route prefix: [{}]", value);
-        route_prefix = Some(value);
-      } else {
-        emit_call_site_error!("Invalid path attribute format. Expected: path = \"/route\"");
+  let route_prefix = named_arg(&args, "path").map(|value| {
+    let mut value = string_value(value);
+    if value.is_empty() {
+      emit_call_site_warning!("Empty path value in controller attribute");
+    }
+    if !value.starts_with('/') {
+      value = format!("/{}", value);
+    }
+    log_verbose!("Parsed route prefix: [{}]", value);
+    value
+  });
+
+  // middleware may be given several times, applied in order
+  let mut middlewares: Vec<Path> = Vec::with_capacity(2); // Most controllers have 0-2 middlewares
+  for arg in &args {
+    let Some(("middleware", value)) = arg
+      .split_once('=')
+      .map(|(key, value)| (key.trim(), value.trim()))
+    else {
+      continue;
+    };
+    if value.is_empty() {
+      emit_call_site_error!("Empty middleware value in controller attribute");
+      continue;
+    }
+    match syn::parse_str::<Path>(value) {
+      Ok(middleware_path) => {
+        log_verbose!("Parsed middleware: [{}]", value);
+        middlewares.push(middleware_path);
       }
-    } else if arg.starts_with("middleware") {
-      let parts: Vec<&str> = arg.split("=").collect();
-      if parts.len() == 2 {
-        let value = parts[1].trim();
-        if value.is_empty() {
-          emit_call_site_error!("Empty middleware value in controller attribute");
-          continue;
-        }
-        match syn::parse_str::<Path>(value) {
-          Ok(middleware_path) => {
-            log_verbose!("Parsed middleware: [{}]", value);
-            middlewares.push(middleware_path);
-          }
-          Err(_) => {
-            emit_call_site_error!(
-              "Invalid middleware path '{}'. Expected a valid Rust path (e.g., my_middleware or module::middleware)",
-              value
-            );
-          }
-        }
-      } else {
+      Err(_) => {
         emit_call_site_error!(
-          "Invalid middleware attribute format. Expected: middleware = my_middleware"
+          "Invalid middleware path '{}'. Expected a valid Rust path (e.g., my_middleware or module::middleware)",
+          value
         );
       }
     }
   }
 
+  let rejection = named_arg(&args, "rejection")
+    .and_then(|value| parse_type_arg("rejection type", value, "ApiError"));
+  let error_status =
+    named_arg(&args, "error_status").and_then(|value| parse_status_code("error_status", value));
+  let error =
+    named_arg(&args, "error").and_then(|value| parse_type_arg("error type", value, "ApiError"));
+  let body_limit =
+    named_arg(&args, "body_limit").and_then(|value| parse_byte_size("body_limit", value));
+  let validation_error = named_arg(&args, "validation_error")
+    .and_then(|value| parse_path_arg("validation_error handler", value, "validation_response"));
+  let state =
+    named_arg(&args, "state").and_then(|value| parse_type_arg("state type", value, "AppState"));
+  let principal = named_arg(&args, "principal")
+    .and_then(|value| parse_path_arg("principal type", value, "CurrentUser or auth::CurrentUser"));
+  let realm = named_arg(&args, "realm").and_then(parse_realm);
+
   // Parse header(...) attributes
-  let mut search_pos = 0;
-  while let Some(header_start) = attr_str[search_pos..].find("header") {
-    let header_start = search_pos + header_start;
-    if let Some(paren_start) = attr_str[header_start..].find('(') {
-      let paren_start = header_start + paren_start + 1;
-      if let Some(paren_end) = attr_str[paren_start..].find(')') {
-        let header_content = &attr_str[paren_start..paren_start + paren_end];
-        // Parse header_name = "header_value" or (header_name, header_value)
-        if let Some(eq_pos) = header_content.find('=') {
-          let header_name = header_content[..eq_pos].trim().replace('"', "");
-          let header_value = header_content[eq_pos + 1..].trim().replace('"', "");
-          if header_name.is_empty() || header_value.is_empty() {
-            emit_call_site_warning!("Empty header name or value in controller header attribute");
-          }
-          log_verbose!(
-            "Parsed controller header: [{}: {}]",
-            header_name,
-            header_value
-          );
-          response_headers.push((header_name, header_value));
-        } else {
-          // Try parsing as tuple: ("name", "value")
-          let parts: Vec<&str> = header_content.split(',').collect();
-          if parts.len() == 2 {
-            let header_name = parts[0].trim().replace('"', "");
-            let header_value = parts[1].trim().replace('"', "");
-            if header_name.is_empty() || header_value.is_empty() {
-              emit_call_site_warning!("Empty header name or value in controller header attribute");
-            }
-            log_verbose!(
-              "Parsed controller header: [{}: {}]",
-              header_name,
-              header_value
-            );
-            response_headers.push((header_name, header_value));
-          } else {
-            emit_call_site_warning!(
-              "Invalid header attribute format in controller. Expected: header(\"name\", \"value\") or header(name = \"value\")"
-            );
-          }
-        }
-        search_pos = paren_start + paren_end + 1;
-      } else {
-        emit_call_site_warning!("Unclosed parenthesis in header attribute");
-        break;
-      }
-    } else {
-      break;
+  let mut response_headers = parse_header_calls(&attr_str, "header");
+  // Controller headers are applied by a layer, which runs without the router state
+  response_headers.retain(|header| {
+    let computed = matches!(header.value, HeaderValueSource::With(_));
+    if computed {
+      emit_call_site_error!(
+        "header(\"{}\", with = ...) is not supported on controllers: controller headers also reach rejections, 404/405 responses and middleware short-circuits, which run without the router state. Set it on the routes instead",
+        header.name
+      );
     }
-  }
+    !computed
+  });
+  let error_headers = parse_header_calls(&attr_str, "error_header");
+  reject_header_policies(&error_headers, "error_header(...)");
+
+  let cors = parse_cors(&args);
+  let consumes =
+    named_arg(&args, "consumes").and_then(|value| parse_media_types("consumes", value));
+  let produces =
+    named_arg(&args, "produces").and_then(|value| parse_media_types("produces", value));
+  let negotiate = parse_negotiate(&args).unwrap_or_default();
+
+  // security_headers presets default to strict; explicit header(...) calls of the same name win
+  let preset: Vec<ResponseHeader> = parse_security_headers(&args, Some("strict"))
+    .into_iter()
+    .filter(|entry| {
+      !response_headers
+        .iter()
+        .any(|header| header.name == entry.name)
+    })
+    .filter_map(|entry| {
+      Some(ResponseHeader {
+        name: entry.name.to_string(),
+        value: HeaderValueSource::Static(entry.value?),
+        policy: None,
+      })
+    })
+    .collect();
+  let security_headers = !preset.is_empty();
+  response_headers.splice(0..0, preset);
 
   // Parse content_type(...) attribute
-  if let Some(ct_start) = attr_str.find("content_type") {
-    if let Some(paren_start) = attr_str[ct_start..].find('(') {
-      let paren_start = ct_start + paren_start + 1;
-      if let Some(paren_end) = attr_str[paren_start..].find(')') {
-        let ct_content = &attr_str[paren_start..paren_start + paren_end];
-        let ct_value = ct_content.trim().replace('"', "");
-        if ct_value.is_empty() {
-          emit_call_site_warning!("Empty content_type value in controller attribute");
-        }
-        log_verbose!("Parsed controller content_type: [{}]", ct_value);
-        content_type = Some(ct_value);
-      } else {
-        emit_call_site_warning!("Unclosed parenthesis in content_type attribute");
-      }
-    }
-  }
+  let (content_type, content_type_policy) = match parse_content_type(&attr_str, "controller") {
+    Some((value, policy)) => (Some(value), policy.unwrap_or(HeaderPolicy::Override)),
+    None => (None, HeaderPolicy::Override),
+  };
 
   ControllerConfig {
     route_prefix,
     middlewares,
     response_headers,
     content_type,
+    content_type_policy,
+    security_headers,
+    cors,
+    principal,
+    realm,
+    validation_error,
+    rejection,
+    catch_handler: None,
+    error,
+    error_headers,
+    error_status,
+    consumes,
+    produces,
+    negotiate,
+    body_limit,
+    state,
+    requirements: Requirements::default(),
+  }
+}
+
+/// Parses the type given to a controller argument such as `state = AppState<Db>`
+fn parse_type_arg(what: &str, value: &str, example: &str) -> Option<Type> {
+  match syn::parse_str::<Type>(value) {
+    Ok(ty) => {
+      log_verbose!("Parsed {}: [{}]", what, value);
+      Some(ty)
+    }
+    Err(_) => {
+      emit_call_site_error!(
+        "Invalid {} '{}'. Expected a valid Rust type (e.g., {})",
+        what,
+        value,
+        example
+      );
+      None
+    }
+  }
+}
+
+/// Parses the path given to a controller argument such as `principal = auth::CurrentUser`
+fn parse_path_arg(what: &str, value: &str, example: &str) -> Option<Path> {
+  match syn::parse_str::<Path>(value) {
+    Ok(path) => {
+      log_verbose!("Parsed {}: [{}]", what, value);
+      Some(path)
+    }
+    Err(_) => {
+      emit_call_site_error!(
+        "Invalid {} '{}'. Expected a valid Rust path (e.g., {})",
+        what,
+        value,
+        example
+      );
+      None
+    }
   }
 }
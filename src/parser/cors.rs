@@ -0,0 +1,170 @@
+//! `cors(...)` controller configuration
+
+use proc_macro_error::emit_call_site_error;
+
+use super::http::normalize_header_name;
+use super::utils::{balanced_group, split_top_level, string_value};
+
+/// Methods accepted in an explicit `methods = [...]` list
+const METHODS: [&str; 9] = [
+  "GET", "HEAD", "POST", "PUT", "DELETE", "PATCH", "OPTIONS", "TRACE", "CONNECT",
+];
+
+/// A parsed `cors(...)` attribute
+#[derive(Debug)]
+pub struct CorsConfig {
+  /// `None` for `origins = any`
+  pub origins: Option<Vec<String>>,
+  /// `None` for `methods = auto`, resolved from the controller's routes
+  pub methods: Option<Vec<String>>,
+  /// Request headers allowed in preflights; `None` for `headers = any`, which mirrors the request
+  pub headers: Option<Vec<String>>,
+  /// Response headers readable by the browser
+  pub expose: Vec<String>,
+  pub credentials: bool,
+  /// Seconds a preflight may be cached for
+  pub max_age: Option<u64>,
+}
+
+/// Parses a `["a", "b"]` list of string literals, or returns `None` for `any`
+fn string_list(key: &str, value: &str) -> Option<Option<Vec<String>>> {
+  if value == "any" {
+    return Some(None);
+  }
+  match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+    Some(items) => Some(Some(
+      split_top_level(items)
+        .into_iter()
+        .map(string_value)
+        .collect(),
+    )),
+    None => {
+      emit_call_site_error!(
+        "Invalid cors {} '{}'. Expected a list such as [\"...\"] or any",
+        key,
+        value
+      );
+      None
+    }
+  }
+}
+
+/// Checks that an allowed origin is a serialized origin such as `https://app.example:8443`
+fn validate_origin(origin: &str) -> bool {
+  let valid = origin.split_once("://").is_some_and(|(scheme, host)| {
+    !scheme.is_empty()
+      && scheme
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b"+-.".contains(&b))
+      && !host.is_empty()
+      && host
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b"-.:[]".contains(&b))
+  });
+  if !valid {
+    emit_call_site_error!(
+      "Invalid cors origin '{}'. Expected scheme://host[:port] without a path (e.g., \"https://app.example\")",
+      origin
+    );
+  }
+  valid
+}
+
+/// Parses the `cors(...)` call among the top-level arguments of the controller attribute
+pub fn parse_cors(args: &[&str]) -> Option<CorsConfig> {
+  let rest = args.iter().find_map(|arg| {
+    arg
+      .strip_prefix("cors")
+      .map(str::trim_start)
+      .filter(|rest| rest.starts_with('('))
+  })?;
+  let Some(content) = balanced_group(rest, 0) else {
+    emit_call_site_error!("Unclosed parenthesis in cors attribute");
+    return None;
+  };
+
+  let mut cors = CorsConfig {
+    origins: None,
+    methods: None,
+    headers: Some(Vec::new()),
+    expose: Vec::new(),
+    credentials: false,
+    max_age: None,
+  };
+  let mut has_origins = false;
+  for entry in split_top_level(content) {
+    let (key, value) = match entry.split_once('=') {
+      Some((key, value)) => (key.trim(), value.trim()),
+      None => (entry, ""),
+    };
+    match key {
+      "origins" => {
+        has_origins = true;
+        if let Some(origins) = string_list(key, value) {
+          if let Some(origins) = &origins {
+            if !origins.iter().all(|origin| validate_origin(origin)) {
+              continue;
+            }
+          }
+          cors.origins = origins;
+        }
+      }
+      "methods" if value == "auto" => cors.methods = None,
+      "methods" => {
+        if let Some(Some(methods)) = string_list(key, value) {
+          let methods: Vec<String> = methods.iter().map(|m| m.to_ascii_uppercase()).collect();
+          if let Some(method) = methods.iter().find(|m| !METHODS.contains(&m.as_str())) {
+            emit_call_site_error!(
+              "Unknown cors method '{}'. Valid methods are: {}",
+              method,
+              METHODS.join(", ")
+            );
+          }
+          cors.methods = Some(methods);
+        }
+      }
+      "headers" | "expose" => {
+        let Some(list) = string_list(key, value) else {
+          continue;
+        };
+        let list = list.map(|names| {
+          names
+            .iter()
+            .filter_map(|name| normalize_header_name(name))
+            .collect::<Vec<_>>()
+        });
+        match (key, list) {
+          ("headers", list) => cors.headers = list,
+          (_, Some(list)) => cors.expose = list,
+          (_, None) => {
+            emit_call_site_error!("cors expose must list header names; 'any' is not supported")
+          }
+        }
+      }
+      "credentials" if value.is_empty() => cors.credentials = true,
+      "max_age" => match value.parse::<u64>() {
+        Ok(seconds) => cors.max_age = Some(seconds),
+        Err(_) => emit_call_site_error!(
+          "Invalid cors max_age '{}'. Expected a number of seconds",
+          value
+        ),
+      },
+      _ => emit_call_site_error!(
+        "Unknown cors option '{}'. Valid options are: origins, methods, headers, expose, credentials, max_age",
+        entry
+      ),
+    }
+  }
+
+  if !has_origins {
+    emit_call_site_error!("cors(...) requires origins = [\"https://...\"] or origins = any");
+  }
+  if cors.credentials && cors.origins.is_none() {
+    emit_call_site_error!(
+      "cors credentials cannot be combined with origins = any; list the allowed origins instead"
+    );
+  }
+
+  log_verbose!("Parsed cors: [{:?}]", cors);
+  Some(cors)
+}
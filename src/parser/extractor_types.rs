@@ -1,6 +1,6 @@
 //! Extractor type definitions for different parameter extraction strategies
 
-use proc_macro_error::{abort_call_site, emit_call_site_warning};
+use proc_macro_error::{abort_call_site, emit_call_site_error, emit_call_site_warning};
 use std::collections::HashMap;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -8,22 +8,33 @@ pub enum ExtractorType {
   Path,
   Query,
   HeaderParam,
+  BearerToken,
+  BasicAuth,
+  Jwt,
   CookieParam,
   SessionParam,
+  LastEventId,
   State,
   // Body extractors
   Json,
   Form,
   Bytes,
+  BodyStream,
   Text,
   Html,
   Xml,
   JavaScript,
+  XmlBody,
+  MsgPack,
+  Cbor,
+  MergePatch,
+  JsonPatch,
+  Csv,
 
   None,
 }
 
-const VALID_EXTRACTORS: &str = "Json, Form, Path, Query, State, Bytes, Text, Html, Xml, JavaScript, HeaderParam, CookieParam, SessionParam";
+const VALID_EXTRACTORS: &str = "Json, Form, Path, Query, State, Bytes, BodyStream, Text, Html, Xml, JavaScript, XmlBody, MsgPack, Cbor, MergePatch, JsonPatch, Csv, HeaderParam, BearerToken, BasicAuth, Jwt, CookieParam, SessionParam, LastEventId";
 
 impl ExtractorType {
   pub fn from_str(s: &str) -> Result<Self, String> {
@@ -33,14 +44,25 @@ impl ExtractorType {
       "Path" => Ok(ExtractorType::Path),
       "Query" => Ok(ExtractorType::Query),
       "HeaderParam" => Ok(ExtractorType::HeaderParam),
+      "BearerToken" => Ok(ExtractorType::BearerToken),
+      "BasicAuth" => Ok(ExtractorType::BasicAuth),
+      "Jwt" => Ok(ExtractorType::Jwt),
+      "LastEventId" => Ok(ExtractorType::LastEventId),
       "CookieParam" => Ok(ExtractorType::CookieParam),
       "SessionParam" => Ok(ExtractorType::SessionParam),
       "State" => Ok(ExtractorType::State),
       "Bytes" => Ok(ExtractorType::Bytes),
+      "BodyStream" => Ok(ExtractorType::BodyStream),
       "Text" => Ok(ExtractorType::Text),
       "Html" => Ok(ExtractorType::Html),
       "Xml" => Ok(ExtractorType::Xml),
       "JavaScript" => Ok(ExtractorType::JavaScript),
+      "XmlBody" => Ok(ExtractorType::XmlBody),
+      "MsgPack" => Ok(ExtractorType::MsgPack),
+      "Cbor" => Ok(ExtractorType::Cbor),
+      "MergePatch" => Ok(ExtractorType::MergePatch),
+      "JsonPatch" => Ok(ExtractorType::JsonPatch),
+      "Csv" => Ok(ExtractorType::Csv),
       _ => Err(format!(
         "Unknown extractor type: '{}'. Valid extractors are: {}",
         s, VALID_EXTRACTORS
@@ -54,18 +76,83 @@ impl ExtractorType {
       ExtractorType::Json
         | ExtractorType::Form
         | ExtractorType::Bytes
+        | ExtractorType::BodyStream
         | ExtractorType::Text
         | ExtractorType::Html
         | ExtractorType::Xml
         | ExtractorType::JavaScript
+        | ExtractorType::XmlBody
+        | ExtractorType::MsgPack
+        | ExtractorType::Cbor
+        | ExtractorType::MergePatch
+        | ExtractorType::JsonPatch
+        | ExtractorType::Csv
+    )
+  }
+
+  /// Extractors that read the body as a string, into `String`, `Cow<str>` or `Arc<str>`
+  pub fn is_text_extractor(&self) -> bool {
+    matches!(
+      self,
+      ExtractorType::Text | ExtractorType::Html | ExtractorType::Xml | ExtractorType::JavaScript
+    )
+  }
+
+  /// Body extractors whose parameter may be written as `Option<T>`, `None` for an empty body
+  pub fn allows_empty_body(&self) -> bool {
+    matches!(self, ExtractorType::Json | ExtractorType::Form) || self.is_text_extractor()
+  }
+
+  /// Request media types the text and typed body extractors accept unless opted out with
+  /// `any_content_type`
+  pub fn implied_media_types(&self) -> Option<&'static [&'static str]> {
+    match self {
+      ExtractorType::Text => Some(&["text/plain"]),
+      ExtractorType::Html => Some(&["text/html"]),
+      ExtractorType::Xml | ExtractorType::XmlBody => Some(&["application/xml", "text/xml"]),
+      ExtractorType::MsgPack => Some(&["application/msgpack", "application/x-msgpack"]),
+      ExtractorType::Cbor => Some(&["application/cbor"]),
+      ExtractorType::MergePatch => Some(&["application/merge-patch+json"]),
+      ExtractorType::JsonPatch => Some(&["application/json-patch+json"]),
+      ExtractorType::Csv => Some(&["text/csv"]),
+      ExtractorType::JavaScript => Some(&["text/javascript", "application/javascript"]),
+      _ => None,
+    }
+  }
+
+  pub fn supports_validation(&self) -> bool {
+    matches!(
+      self,
+      ExtractorType::Json | ExtractorType::Form | ExtractorType::Query
     )
   }
 
   pub fn requires_feature(&self) -> Option<&'static str> {
     match self {
-      ExtractorType::HeaderParam => Some("headers"),
+      ExtractorType::HeaderParam | ExtractorType::BearerToken | ExtractorType::BasicAuth => {
+        Some("headers")
+      }
+      ExtractorType::Jwt => Some("jwt"),
       ExtractorType::CookieParam => Some("cookies"),
       ExtractorType::SessionParam => Some("sessions"),
+      ExtractorType::XmlBody => Some("xml"),
+      ExtractorType::MsgPack => Some("msgpack"),
+      ExtractorType::Cbor => Some("cbor"),
+      ExtractorType::JsonPatch => Some("json_patch"),
+      ExtractorType::Csv => Some("csv"),
+      _ => None,
+    }
+  }
+
+  /// The feature and crate of extractors whose generated code calls into an optional crate.
+  /// Using one without its feature is a compile error, as for response formats, rather than an
+  /// unresolved crate in the generated code
+  fn required_crate(&self) -> Option<(&'static str, &'static str, bool)> {
+    match self {
+      ExtractorType::XmlBody => Some(("xml", "quick-xml", cfg!(feature = "xml"))),
+      ExtractorType::MsgPack => Some(("msgpack", "rmp-serde", cfg!(feature = "msgpack"))),
+      ExtractorType::Cbor => Some(("cbor", "ciborium", cfg!(feature = "cbor"))),
+      ExtractorType::Csv => Some(("csv", "csv", cfg!(feature = "csv"))),
       _ => None,
     }
   }
@@ -102,9 +189,35 @@ pub fn validate_extractors(extractors: &HashMap<String, ExtractorType>, route_me
     );
   }
 
+  // Warning: Patch documents on methods other than PATCH (RFC 5789)
+  for (name, ext) in &body_extractors {
+    if matches!(ext, ExtractorType::MergePatch | ExtractorType::JsonPatch)
+      && route_method != "patch"
+    {
+      emit_call_site_warning!(
+        "Patch extractor '{}' ({:?}) on {} method. Patch documents are meant for PATCH routes",
+        name,
+        ext,
+        route_method.to_uppercase()
+      );
+    }
+  }
+
   // Check for feature-gated extractors
   for (param_name, extractor) in extractors {
-    if let Some(feature) = extractor.requires_feature() {
+    if let Some((feature, dependency, enabled)) = extractor.required_crate() {
+      if !enabled {
+        emit_call_site_error!(
+          "Extractor '{:?}' for parameter '{}' requires the '{}' feature and the '{}' crate. \
+           Add it to your Cargo.toml: route_controller = {{ version = \"*\", features = [\"{}\"]}}",
+          extractor,
+          param_name,
+          feature,
+          dependency,
+          feature
+        );
+      }
+    } else if let Some(feature) = extractor.requires_feature() {
       emit_call_site_warning!(
         "Extractor '{:?}' for parameter '{}' requires the '{}' feature to be enabled. \
          Add it to your Cargo.toml: route_controller = {{ version = \"*\", features = [\"{}\"]}}",
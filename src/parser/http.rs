@@ -0,0 +1,170 @@
+//! Compile-time checks for static header names, header values and media types
+
+use proc_macro_error::emit_call_site_error;
+use quote::ToTokens;
+
+/// How a controller-level header treats a value already present on the response
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HeaderPolicy {
+  /// Replace any existing value
+  Override,
+  /// Keep a value set by the handler or route
+  IfAbsent,
+}
+
+impl HeaderPolicy {
+  /// Parses the optional third argument of `header(...)`/`content_type(...)`
+  pub fn parse(arg: &str) -> Option<Self> {
+    match arg.trim() {
+      "override" => Some(HeaderPolicy::Override),
+      "if_absent" => Some(HeaderPolicy::IfAbsent),
+      other => {
+        emit_call_site_error!(
+          "Unknown header policy '{}'. Valid policies are: override, if_absent",
+          other
+        );
+        None
+      }
+    }
+  }
+}
+
+/// A parsed `header(...)` entry
+#[derive(Debug)]
+pub struct ResponseHeader {
+  pub name: String,
+  pub value: HeaderValueSource,
+  /// Explicit policy, only meaningful on controller-level headers
+  pub policy: Option<HeaderPolicy>,
+}
+
+/// Where a `header(...)` value comes from
+pub enum HeaderValueSource {
+  /// A string literal, validated at compile time
+  Static(String),
+  /// A constant or expression such as `VERSION` or `env!("CARGO_PKG_VERSION")`
+  Expr(syn::Expr),
+  /// `with = my_fn`: computed per request by `my_fn(&Parts, &State)`
+  With(syn::Path),
+}
+
+impl std::fmt::Debug for HeaderValueSource {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      HeaderValueSource::Static(value) => write!(f, "{:?}", value),
+      HeaderValueSource::Expr(expr) => write!(f, "{}", expr.to_token_stream()),
+      HeaderValueSource::With(path) => write!(f, "with = {}", path.to_token_stream()),
+    }
+  }
+}
+
+impl HeaderValueSource {
+  /// Parses the value part of a `header(...)` call, validating string literals
+  pub fn parse(name: &str, value: &str) -> Option<Self> {
+    let value = value.trim();
+    if let Some(("with", path)) = value.split_once('=').map(|(key, path)| (key.trim(), path)) {
+      return match syn::parse_str::<syn::Path>(path.trim()) {
+        Ok(path) => Some(HeaderValueSource::With(path)),
+        Err(_) => {
+          emit_call_site_error!(
+            "Invalid function '{}' for header '{}'. Expected: with = my_fn",
+            path.trim(),
+            name
+          );
+          None
+        }
+      };
+    }
+
+    match syn::parse_str::<syn::Expr>(value) {
+      Ok(syn::Expr::Lit(syn::ExprLit {
+        lit: syn::Lit::Str(lit),
+        ..
+      })) => {
+        let value = lit.value();
+        validate_header_value(name, &value).then_some(HeaderValueSource::Static(value))
+      }
+      Ok(expr) => Some(HeaderValueSource::Expr(expr)),
+      Err(_) => {
+        emit_call_site_error!(
+          "Invalid value '{}' for header '{}'. Expected a string literal, constant, expression or 'with = my_fn'",
+          value,
+          name
+        );
+        None
+      }
+    }
+  }
+}
+
+/// RFC 9110 `tchar`: the characters allowed in header names and media type tokens
+fn is_tchar(byte: u8) -> bool {
+  byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte)
+}
+
+fn is_token(s: &str) -> bool {
+  !s.is_empty() && s.bytes().all(is_tchar)
+}
+
+/// Returns the lowercase form of a valid header name, emitting an error for invalid names.
+/// Lowercasing is always safe because header names are case-insensitive
+pub fn normalize_header_name(name: &str) -> Option<String> {
+  if is_token(name) {
+    let lowercase = name.to_ascii_lowercase();
+    if lowercase != name {
+      log_verbose!("Lowercased header name: [{}] -> [{}]", name, lowercase);
+    }
+    Some(lowercase)
+  } else {
+    emit_call_site_error!(
+      "Invalid header name '{}'. Header names may only contain letters, digits and !#$%&'*+-.^_`|~",
+      name
+    );
+    None
+  }
+}
+
+/// Checks that a static header value only contains visible ASCII, spaces and tabs
+pub fn validate_header_value(name: &str, value: &str) -> bool {
+  match value
+    .chars()
+    .find(|&ch| ch != '\t' && !(' '..='~').contains(&ch))
+  {
+    Some(ch) => {
+      emit_call_site_error!(
+        "Invalid value for header '{}': character {:?} is not allowed in header values",
+        name,
+        ch
+      );
+      false
+    }
+    None => true,
+  }
+}
+
+/// Checks that a string is a `type/subtype` media type with optional `; name=value` parameters
+pub fn validate_media_type(attr: &str, media_type: &str) -> bool {
+  let mut parts = media_type.split(';');
+  let essence = parts.next().unwrap_or_default().trim();
+  let valid_essence = essence
+    .split_once('/')
+    .is_some_and(|(ty, subtype)| is_token(ty) && is_token(subtype));
+
+  let valid_params = parts.all(|param| {
+    param.trim().split_once('=').is_some_and(|(name, value)| {
+      let value = value.trim();
+      let quoted = value.len() >= 2 && value.starts_with('"') && value.ends_with('"');
+      is_token(name.trim()) && (is_token(value) || quoted)
+    })
+  });
+
+  if !(valid_essence && valid_params) {
+    emit_call_site_error!(
+      "Invalid media type '{}' in {}. Expected 'type/subtype' with optional '; name=value' parameters \
+       (e.g., \"application/json; charset=utf-8\")",
+      media_type,
+      attr
+    );
+  }
+  valid_essence && valid_params
+}
@@ -1,20 +1,48 @@
 //! Parsing controller attributes and routes
 
 mod config;
+mod cors;
 mod extractor_types;
+mod http;
+mod negotiate;
 mod params;
+mod requirements;
 mod route;
+mod rpc;
+mod security;
+mod sse;
+mod utils;
+mod ws;
 
 // Re-export public types and functions
 pub use config::parse_controller_attributes;
 pub use extractor_types::ExtractorType;
-pub use params::analyze_params;
+pub use params::{
+  BodyTarget, analyze_params, has_optional_body, is_scalar_type, is_type_named, option_inner_type,
+  returns_result, stream_item_type, success_type,
+};
+pub use requirements::{is_requires_attr, parse_requirements};
 pub use route::extract_route_from_attrs;
+pub use rpc::extract_rpc_from_attrs;
 
 // Re-export internal types for use within the crate
 #[allow(unused_imports)]
 pub(crate) use config::ControllerConfig;
 #[allow(unused_imports)]
+pub(crate) use cors::CorsConfig;
+#[allow(unused_imports)]
+pub(crate) use http::{HeaderPolicy, HeaderValueSource, ResponseHeader};
+#[allow(unused_imports)]
+pub(crate) use negotiate::{ResponseFormat, StreamFormat};
+#[allow(unused_imports)]
 pub(crate) use params::ParamInfo;
 #[allow(unused_imports)]
-pub(crate) use route::RouteInfo;
+pub(crate) use requirements::Requirements;
+#[allow(unused_imports)]
+pub(crate) use route::{Consumes, LocationTemplate, RouteInfo};
+#[allow(unused_imports)]
+pub(crate) use rpc::RpcMethod;
+#[allow(unused_imports)]
+pub(crate) use security::SecurityHeader;
+pub(crate) use sse::{SseConfig, SseField};
+pub(crate) use ws::WsConfig;
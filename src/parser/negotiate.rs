@@ -0,0 +1,238 @@
+//! `negotiate(...)`, `respond = ...` and `stream = ...` response format configuration
+
+use proc_macro_error::emit_call_site_error;
+
+use super::utils::{balanced_group, named_arg, split_top_level, string_value};
+
+/// A serialization format a negotiating route can respond with
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ResponseFormat {
+  Json,
+  Xml,
+  MsgPack,
+  Cbor,
+  Csv,
+}
+
+const VALID_FORMATS: &str = "json, xml, msgpack, cbor, csv";
+
+impl ResponseFormat {
+  fn from_str(s: &str) -> Option<Self> {
+    match s {
+      "json" => Some(ResponseFormat::Json),
+      "xml" => Some(ResponseFormat::Xml),
+      "msgpack" => Some(ResponseFormat::MsgPack),
+      "cbor" => Some(ResponseFormat::Cbor),
+      "csv" => Some(ResponseFormat::Csv),
+      _ => None,
+    }
+  }
+
+  /// Media types matched against `Accept`; the matched one is sent as `Content-Type`
+  pub fn media_types(&self) -> &'static [&'static str] {
+    match self {
+      ResponseFormat::Json => &["application/json"],
+      ResponseFormat::Xml => &["application/xml", "text/xml"],
+      ResponseFormat::MsgPack => &["application/msgpack", "application/x-msgpack"],
+      ResponseFormat::Cbor => &["application/cbor"],
+      ResponseFormat::Csv => &["text/csv"],
+    }
+  }
+
+  /// Cargo feature of this crate that enables the format, with the serializer crate it needs
+  pub fn requires_feature(&self) -> Option<(&'static str, &'static str)> {
+    match self {
+      ResponseFormat::Json => None,
+      ResponseFormat::Xml => Some(("xml", "quick-xml")),
+      ResponseFormat::MsgPack => Some(("msgpack", "rmp-serde")),
+      ResponseFormat::Cbor => Some(("cbor", "ciborium")),
+      ResponseFormat::Csv => Some(("csv", "csv")),
+    }
+  }
+
+  fn is_enabled(&self) -> bool {
+    match self {
+      ResponseFormat::Json => true,
+      ResponseFormat::Xml => cfg!(feature = "xml"),
+      ResponseFormat::MsgPack => cfg!(feature = "msgpack"),
+      ResponseFormat::Cbor => cfg!(feature = "cbor"),
+      ResponseFormat::Csv => cfg!(feature = "csv"),
+    }
+  }
+}
+
+/// Formats offered by a bare `negotiate`: JSON plus every enabled format except CSV, which
+/// needs a collection to serialize and has to be listed explicitly
+fn default_formats() -> Vec<ResponseFormat> {
+  [
+    ResponseFormat::Json,
+    ResponseFormat::Xml,
+    ResponseFormat::MsgPack,
+    ResponseFormat::Cbor,
+  ]
+  .into_iter()
+  .filter(ResponseFormat::is_enabled)
+  .collect()
+}
+
+/// Parses a format name, emitting an error when it is unknown or its feature is disabled
+fn parse_format(name: &str) -> Option<ResponseFormat> {
+  let Some(format) = ResponseFormat::from_str(name) else {
+    emit_call_site_error!(
+      "Unknown response format '{}'. Valid formats are: {}",
+      name,
+      VALID_FORMATS
+    );
+    return None;
+  };
+  if let (Some((feature, dependency)), false) = (format.requires_feature(), format.is_enabled()) {
+    emit_call_site_error!(
+      "Response format '{}' requires the '{}' feature and the '{}' crate. \
+       Add it to your Cargo.toml: route_controller = {{ version = \"*\", features = [\"{}\"]}}",
+      name,
+      feature,
+      dependency,
+      feature
+    );
+    return None;
+  }
+  Some(format)
+}
+
+/// Parses `respond = xml`, the single format a route serializes its return value into
+/// regardless of `Accept`
+pub fn parse_respond(args: &[&str]) -> Option<ResponseFormat> {
+  let format = parse_format(named_arg(args, "respond")?)?;
+  log_verbose!("Parsed respond: [{:?}]", format);
+  Some(format)
+}
+
+/// Parses `xml_root = "books"`, the element XML responses wrap a returned sequence in. Names
+/// start with a letter or `_` and continue with letters, digits, `_`, `-` or `.`
+pub fn parse_xml_root(args: &[&str]) -> Option<String> {
+  let value = named_arg(args, "xml_root")?;
+  let root = string_value(value);
+  let mut bytes = root.bytes();
+  let valid = value.starts_with('"')
+    && bytes
+      .next()
+      .is_some_and(|b| b.is_ascii_alphabetic() || b == b'_')
+    && bytes.all(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'.'));
+  if !valid {
+    emit_call_site_error!(
+      "Invalid xml_root {}. Expected an XML element name such as \"books\"",
+      value
+    );
+    return None;
+  }
+  log_verbose!("Parsed xml_root: [{}]", root);
+  Some(root)
+}
+
+/// Parses `negotiate`, `negotiate(json, xml, ...)` or `negotiate = false` from the top-level
+/// arguments of an attribute. Returns the offered formats in order of preference, empty for
+/// `negotiate = false`, or `None` when the attribute is absent
+pub fn parse_negotiate(args: &[&str]) -> Option<Vec<ResponseFormat>> {
+  let rest = args.iter().find_map(|arg| {
+    arg
+      .strip_prefix("negotiate")
+      .map(str::trim_start)
+      .filter(|rest| rest.is_empty() || rest.starts_with(['=', '(']))
+  })?;
+
+  if rest.is_empty() {
+    return Some(default_formats());
+  }
+  if let Some(value) = rest.strip_prefix('=') {
+    return match value.trim() {
+      "false" => Some(Vec::new()),
+      "true" => Some(default_formats()),
+      value => {
+        emit_call_site_error!(
+          "Invalid negotiate value '{}'. Expected negotiate, negotiate(json, xml) or negotiate = false",
+          value
+        );
+        None
+      }
+    };
+  }
+
+  let Some(content) = balanced_group(rest, 0) else {
+    emit_call_site_error!("Unclosed parenthesis in negotiate attribute");
+    return None;
+  };
+  let mut formats = Vec::new();
+  for format in split_top_level(content)
+    .into_iter()
+    .filter_map(parse_format)
+  {
+    if !formats.contains(&format) {
+      formats.push(format);
+    }
+  }
+  if formats.is_empty() {
+    emit_call_site_error!(
+      "negotiate(...) must list at least one format: {}",
+      VALID_FORMATS
+    );
+    return None;
+  }
+
+  log_verbose!("Parsed negotiate: [{:?}]", formats);
+  Some(formats)
+}
+
+/// A format a streaming route writes the items of its `impl Stream` into, one chunk per item
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StreamFormat {
+  /// One JSON document per line
+  Ndjson,
+  /// A single JSON array, written element by element
+  JsonArray,
+  /// CSV records, with a header row taken from the first item
+  Csv,
+}
+
+const VALID_STREAM_FORMATS: &str = "ndjson, json_array, csv";
+
+impl StreamFormat {
+  fn from_str(s: &str) -> Option<Self> {
+    match s {
+      "ndjson" => Some(StreamFormat::Ndjson),
+      "json_array" => Some(StreamFormat::JsonArray),
+      "csv" => Some(StreamFormat::Csv),
+      _ => None,
+    }
+  }
+
+  /// Sent as `Content-Type`
+  pub fn media_type(&self) -> &'static str {
+    match self {
+      StreamFormat::Ndjson => "application/x-ndjson",
+      StreamFormat::JsonArray => "application/json",
+      StreamFormat::Csv => "text/csv",
+    }
+  }
+}
+
+/// Parses `stream = ndjson`, the format a route streams the items of its return value in
+pub fn parse_stream(args: &[&str]) -> Option<StreamFormat> {
+  let name = named_arg(args, "stream")?;
+  let Some(format) = StreamFormat::from_str(name) else {
+    emit_call_site_error!(
+      "Unknown stream format '{}'. Valid formats are: {}",
+      name,
+      VALID_STREAM_FORMATS
+    );
+    return None;
+  };
+  if format == StreamFormat::Csv && !cfg!(feature = "csv") {
+    emit_call_site_error!(
+      "Stream format 'csv' requires the 'csv' feature and the 'csv' crate. \
+       Add it to your Cargo.toml: route_controller = {{ version = \"*\", features = [\"csv\"]}}"
+    );
+    return None;
+  }
+  log_verbose!("Parsed stream: [{:?}]", format);
+  Some(format)
+}
@@ -10,6 +10,218 @@ pub struct ParamInfo<'a> {
   pub pat: &'a Pat,
   pub ty: &'a Type,
   pub extractor_type: ExtractorType,
+  /// `Option<T>` body parameter, `None` when the request body is empty
+  pub optional: bool,
+  pub target: BodyTarget,
+}
+
+impl ParamInfo<'_> {
+  /// The declared type without the `Option` of an optional body
+  pub fn body_ty(&self) -> &Type {
+    if self.optional {
+      option_inner_type(self.ty).unwrap_or(self.ty)
+    } else {
+      self.ty
+    }
+  }
+}
+
+/// The shape a body parameter is declared with, read from its type
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BodyTarget {
+  /// The extractor's own target: `T` for `Json`, `Vec<u8>` for `Bytes`, `String` for text
+  Owned,
+  /// `bytes::Bytes`, passed on without copying
+  Bytes,
+  /// `Box<[u8]>`
+  BoxedSlice,
+  /// `Cow<str>`
+  Cow,
+  /// `Arc<str>`
+  Arc,
+}
+
+/// Returns whether a body parameter is optional and the shape it is declared with
+fn body_shape(ty: &Type, extractor_type: ExtractorType) -> (bool, BodyTarget) {
+  let optional_inner = option_inner_type(ty).filter(|_| extractor_type.allows_empty_body());
+  let ty = optional_inner.unwrap_or(ty);
+  let target = if extractor_type == ExtractorType::Bytes {
+    if is_type_named(ty, "Bytes") {
+      BodyTarget::Bytes
+    } else if type_argument(ty, "Box").is_some_and(is_byte_slice) {
+      BodyTarget::BoxedSlice
+    } else {
+      BodyTarget::Owned
+    }
+  } else if extractor_type.is_text_extractor() && type_argument(ty, "Cow").is_some_and(is_str) {
+    BodyTarget::Cow
+  } else if extractor_type.is_text_extractor() && type_argument(ty, "Arc").is_some_and(is_str) {
+    BodyTarget::Arc
+  } else {
+    BodyTarget::Owned
+  };
+  (optional_inner.is_some(), target)
+}
+
+/// Returns true when a route's body parameter is `Option<T>`, so a request may leave out the body
+/// and its `Content-Type`
+pub fn has_optional_body(
+  sig: &syn::Signature,
+  extractor_map: &HashMap<String, ExtractorType>,
+) -> bool {
+  sig.inputs.iter().any(|input| {
+    let FnArg::Typed(pat_type) = input else {
+      return false;
+    };
+    let Pat::Ident(pat_ident) = &*pat_type.pat else {
+      return false;
+    };
+    extractor_map
+      .get(pat_ident.ident.to_string().as_str())
+      .is_some_and(|extractor_type| body_shape(&pat_type.ty, *extractor_type).0)
+  })
+}
+
+/// Returns `T` when the parameter type is written as `Option<T>`
+pub fn option_inner_type(ty: &Type) -> Option<&Type> {
+  let Type::Path(type_path) = ty else {
+    return None;
+  };
+  let segment = type_path.path.segments.last()?;
+  if segment.ident != "Option" {
+    return None;
+  }
+  match &segment.arguments {
+    syn::PathArguments::AngleBracketed(args) => match args.args.first()? {
+      syn::GenericArgument::Type(inner) => Some(inner),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+/// Returns the first type argument of a type whose last path segment is `name`, e.g. `str` for
+/// `Cow<'a, str>` and `Cow`
+fn type_argument<'a>(ty: &'a Type, name: &str) -> Option<&'a Type> {
+  let Type::Path(type_path) = ty else {
+    return None;
+  };
+  let segment = type_path.path.segments.last()?;
+  if segment.ident != name {
+    return None;
+  }
+  let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+    return None;
+  };
+  args.args.iter().find_map(|arg| match arg {
+    syn::GenericArgument::Type(inner) => Some(inner),
+    _ => None,
+  })
+}
+
+/// Returns true for `[u8]`
+fn is_byte_slice(ty: &Type) -> bool {
+  matches!(ty, Type::Slice(slice) if is_type_named(&slice.elem, "u8"))
+}
+
+/// Returns true for `str`
+fn is_str(ty: &Type) -> bool {
+  is_type_named(ty, "str")
+}
+
+/// Returns true when the type's last path segment is `name`, e.g. `axum::body::Bytes` for `Bytes`
+pub fn is_type_named(ty: &Type, name: &str) -> bool {
+  let Type::Path(type_path) = ty else {
+    return false;
+  };
+  type_path
+    .path
+    .segments
+    .last()
+    .is_some_and(|segment| segment.ident == name)
+}
+
+/// Returns true when the handler's return type is written as `Result<T, E>`
+pub fn returns_result(sig: &syn::Signature) -> bool {
+  let syn::ReturnType::Type(_, ty) = &sig.output else {
+    return false;
+  };
+  let Type::Path(type_path) = &**ty else {
+    return false;
+  };
+  type_path
+    .path
+    .segments
+    .last()
+    .is_some_and(|segment| segment.ident == "Result")
+}
+
+/// Returns the type of successful responses: `T` for `Result<T, E>`, otherwise the return type
+pub fn success_type(sig: &syn::Signature) -> Option<&Type> {
+  let syn::ReturnType::Type(_, ty) = &sig.output else {
+    return None;
+  };
+  if !returns_result(sig) {
+    return Some(ty);
+  }
+  let Type::Path(type_path) = &**ty else {
+    return None;
+  };
+  match &type_path.path.segments.last()?.arguments {
+    syn::PathArguments::AngleBracketed(args) => match args.args.first()? {
+      syn::GenericArgument::Type(inner) => Some(inner),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+/// Returns `T` for a success type written as `impl Stream<Item = T>`
+pub fn stream_item_type(sig: &syn::Signature) -> Option<&Type> {
+  let Type::ImplTrait(impl_trait) = success_type(sig)? else {
+    return None;
+  };
+  impl_trait.bounds.iter().find_map(|bound| {
+    let syn::TypeParamBound::Trait(trait_bound) = bound else {
+      return None;
+    };
+    let syn::PathArguments::AngleBracketed(args) = &trait_bound.path.segments.last()?.arguments
+    else {
+      return None;
+    };
+    args.args.iter().find_map(|arg| match arg {
+      syn::GenericArgument::AssocType(assoc) if assoc.ident == "Item" => Some(&assoc.ty),
+      _ => None,
+    })
+  })
+}
+
+/// Returns true for types that are returned as a bare id (integers, strings and `Uuid`)
+pub fn is_scalar_type(ty: &Type) -> bool {
+  match ty {
+    Type::Reference(reference) => is_scalar_type(&reference.elem),
+    Type::Path(type_path) => type_path.path.segments.last().is_some_and(|segment| {
+      matches!(
+        segment.ident.to_string().as_str(),
+        "u8"
+          | "u16"
+          | "u32"
+          | "u64"
+          | "u128"
+          | "usize"
+          | "i8"
+          | "i16"
+          | "i32"
+          | "i64"
+          | "i128"
+          | "isize"
+          | "str"
+          | "String"
+          | "Uuid"
+      )
+    }),
+    _ => false,
+  }
 }
 
 /// Analyzes function parameters using explicit extractor mappings from route attributes
@@ -40,8 +252,9 @@ pub fn analyze_params<'a>(
       let extractor_type = *extractor_map
         .get(param_name.as_str())
         .unwrap_or_else(|| {
-          // Warn about parameters without extractors
-          if param_name != "unknown" {
+          // Warn about parameters without extractors. A WebSocket is the upgraded
+          // connection of a #[ws] route
+          if param_name != "unknown" && !is_type_named(ty, "WebSocket") {
             emit_call_site_warning!(
               "Parameter '{}' has no extractor specified. It will not receive any data from the request",
               param_name
@@ -55,10 +268,13 @@ pub fn analyze_params<'a>(
         emit_call_site_warning!("Duplicate parameter name found in function signature");
       }
 
+      let (optional, target) = body_shape(ty, extractor_type);
       params.push(ParamInfo {
         pat,
         ty,
         extractor_type,
+        optional,
+        target,
       });
     }
   }
@@ -0,0 +1,99 @@
+//! Role and permission requirements parsed from `#[requires(...)]` attributes
+
+use proc_macro_error::emit_call_site_error;
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Expr, Lit, MetaNameValue, Token};
+
+#[derive(Clone, Default, Debug)]
+pub struct Requirements {
+  pub roles: Vec<String>,
+  pub permissions: Vec<String>,
+}
+
+impl Requirements {
+  pub fn is_empty(&self) -> bool {
+    self.roles.is_empty() && self.permissions.is_empty()
+  }
+
+  /// Combines controller-level requirements with route-level ones (both must hold)
+  pub fn merged_with(&self, other: &Requirements) -> Requirements {
+    let mut merged = self.clone();
+    for role in &other.roles {
+      if !merged.roles.contains(role) {
+        merged.roles.push(role.clone());
+      }
+    }
+    for permission in &other.permissions {
+      if !merged.permissions.contains(permission) {
+        merged.permissions.push(permission.clone());
+      }
+    }
+    merged
+  }
+}
+
+pub fn is_requires_attr(attr: &Attribute) -> bool {
+  attr.path().is_ident("requires")
+}
+
+/// Collects every `#[requires(role = "...", permission = "...")]` attribute into one set
+pub fn parse_requirements(attrs: &[Attribute]) -> Requirements {
+  let mut requirements = Requirements::default();
+
+  for attr in attrs.iter().filter(|attr| is_requires_attr(attr)) {
+    let pairs = match attr.parse_args_with(Punctuated::<MetaNameValue, Token![,]>::parse_terminated)
+    {
+      Ok(pairs) => pairs,
+      Err(_) => {
+        emit_call_site_error!(
+          "Invalid requires attribute format. Expected: #[requires(role = \"admin\")] or #[requires(permission = \"orders:write\")]"
+        );
+        continue;
+      }
+    };
+
+    for pair in pairs {
+      let key = pair
+        .path
+        .get_ident()
+        .map(|ident| ident.to_string())
+        .unwrap_or_default();
+      let value = match &pair.value {
+        Expr::Lit(expr_lit) => match &expr_lit.lit {
+          Lit::Str(lit_str) => lit_str.value(),
+          _ => String::new(),
+        },
+        _ => String::new(),
+      };
+
+      if value.is_empty() {
+        emit_call_site_error!(
+          "Empty or non-string value for '{}' in requires attribute. Expected a string literal",
+          key
+        );
+        continue;
+      }
+
+      match key.as_str() {
+        "role" => requirements.roles.push(value),
+        "permission" => requirements.permissions.push(value),
+        _ => {
+          emit_call_site_error!(
+            "Unknown requires key '{}'. Valid keys are: role, permission",
+            key
+          );
+        }
+      }
+    }
+  }
+
+  if !requirements.is_empty() {
+    log_verbose!(
+      "Parsed requirements: [Roles:{:?}] [Permissions:{:?}]",
+      requirements.roles,
+      requirements.permissions
+    );
+  }
+
+  requirements
+}
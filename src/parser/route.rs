@@ -3,16 +3,149 @@
 use proc_macro_error::{emit_call_site_error, emit_call_site_warning};
 use quote::ToTokens;
 use std::collections::{HashMap, HashSet};
-use syn::Attribute;
+use syn::{Attribute, Type};
 
 use super::extractor_types::{ExtractorType, validate_extractors};
+use super::http::ResponseHeader;
+use super::negotiate::{
+  ResponseFormat, StreamFormat, parse_negotiate, parse_respond, parse_stream, parse_xml_root,
+};
+use super::requirements::{Requirements, parse_requirements};
+use super::security::{SecurityHeader, parse_security_headers};
+use super::sse::{SseConfig, parse_sse};
+use super::utils::{
+  attr_args, balanced_group, named_arg, parse_byte_size, parse_content_type, parse_filename,
+  parse_header_calls, parse_media_types, parse_realm, parse_status_code, reject_header_policies,
+  split_top_level,
+};
+use super::ws::{WsConfig, parse_ws};
 
 pub struct RouteInfo {
   pub method: String,
   pub path: String,
   pub extractors: HashMap<String, ExtractorType>,
-  pub response_headers: Vec<(String, String)>, // (header_name, header_value)
+  pub response_headers: Vec<ResponseHeader>,
   pub content_type: Option<String>,
+  pub requirements: Requirements,
+  /// Parameters whose extracted value is checked with `validator::Validate`
+  pub validated: HashSet<String>,
+  /// Route-level override of the controller's rejection type
+  pub rejection: Option<Type>,
+  /// Route-level override of the controller's error type for `Result` handlers
+  pub error: Option<Type>,
+  /// Headers added only to error responses of `Result` handlers
+  pub error_headers: Vec<ResponseHeader>,
+  /// Status code that replaces the error type's own status
+  pub error_status: Option<u16>,
+  /// Status code of successful responses
+  pub status: Option<u16>,
+  /// `Location` header filled in from the returned value
+  pub location: Option<LocationTemplate>,
+  /// Request media types accepted, answering 415 for others
+  pub consumes: Consumes,
+  /// Response media types offered, answering 406 when `Accept` allows none of them
+  pub produces: Option<Vec<String>>,
+  /// Formats the return value is serialized into by `Accept`, replacing the controller's.
+  /// Empty for `negotiate = false`
+  pub negotiate: Option<Vec<ResponseFormat>>,
+  /// Single format the return value is serialized into regardless of `Accept`
+  pub respond: Option<ResponseFormat>,
+  /// Element an XML response wraps a returned sequence in, `items` when unset
+  pub xml_root: Option<String>,
+  /// Format the items of a returned `impl Stream` are written in as they arrive
+  pub stream: Option<StreamFormat>,
+  /// Set for `#[sse(...)]` routes, which are registered as GET and send the items of a returned
+  /// `impl Stream` as Server-Sent Events
+  pub sse: Option<SseConfig>,
+  /// Set for `#[ws(...)]` routes, which are registered as GET and hand the upgraded socket to
+  /// the handler
+  pub ws: Option<WsConfig>,
+  /// Name sent in `Content-Disposition: attachment` on successful responses
+  pub filename: Option<String>,
+  /// Realm sent in the Basic challenge of `BasicAuth` parameters, replacing the controller's
+  pub realm: Option<String>,
+  /// Maximum request body size in bytes, replacing the controller's and axum's default
+  pub body_limit: Option<usize>,
+  /// Entries replacing or disabling the controller's `security_headers` preset
+  pub security_headers: Vec<SecurityHeader>,
+}
+
+/// The request media types a route accepts
+#[derive(Debug)]
+pub enum Consumes {
+  /// The controller's `consumes`, applied when the route has a body extractor
+  Inherit,
+  /// Any media type, for text extractors marked `any_content_type`
+  Any,
+  /// The route's own `consumes`, or the media type implied by its text extractor
+  Only(Vec<String>),
+}
+
+/// A `location = "/users/{id}"` template split into a format string and the field paths
+/// that fill its placeholders
+pub struct LocationTemplate {
+  pub format: String,
+  /// One entry per placeholder, e.g. `["user", "id"]` for `{user.id}`
+  pub fields: Vec<Vec<String>>,
+}
+
+impl LocationTemplate {
+  /// Parses and validates a template, emitting an error and returning `None` when invalid
+  pub fn parse(template: &str) -> Option<Self> {
+    if !(template.starts_with('/') || template.contains("://")) {
+      emit_call_site_error!(
+        "Invalid location '{}'. Expected an absolute path (\"/users/{{id}}\") or URL",
+        template
+      );
+      return None;
+    }
+
+    let mut format = String::with_capacity(template.len());
+    let mut fields = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find(['{', '}']) {
+      format.push_str(&rest[..open]);
+      let close = match (rest.as_bytes()[open], rest[open..].find('}')) {
+        (b'{', Some(close)) => open + close,
+        _ => {
+          emit_call_site_error!("Unbalanced braces in location '{}'", template);
+          return None;
+        }
+      };
+      let field = &rest[open + 1..close];
+      let segments: Vec<String> = field.split('.').map(|s| s.trim().to_string()).collect();
+      let valid = segments.iter().all(|segment| {
+        syn::parse_str::<syn::Ident>(segment).is_ok()
+          || (!segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit()))
+      });
+      if !valid {
+        emit_call_site_error!(
+          "Invalid placeholder '{{{}}}' in location '{}'. Expected a field name such as {{id}} or {{user.id}}",
+          field,
+          template
+        );
+        return None;
+      }
+      format.push_str("{}");
+      fields.push(segments);
+      rest = &rest[close + 1..];
+    }
+    format.push_str(rest);
+
+    if let Some(ch) = format
+      .chars()
+      .find(|ch| !ch.is_ascii_graphic() || matches!(ch, '"' | '<' | '>' | '\\'))
+    {
+      emit_call_site_error!(
+        "Invalid character {:?} in location '{}'. Percent-encode it instead",
+        ch,
+        template
+      );
+      return None;
+    }
+
+    Some(LocationTemplate { format, fields })
+  }
 }
 
 /// Validates path parameters and emits errors/warnings
@@ -76,6 +209,90 @@ fn validate_path_parameters(path: &str, extractors: &HashMap<String, ExtractorTy
   }
 }
 
+/// Parses `extract(param = Type, ...)`, returning the extractor of each parameter, the
+/// parameters marked `validate` and whether any extractor is marked `any_content_type`
+pub(super) fn parse_extract(
+  attr_str: &str,
+) -> (HashMap<String, ExtractorType>, HashSet<String>, bool) {
+  let mut extractors = HashMap::with_capacity(4); // Most routes have 0-4 extractors
+  let mut validated = HashSet::new();
+  let mut any_content_type = false;
+
+  if let Some(extract_start) = attr_str.find("extract") {
+    if let Some(paren_start) = attr_str[extract_start..].find('(') {
+      let paren_start = extract_start + paren_start;
+      if let Some(extract_content) = balanced_group(attr_str, paren_start) {
+        // Parse param = Type or param = Type(options) pairs
+        for pair in split_top_level(extract_content) {
+          let parts: Vec<&str> = pair.splitn(2, '=').map(|s| s.trim()).collect();
+          if parts.len() == 2 {
+            let param_name = parts[0].to_string();
+            let (extractor_str, options) = match parts[1].find('(') {
+              Some(open) => (
+                parts[1][..open].trim(),
+                balanced_group(parts[1], open).unwrap_or(""),
+              ),
+              None => (parts[1], ""),
+            };
+
+            // Use from_str for validation
+            match ExtractorType::from_str(extractor_str) {
+              Ok(extractor_type) => {
+                for option in split_top_level(options) {
+                  match option {
+                    "validate" if extractor_type.supports_validation() => {
+                      validated.insert(param_name.clone());
+                    }
+                    "validate" => {
+                      emit_call_site_error!(
+                        "Extractor {:?} for parameter '{}' does not support validation. \
+                         Only Json, Form and Query can be validated",
+                        extractor_type,
+                        param_name
+                      );
+                    }
+                    "any_content_type" if extractor_type.implied_media_types().is_some() => {
+                      any_content_type = true;
+                    }
+                    "any_content_type" => {
+                      emit_call_site_error!(
+                        "Extractor {:?} for parameter '{}' does not check the content type. \
+                         Only Text, Html, Xml, JavaScript, XmlBody, MsgPack, Cbor, MergePatch, JsonPatch and Csv accept any_content_type",
+                        extractor_type,
+                        param_name
+                      );
+                    }
+                    _ => {
+                      emit_call_site_error!(
+                        "Unknown option '{}' for extractor {:?}. Valid options are: validate, any_content_type",
+                        option,
+                        extractor_type
+                      );
+                    }
+                  }
+                }
+                extractors.insert(param_name, extractor_type);
+              }
+              Err(err_msg) => {
+                emit_call_site_error!("{}", err_msg);
+                // Insert None to continue parsing
+                extractors.insert(param_name, ExtractorType::None);
+              }
+            }
+          } else if parts.len() == 1 && !parts[0].is_empty() {
+            emit_call_site_error!(
+              "Invalid extractor syntax '{}'. Expected format: 'param_name = ExtractorType'",
+              pair
+            );
+          }
+        }
+      }
+    }
+  }
+
+  (extractors, validated, any_content_type)
+}
+
 pub fn extract_route_from_attrs(attrs: &[Attribute]) -> Option<RouteInfo> {
   for attr in attrs {
     let path_segments: Vec<String> = attr
@@ -88,123 +305,246 @@ pub fn extract_route_from_attrs(attrs: &[Attribute]) -> Option<RouteInfo> {
     if path_segments.len() == 1 {
       let method = path_segments[0].to_lowercase();
       match method.as_str() {
-        "get" | "head" | "delete" | "options" | "patch" | "post" | "put" | "trace" | "connect" => {
+        "get" | "head" | "delete" | "options" | "patch" | "post" | "put" | "trace" | "connect"
+        | "sse" | "ws" => {
           let mut route_path = "/".to_string();
-          let mut extractors = HashMap::with_capacity(4); // Most routes have 0-4 extractors
-          let mut response_headers = Vec::with_capacity(2); // Most routes have 0-2 headers
-          let mut content_type = None;
-
           // Parse attribute content
           let attr_str = attr.meta.to_token_stream().to_string();
 
-          // Extract path (first quoted string)
-          if let Some(start) = attr_str.find('"') {
-            if let Some(end) = attr_str[start + 1..].find('"') {
-              let mut path = attr_str[start + 1..start + 1 + end].to_string();
-              if !path.is_empty() {
-                if !path.starts_with('/') {
-                  path = format!("/{}", path);
-                }
-                route_path = path;
-              }
+          let args = attr_args(&attr_str);
+
+          // Extract path (leading string literal argument)
+          if let Some(path) = args
+            .first()
+            .and_then(|arg| arg.strip_prefix('"'))
+            .and_then(|arg| arg.strip_suffix('"'))
+          {
+            if !path.is_empty() {
+              route_path = if path.starts_with('/') {
+                path.to_string()
+              } else {
+                format!("/{}", path)
+              };
             }
           }
 
           // Parse extract(...) if present
-          if let Some(extract_start) = attr_str.find("extract") {
-            if let Some(paren_start) = attr_str[extract_start..].find('(') {
-              let paren_start = extract_start + paren_start + 1;
-              if let Some(paren_end) = attr_str[paren_start..].find(')') {
-                let extract_content = &attr_str[paren_start..paren_start + paren_end];
-
-                // Parse param = Type pairs
-                for pair in extract_content.split(',') {
-                  let parts: Vec<&str> = pair.trim().split('=').map(|s| s.trim()).collect();
-                  if parts.len() == 2 {
-                    let param_name = parts[0].to_string();
-                    let extractor_str = parts[1];
-
-                    // Use from_str for validation
-                    match ExtractorType::from_str(extractor_str) {
-                      Ok(extractor_type) => {
-                        extractors.insert(param_name, extractor_type);
-                      }
-                      Err(err_msg) => {
-                        emit_call_site_error!(
-                          "{}. Valid extractors are: Json, Form, Path, Query, State, Bytes, Text, Html, Xml, JavaScript, HeaderParam, CookieParam, SessionParam",
-                          err_msg
-                        );
-                        // Insert None to continue parsing
-                        extractors.insert(param_name, ExtractorType::None);
-                      }
-                    }
-                  } else if parts.len() == 1 && !parts[0].is_empty() {
-                    emit_call_site_error!(
-                      "Invalid extractor syntax '{}'. Expected format: 'param_name = ExtractorType'",
-                      pair.trim()
-                    );
-                  }
-                }
+          let (extractors, mut validated, any_content_type) = parse_extract(&attr_str);
+
+          // Parse rejection = ErrorType
+          let rejection = named_arg(&args, "rejection").and_then(|value| {
+            syn::parse_str::<Type>(value)
+              .map_err(|_| {
+                emit_call_site_error!(
+                  "Invalid rejection type '{}'. Expected a valid Rust type (e.g., ApiError)",
+                  value
+                );
+              })
+              .ok()
+          });
+
+          // Parse error = ErrorType and error_status = 500
+          let error = named_arg(&args, "error").and_then(|value| {
+            syn::parse_str::<Type>(value)
+              .map_err(|_| {
+                emit_call_site_error!(
+                  "Invalid error type '{}'. Expected a valid Rust type (e.g., ApiError)",
+                  value
+                );
+              })
+              .ok()
+          });
+          let error_status = named_arg(&args, "error_status")
+            .and_then(|value| parse_status_code("error_status", value));
+
+          // Parse status = 201 and location = "/users/{id}"
+          let status =
+            named_arg(&args, "status").and_then(|value| parse_status_code("status", value));
+          let location = named_arg(&args, "location").and_then(|value| {
+            match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+              Some(template) => LocationTemplate::parse(template),
+              None => {
+                emit_call_site_error!(
+                  "Invalid location '{}'. Expected a string literal (e.g., location = \"/users/{{id}}\")",
+                  value
+                );
+                None
               }
             }
-          }
+          });
 
-          // Parse header(...) attributes
-          let mut search_pos = 0;
-          while let Some(header_start) = attr_str[search_pos..].find("header") {
-            let header_start = search_pos + header_start;
-            if let Some(paren_start) = attr_str[header_start..].find('(') {
-              let paren_start = header_start + paren_start + 1;
-              if let Some(paren_end) = attr_str[paren_start..].find(')') {
-                let header_content = &attr_str[paren_start..paren_start + paren_end];
-                // Parse header_name = "header_value" or (header_name, header_value)
-                if let Some(eq_pos) = header_content.find('=') {
-                  let header_name = header_content[..eq_pos].trim().replace('"', "");
-                  let header_value = header_content[eq_pos + 1..].trim().replace('"', "");
-                  if header_name.is_empty() || header_value.is_empty() {
-                    emit_call_site_warning!("Empty header name or value in header attribute");
-                  }
-                  response_headers.push((header_name, header_value));
-                } else {
-                  // Try parsing as tuple: ("name", "value")
-                  let parts: Vec<&str> = header_content.split(',').collect();
-                  if parts.len() == 2 {
-                    let header_name = parts[0].trim().replace('"', "");
-                    let header_value = parts[1].trim().replace('"', "");
-                    if header_name.is_empty() || header_value.is_empty() {
-                      emit_call_site_warning!("Empty header name or value in header attribute");
-                    }
-                    response_headers.push((header_name, header_value));
-                  } else {
-                    emit_call_site_warning!(
-                      "Invalid header attribute format. Expected: header(\"name\", \"value\") or header(name = \"value\")"
-                    );
-                  }
-                }
-                search_pos = paren_start + paren_end + 1;
-              } else {
-                break;
+          // Parse consumes = [...] and produces = [...]. Text extractors imply their media type
+          let implied = extractors
+            .values()
+            .find_map(|ext| ext.implied_media_types());
+          let consumes = match named_arg(&args, "consumes") {
+            Some(value) => parse_media_types("consumes", value)
+              .map(Consumes::Only)
+              .unwrap_or(Consumes::Inherit),
+            None if any_content_type => Consumes::Any,
+            None => match implied {
+              Some(media_types) => {
+                Consumes::Only(media_types.iter().map(|m| m.to_string()).collect())
               }
-            } else {
-              break;
+              None => Consumes::Inherit,
+            },
+          };
+          let produces =
+            named_arg(&args, "produces").and_then(|value| parse_media_types("produces", value));
+          let negotiate = parse_negotiate(&args);
+          let respond = parse_respond(&args);
+          if respond.is_some() && negotiate.is_some() {
+            emit_call_site_error!(
+              "respond and negotiate on route '{}' are exclusive: respond always uses one format",
+              route_path
+            );
+          }
+          let stream = parse_stream(&args);
+          let xml_root = parse_xml_root(&args);
+
+          // Server-Sent Events and WebSocket routes answer GET requests
+          let sse = (method == "sse").then(|| parse_sse(&args));
+          let ws = (method == "ws").then(|| parse_ws(&args));
+          let method = if sse.is_some() || ws.is_some() {
+            "get".to_string()
+          } else {
+            method
+          };
+          if ws.is_some() {
+            if let Some((name, _)) = extractors.iter().find(|(_, ext)| ext.is_body_extractor()) {
+              emit_call_site_error!(
+                "#[ws] route '{}' cannot extract the body into '{}': WebSocket handshakes have no body",
+                route_path,
+                name
+              );
             }
+            if stream.is_some()
+              || respond.is_some()
+              || negotiate.is_some()
+              || location.is_some()
+              || status.is_some()
+            {
+              emit_call_site_error!(
+                "#[ws] route '{}' answers 101 Switching Protocols and cannot set stream, respond, negotiate, location or status",
+                route_path
+              );
+            }
+          }
+          if sse.is_none()
+            && extractors
+              .values()
+              .any(|ext| *ext == ExtractorType::LastEventId)
+          {
+            emit_call_site_warning!(
+              "LastEventId on route '{}' is only sent by EventSource clients reconnecting to an #[sse] route",
+              route_path
+            );
+          }
+          if sse.is_some()
+            && (stream.is_some() || respond.is_some() || negotiate.is_some() || location.is_some())
+          {
+            emit_call_site_error!(
+              "#[sse] route '{}' sends events and cannot use stream, respond, negotiate or location",
+              route_path
+            );
+          }
+          if stream.is_some() && (respond.is_some() || negotiate.is_some()) {
+            emit_call_site_error!(
+              "stream on route '{}' cannot be combined with respond or negotiate: the stream format sets the body",
+              route_path
+            );
+          }
+          if stream.is_some() && location.is_some() {
+            emit_call_site_error!(
+              "location on route '{}' cannot be read from a stream",
+              route_path
+            );
+          }
+
+          // Parse body_limit = "50MB"
+          let body_limit =
+            named_arg(&args, "body_limit").and_then(|value| parse_byte_size("body_limit", value));
+          if body_limit.is_some() && !extractors.values().any(|ext| ext.is_body_extractor()) {
+            emit_call_site_warning!(
+              "body_limit on route '{}' has no effect: the route has no body extractor",
+              route_path
+            );
+          }
+
+          // Parse realm = "admin", sent in the Basic challenge
+          let realm = named_arg(&args, "realm").and_then(parse_realm);
+          if realm.is_some()
+            && !extractors
+              .values()
+              .any(|ext| *ext == ExtractorType::BasicAuth)
+          {
+            emit_call_site_warning!(
+              "realm on route '{}' has no effect: the route has no BasicAuth parameter",
+              route_path
+            );
+          }
+
+          // #[validate] on the handler validates every Json, Form and Query parameter
+          if attrs.iter().any(|attr| attr.path().is_ident("validate")) {
+            validated.extend(
+              extractors
+                .iter()
+                .filter(|(_, ext)| ext.supports_validation())
+                .map(|(name, _)| name.clone()),
+            );
           }
 
+          // Parse header(...) and error_header(...) attributes
+          let response_headers = parse_header_calls(&attr_str, "header");
+          let error_headers = parse_header_calls(&attr_str, "error_header");
+          reject_header_policies(&response_headers, "routes");
+          reject_header_policies(&error_headers, "error_header(...)");
+
           // Parse content_type(...) attribute
-          if let Some(ct_start) = attr_str.find("content_type") {
-            if let Some(paren_start) = attr_str[ct_start..].find('(') {
-              let paren_start = ct_start + paren_start + 1;
-              if let Some(paren_end) = attr_str[paren_start..].find(')') {
-                let ct_content = &attr_str[paren_start..paren_start + paren_end];
-                let ct_value = ct_content.trim().replace('"', "");
-                if ct_value.is_empty() {
-                  emit_call_site_warning!("Empty content_type value");
-                }
-                content_type = Some(ct_value);
-              }
+          let content_type = parse_content_type(&attr_str, "route").map(|(value, policy)| {
+            if policy.is_some() {
+              emit_call_site_error!(
+                "Header policies apply to controller-level content_type(...) only"
+              );
             }
+            value
+          });
+          if content_type.is_some()
+            && (respond.is_some()
+              || stream.is_some()
+              || sse.is_some()
+              || negotiate.as_ref().is_some_and(|f| !f.is_empty()))
+          {
+            emit_call_site_error!(
+              "content_type(...) conflicts with negotiate, respond, stream and sse on route '{}': the serialized format sets the content type",
+              route_path
+            );
+          }
+
+          // Parse filename = "export.csv", offering the response as a download
+          let filename = named_arg(&args, "filename").and_then(parse_filename);
+          if filename.is_some() && sse.is_some() {
+            emit_call_site_error!(
+              "filename on route '{}' does not apply to #[sse] routes: event streams are not downloads",
+              route_path
+            );
+          }
+
+          if ws.is_some()
+            && (content_type.is_some()
+              || filename.is_some()
+              || !response_headers.is_empty()
+              || !error_headers.is_empty())
+          {
+            emit_call_site_error!(
+              "#[ws] route '{}' answers 101 Switching Protocols and cannot set header(...), content_type(...) or filename",
+              route_path
+            );
           }
 
+          // Parse security_headers(...) adjustments; routes have no default preset
+          let security_headers = parse_security_headers(&args, None);
+
           // Validate extractors
           validate_extractors(&extractors, &method);
 
@@ -226,12 +566,36 @@ pub fn extract_route_from_attrs(attrs: &[Attribute]) -> Option<RouteInfo> {
             extractors,
             response_headers,
             content_type,
+            requirements: parse_requirements(attrs),
+            validated,
+            rejection,
+            error,
+            error_headers,
+            error_status,
+            status,
+            location,
+            consumes,
+            produces,
+            negotiate,
+            respond,
+            xml_root,
+            stream,
+            sse,
+            ws,
+            filename,
+            realm,
+            body_limit,
+            security_headers,
           });
         }
+        "requires" | "validate" | "catch" | "rpc" => {
+          // Handler markers, collected alongside the route attribute. #[rpc] methods are read by
+          // the JSON-RPC dispatcher
+        }
         _ => {
           // Unknown HTTP method
           emit_call_site_error!(
-            "Unknown HTTP method '{}'. Valid methods are: get, post, put, patch, delete, head, options, trace",
+            "Unknown HTTP method '{}'. Valid methods are: get, post, put, patch, delete, head, options, trace, sse, ws",
             method
           );
         }
@@ -0,0 +1,103 @@
+//! `#[rpc(...)]` JSON-RPC method configuration
+
+use proc_macro_error::emit_call_site_error;
+use quote::ToTokens;
+use std::collections::HashMap;
+use syn::Attribute;
+
+use super::extractor_types::{ExtractorType, validate_extractors};
+use super::route::parse_extract;
+use super::utils::{attr_args, named_arg, string_value};
+
+/// A parsed `#[rpc(...)]` method of an `#[rpc_controller]`
+#[derive(Debug)]
+pub struct RpcMethod {
+  /// Name the method is called by, the handler's name unless `name = "..."` is given
+  pub name: String,
+  /// Parameters filled from the HTTP request rather than from the call's `params`
+  pub extractors: HashMap<String, ExtractorType>,
+}
+
+/// Returns true for the extractors that read the request parts or state, which every call of
+/// a batch shares
+fn supported_in_rpc(extractor_type: ExtractorType) -> bool {
+  matches!(
+    extractor_type,
+    ExtractorType::State
+      | ExtractorType::HeaderParam
+      | ExtractorType::CookieParam
+      | ExtractorType::SessionParam
+  )
+}
+
+/// Parses the `#[rpc]` attribute of a method, if it has one
+pub fn extract_rpc_from_attrs(attrs: &[Attribute], handler_name: &str) -> Option<RpcMethod> {
+  let attr = attrs.iter().find(|attr| attr.path().is_ident("rpc"))?;
+
+  // A bare #[rpc] has no argument list
+  let attr_str = match &attr.meta {
+    syn::Meta::Path(_) => String::new(),
+    meta => meta.to_token_stream().to_string(),
+  };
+  let args = attr_args(&attr_str);
+
+  let name = named_arg(&args, "name")
+    .map(string_value)
+    .unwrap_or_else(|| handler_name.to_string());
+  if name.is_empty() {
+    emit_call_site_error!("Empty JSON-RPC method name on '{}'", handler_name);
+  } else if name.starts_with("rpc.") {
+    emit_call_site_error!(
+      "JSON-RPC method name '{}' is invalid: names starting with 'rpc.' are reserved by the specification",
+      name
+    );
+  }
+
+  // Both apply to routes only; an #[rpc] method would otherwise run unchecked
+  for (marker, instead) in [
+    ("requires", "Check access in a middleware instead"),
+    ("validate", "Validate the params in the method instead"),
+  ] {
+    if attrs.iter().any(|attr| attr.path().is_ident(marker)) {
+      emit_call_site_error!(
+        "#[{}] is not supported on #[rpc] method '{}'. {}",
+        marker,
+        handler_name,
+        instead
+      );
+    }
+  }
+
+  let (extractors, validated, any_content_type) = parse_extract(&attr_str);
+  for (param_name, extractor_type) in &extractors {
+    if *extractor_type != ExtractorType::None && !supported_in_rpc(*extractor_type) {
+      emit_call_site_error!(
+        "Extractor {:?} for parameter '{}' is not supported on #[rpc] methods. \
+         Use State, HeaderParam, CookieParam or SessionParam; other parameters are read from the call's params",
+        extractor_type,
+        param_name
+      );
+    }
+  }
+  // Feature-gated extractors are reported as for routes
+  validate_extractors(&extractors, "post");
+  if !validated.is_empty() || any_content_type {
+    emit_call_site_error!(
+      "validate and any_content_type do not apply to #[rpc] methods, whose params are always JSON"
+    );
+  }
+
+  for arg in &args {
+    let key = arg.split('=').next().unwrap_or("").trim();
+    if !matches!(key, "name" | "") && !key.starts_with("extract") {
+      emit_call_site_error!(
+        "Unknown #[rpc] argument '{}'. Valid arguments are: name, extract",
+        key
+      );
+    }
+  }
+
+  let method = RpcMethod { name, extractors };
+  log_verbose!("Parsed rpc: [{:?}]", method);
+  Some(method)
+}
@@ -0,0 +1,141 @@
+//! `security_headers` presets and their per-route adjustments
+
+use proc_macro_error::emit_call_site_error;
+
+use super::http::validate_header_value;
+use super::utils::{balanced_group, split_top_level, string_value};
+
+/// Keys accepted by `security_headers(...)` and the header each one sets
+const SECURITY_HEADERS: [(&str, &str); 5] = [
+  ("hsts", "strict-transport-security"),
+  ("content_type_options", "x-content-type-options"),
+  ("frame_options", "x-frame-options"),
+  ("referrer_policy", "referrer-policy"),
+  ("csp", "content-security-policy"),
+];
+
+/// For APIs that never serve documents or get framed
+const STRICT: [&str; 5] = [
+  "max-age=63072000; includeSubDomains",
+  "nosniff",
+  "DENY",
+  "no-referrer",
+  "default-src 'none'; frame-ancestors 'none'; base-uri 'none'; form-action 'none'",
+];
+
+/// For applications that serve their own pages and frame themselves
+const BASIC: [&str; 5] = [
+  "max-age=31536000",
+  "nosniff",
+  "SAMEORIGIN",
+  "strict-origin-when-cross-origin",
+  "frame-ancestors 'self'",
+];
+
+/// One entry of a `security_headers` preset
+#[derive(Debug)]
+pub struct SecurityHeader {
+  pub name: &'static str,
+  /// `None` disables the header
+  pub value: Option<String>,
+}
+
+/// Returns the entries of a named preset, with every header disabled for `none`
+fn preset(name: &str) -> Option<Vec<SecurityHeader>> {
+  let values = match name {
+    "strict" => Some(STRICT),
+    "basic" => Some(BASIC),
+    "none" => None,
+    _ => {
+      emit_call_site_error!(
+        "Unknown security_headers preset '{}'. Valid presets are: strict, basic, none",
+        name
+      );
+      return None;
+    }
+  };
+  Some(
+    SECURITY_HEADERS
+      .iter()
+      .enumerate()
+      .map(|(index, (_, header))| SecurityHeader {
+        name: header,
+        value: values.map(|values| values[index].to_string()),
+      })
+      .collect(),
+  )
+}
+
+/// Parses `security_headers = preset` or `security_headers(preset, key = "value", key = false)`
+/// from the top-level arguments of an attribute. Keys replace or disable single entries of the
+/// preset, which is `default_preset` when none is named
+pub fn parse_security_headers(args: &[&str], default_preset: Option<&str>) -> Vec<SecurityHeader> {
+  let Some(rest) = args.iter().find_map(|arg| {
+    arg
+      .strip_prefix("security_headers")
+      .map(str::trim_start)
+      .filter(|rest| rest.starts_with(['=', '(']))
+  }) else {
+    return Vec::new();
+  };
+
+  let entries: Vec<&str> = match rest.strip_prefix('=') {
+    Some(name) => vec![name.trim()],
+    None => match balanced_group(rest, 0) {
+      Some(content) => split_top_level(content),
+      None => {
+        emit_call_site_error!("Unclosed parenthesis in security_headers attribute");
+        return Vec::new();
+      }
+    },
+  };
+
+  let (named_preset, keys) = match entries.split_first() {
+    Some((first, keys)) if !first.contains('=') => (Some(*first), keys),
+    _ => (None, entries.as_slice()),
+  };
+  let mut headers = named_preset
+    .or(default_preset)
+    .and_then(preset)
+    .unwrap_or_default();
+
+  for entry in keys {
+    let Some((key, value)) = entry.split_once('=').map(|(k, v)| (k.trim(), v.trim())) else {
+      emit_call_site_error!(
+        "Invalid security_headers entry '{}'. Expected: key = \"value\" or key = false",
+        entry
+      );
+      continue;
+    };
+    let Some((_, name)) = SECURITY_HEADERS.iter().find(|(k, _)| *k == key) else {
+      emit_call_site_error!(
+        "Unknown security header '{}'. Valid keys are: hsts, content_type_options, frame_options, referrer_policy, csp",
+        key
+      );
+      continue;
+    };
+    let value = match value {
+      "false" => None,
+      _ if value.starts_with('"') => {
+        let value = string_value(value);
+        if !validate_header_value(name, &value) {
+          continue;
+        }
+        Some(value)
+      }
+      _ => {
+        emit_call_site_error!(
+          "Invalid value '{}' for security header '{}'. Expected a string literal or false",
+          value,
+          key
+        );
+        continue;
+      }
+    };
+    headers.retain(|header| header.name != *name);
+    headers.push(SecurityHeader { name, value });
+  }
+
+  log_verbose!("Parsed security_headers: [{:?}]", headers);
+  headers
+}
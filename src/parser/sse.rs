@@ -0,0 +1,98 @@
+//! `#[sse(...)]` Server-Sent Events route configuration
+
+use proc_macro_error::emit_call_site_error;
+
+use super::utils::{named_arg, string_value};
+
+/// Interval of axum's default keep-alive comments
+const DEFAULT_KEEP_ALIVE_MS: u64 = 15_000;
+
+/// A parsed `#[sse(...)]` route
+#[derive(Debug)]
+pub struct SseConfig {
+  /// Milliseconds between keep-alive comments; `None` for `keep_alive = false`
+  pub keep_alive_ms: Option<u64>,
+  /// Source of each event's `event:` field
+  pub event: Option<SseField>,
+  /// Source of each event's `id:` field
+  pub id: Option<SseField>,
+}
+
+/// Where an event field comes from
+#[derive(Debug)]
+pub enum SseField {
+  /// The same value for every event: `event = "update"`
+  Constant(String),
+  /// A field of the item, e.g. `["user", "id"]` for `id = user.id`
+  Field(Vec<String>),
+}
+
+/// Parses `keep_alive`, `event` and `id` from the arguments of an `#[sse(...)]` attribute
+pub fn parse_sse(args: &[&str]) -> SseConfig {
+  let keep_alive_ms = match named_arg(args, "keep_alive") {
+    None | Some("true") => Some(DEFAULT_KEEP_ALIVE_MS),
+    Some("false") => None,
+    Some(value) => parse_duration_ms("keep_alive", value),
+  };
+  let config = SseConfig {
+    keep_alive_ms,
+    event: named_arg(args, "event").and_then(|value| parse_field("event", value)),
+    id: named_arg(args, "id").and_then(|value| parse_field("id", value)),
+  };
+  log_verbose!("Parsed sse: [{:?}]", config);
+  config
+}
+
+/// Parses a duration given as a number of seconds (`30`) or a string with a unit (`"500ms"`,
+/// `"15s"`, `"2m"`)
+fn parse_duration_ms(key: &str, value: &str) -> Option<u64> {
+  let text = string_value(value).replace('_', "");
+  let digits = text
+    .find(|ch: char| !ch.is_ascii_digit())
+    .unwrap_or(text.len());
+  let (number, unit) = text.split_at(digits);
+  let multiplier = match unit.trim() {
+    "ms" => Some(1),
+    "" | "s" => Some(1_000),
+    "m" => Some(60_000),
+    _ => None,
+  };
+  match (number.parse::<u64>(), multiplier) {
+    (Ok(number), Some(multiplier)) if number > 0 => number.checked_mul(multiplier),
+    _ => {
+      emit_call_site_error!(
+        "Invalid {} '{}'. Expected a duration such as \"500ms\", \"15s\" or \"2m\", or false",
+        key,
+        text
+      );
+      None
+    }
+  }
+}
+
+/// Parses `"constant"` or a (possibly nested) field name of the stream item
+fn parse_field(key: &str, value: &str) -> Option<SseField> {
+  if value.starts_with('"') {
+    let constant = string_value(value);
+    if constant.contains(['\n', '\r']) {
+      emit_call_site_error!("sse {} '{}' may not contain line breaks", key, constant);
+      return None;
+    }
+    return Some(SseField::Constant(constant));
+  }
+  let segments: Vec<String> = value.split('.').map(|s| s.trim().to_string()).collect();
+  let valid = segments.iter().all(|segment| {
+    syn::parse_str::<syn::Ident>(segment).is_ok()
+      || (!segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit()))
+  });
+  if !valid {
+    emit_call_site_error!(
+      "Invalid sse {} '{}'. Expected a field of the stream item such as {} = kind, or a string",
+      key,
+      value,
+      key
+    );
+    return None;
+  }
+  Some(SseField::Field(segments))
+}
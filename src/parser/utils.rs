@@ -0,0 +1,354 @@
+//! Helpers for scanning stringified attribute arguments
+
+use proc_macro_error::{emit_call_site_error, emit_call_site_warning};
+
+use super::http::{
+  HeaderPolicy, HeaderValueSource, ResponseHeader, normalize_header_name, validate_media_type,
+};
+
+/// Returns the text between the parenthesis at byte offset `open` and its matching close,
+/// skipping over parentheses inside string literals
+pub fn balanced_group(s: &str, open: usize) -> Option<&str> {
+  let mut depth = 0usize;
+  let mut in_string = false;
+  let mut escaped = false;
+
+  for (index, ch) in s[open..].char_indices() {
+    if in_string {
+      match ch {
+        _ if escaped => escaped = false,
+        '\\' => escaped = true,
+        '"' => in_string = false,
+        _ => {}
+      }
+      continue;
+    }
+    match ch {
+      '"' => in_string = true,
+      '(' | '[' => depth += 1,
+      ')' | ']' => {
+        depth = depth.checked_sub(1)?;
+        if depth == 0 {
+          return Some(&s[open + 1..open + index]);
+        }
+      }
+      _ => {}
+    }
+  }
+  None
+}
+
+/// Splits on commas that are not nested inside parentheses, brackets, generic arguments or
+/// string literals. Angle brackets only count when they balance, so a stray `<` in an
+/// expression does not swallow the arguments after it
+pub fn split_top_level(s: &str) -> Vec<&str> {
+  split_commas(s, true).unwrap_or_else(|| split_commas(s, false).unwrap_or_default())
+}
+
+/// Returns `None` when angle brackets are tracked and do not balance
+fn split_commas(s: &str, generics: bool) -> Option<Vec<&str>> {
+  let mut parts = Vec::new();
+  let mut depth = 0usize;
+  let mut angle_depth = 0usize;
+  let mut in_string = false;
+  let mut escaped = false;
+  let mut start = 0;
+  let mut previous = ' ';
+
+  for (index, ch) in s.char_indices() {
+    if in_string {
+      match ch {
+        _ if escaped => escaped = false,
+        '\\' => escaped = true,
+        '"' => in_string = false,
+        _ => {}
+      }
+      continue;
+    }
+    match ch {
+      '"' => in_string = true,
+      '(' | '[' => depth += 1,
+      ')' | ']' => depth = depth.saturating_sub(1),
+      '<' if generics => angle_depth += 1,
+      // `->` and `=>` are arrows, not closing brackets
+      '>' if generics && !matches!(previous, '-' | '=') => {
+        angle_depth = angle_depth.checked_sub(1)?;
+      }
+      ',' if depth == 0 && angle_depth == 0 => {
+        parts.push(s[start..index].trim());
+        start = index + 1;
+      }
+      _ => {}
+    }
+    if !ch.is_whitespace() {
+      previous = ch;
+    }
+  }
+
+  if angle_depth != 0 {
+    return None;
+  }
+  let rest = s[start..].trim();
+  if !rest.is_empty() {
+    parts.push(rest);
+  }
+  Some(parts)
+}
+
+/// Returns the top-level arguments inside the outermost parentheses of an attribute,
+/// e.g. `["\"/users\"", "status = 201"]` for `post ("/users" , status = 201)`
+pub fn attr_args(attr_str: &str) -> Vec<&str> {
+  attr_str
+    .find('(')
+    .and_then(|open| balanced_group(attr_str, open))
+    .map(split_top_level)
+    .unwrap_or_default()
+}
+
+/// Returns the trimmed value of a top-level `key = value` argument
+pub fn named_arg<'a>(args: &[&'a str], key: &str) -> Option<&'a str> {
+  args.iter().find_map(|arg| {
+    let (name, value) = arg.split_once('=')?;
+    (name.trim() == key).then(|| value.trim())
+  })
+}
+
+/// Returns the byte offsets of `keyword(` calls outside string literals, skipping matches that
+/// are part of a longer identifier (so `header` does not match `error_header`)
+pub fn find_calls(s: &str, keyword: &str) -> Vec<usize> {
+  let is_ident = |ch: char| ch.is_alphanumeric() || ch == '_';
+  let mut found = Vec::new();
+  let mut in_string = false;
+  let mut escaped = false;
+
+  for (index, ch) in s.char_indices() {
+    if in_string {
+      match ch {
+        _ if escaped => escaped = false,
+        '\\' => escaped = true,
+        '"' => in_string = false,
+        _ => {}
+      }
+      continue;
+    }
+    if ch == '"' {
+      in_string = true;
+      continue;
+    }
+    if !s[index..].starts_with(keyword) || s[..index].chars().next_back().is_some_and(is_ident) {
+      continue;
+    }
+    let rest = s[index + keyword.len()..].trim_start();
+    if rest.starts_with('(') {
+      found.push(s.len() - rest.len());
+    }
+  }
+  found
+}
+
+/// Returns the contents of a stringified string literal with escapes resolved, or the trimmed
+/// text with quotes removed when it is not a literal
+pub fn string_value(s: &str) -> String {
+  let s = s.trim();
+  match syn::parse_str::<syn::LitStr>(s) {
+    Ok(lit) => lit.value(),
+    Err(_) => s.replace('"', ""),
+  }
+}
+
+/// Parses the `content_type("...")` call of an attribute, validating it as a media type. A
+/// second argument sets the policy for controller-level content types
+pub fn parse_content_type(attr_str: &str, context: &str) -> Option<(String, Option<HeaderPolicy>)> {
+  let open = *find_calls(attr_str, "content_type").first()?;
+  let Some(content) = balanced_group(attr_str, open) else {
+    emit_call_site_warning!("Unclosed parenthesis in content_type attribute");
+    return None;
+  };
+  let args = split_top_level(content);
+  let value = string_value(args.first().copied().unwrap_or_default());
+  let policy = args.get(1).and_then(|arg| HeaderPolicy::parse(arg));
+  log_verbose!("Parsed {} content_type: [{}]", context, value);
+  validate_media_type(&format!("{} content_type", context), &value).then_some((value, policy))
+}
+
+/// Parses every `keyword("name", value[, policy])` or `keyword(name = value)` call
+pub fn parse_header_calls(attr_str: &str, keyword: &str) -> Vec<ResponseHeader> {
+  let mut headers = Vec::new();
+  for open in find_calls(attr_str, keyword) {
+    let Some(content) = balanced_group(attr_str, open) else {
+      emit_call_site_warning!("Unclosed parenthesis in {} attribute", keyword);
+      continue;
+    };
+    let parts = split_top_level(content);
+    let (pair, policy) = match parts.as_slice() {
+      [name, value] => (Some((*name, *value)), None),
+      [name, value, policy] => (Some((*name, *value)), HeaderPolicy::parse(policy)),
+      [single] => (
+        single
+          .split_once('=')
+          .filter(|(name, _)| !name.contains('"')),
+        None,
+      ),
+      _ => (None, None),
+    };
+    match pair {
+      Some((name, value)) => {
+        let name = string_value(name);
+        if name.is_empty() || value.trim().is_empty() || value.trim() == "\"\"" {
+          emit_call_site_warning!("Empty header name or value in {} attribute", keyword);
+        }
+        let Some(name) = normalize_header_name(&name) else {
+          continue;
+        };
+        if let Some(value) = HeaderValueSource::parse(&name, value) {
+          log_verbose!("Parsed {}: [{}: {:?}]", keyword, name, value);
+          headers.push(ResponseHeader {
+            name,
+            value,
+            policy,
+          });
+        }
+      }
+      None => {
+        emit_call_site_warning!(
+          "Invalid {0} attribute format. Expected: {0}(\"name\", value) or {0}(name = value)",
+          keyword
+        );
+      }
+    }
+  }
+  headers
+}
+
+/// Emits an error for headers that set a policy where only controller-level headers may
+pub fn reject_header_policies(headers: &[ResponseHeader], context: &str) {
+  for header in headers.iter().filter(|header| header.policy.is_some()) {
+    emit_call_site_error!(
+      "Header policy on '{}' is not supported on {}. Policies apply to controller-level header(...) and content_type(...) only",
+      header.name,
+      context
+    );
+  }
+}
+
+/// Parses an HTTP status code argument, rejecting values outside 100-599
+pub fn parse_status_code(key: &str, value: &str) -> Option<u16> {
+  match value.parse::<u16>() {
+    Ok(code) if (100..=599).contains(&code) => Some(code),
+    _ => {
+      emit_call_site_error!(
+        "Invalid {} '{}'. Expected an HTTP status code between 100 and 599",
+        key,
+        value
+      );
+      None
+    }
+  }
+}
+
+/// Parses a body size given as a byte count (`1048576`) or a string with a binary unit
+/// (`"50MB"`, `"512 KiB"`). `KB`, `MB` and `GB` are powers of 1024, as in axum's 2 MB default
+pub fn parse_byte_size(key: &str, value: &str) -> Option<usize> {
+  let text = string_value(value).replace('_', "");
+  let digits = text
+    .find(|ch: char| !ch.is_ascii_digit())
+    .unwrap_or(text.len());
+  let (number, unit) = text.split_at(digits);
+  let multiplier: Option<usize> = match unit.trim().to_ascii_uppercase().as_str() {
+    "" | "B" => Some(1),
+    "KB" | "KIB" => Some(1 << 10),
+    "MB" | "MIB" => Some(1 << 20),
+    "GB" | "GIB" => Some(1 << 30),
+    _ => None,
+  };
+  match (number.parse::<usize>(), multiplier) {
+    (Ok(number), Some(multiplier)) if number > 0 => number.checked_mul(multiplier).or_else(|| {
+      emit_call_site_error!("{} '{}' is too large", key, text);
+      None
+    }),
+    _ => {
+      emit_call_site_error!(
+        "Invalid {} '{}'. Expected a size such as \"512KB\", \"50MB\" or a number of bytes",
+        key,
+        text
+      );
+      None
+    }
+  }
+}
+
+/// Parses the `filename = "export.csv"` of a download. Names are kept to printable ASCII without
+/// quotes, backslashes or slashes, so they can be sent in a quoted `Content-Disposition` value
+pub fn parse_filename(value: &str) -> Option<String> {
+  let filename = string_value(value);
+  let valid = value.starts_with('"')
+    && !filename.trim().is_empty()
+    && filename
+      .bytes()
+      .all(|b| (b.is_ascii_graphic() || b == b' ') && !matches!(b, b'"' | b'\\' | b'/'));
+  if !valid {
+    emit_call_site_error!(
+      "Invalid filename {}. Expected a string of printable ASCII without quotes, backslashes or slashes, such as \"export.csv\"",
+      value
+    );
+    return None;
+  }
+  Some(filename)
+}
+
+/// Parses the `realm = "admin area"` of a Basic challenge, kept to printable ASCII without quotes
+/// or backslashes so it can be sent as a quoted `WWW-Authenticate` parameter
+pub fn parse_realm(value: &str) -> Option<String> {
+  let realm = string_value(value);
+  let valid = value.starts_with('"')
+    && !realm.trim().is_empty()
+    && realm
+      .bytes()
+      .all(|b| (b.is_ascii_graphic() || b == b' ') && !matches!(b, b'"' | b'\\'));
+  if !valid {
+    emit_call_site_error!(
+      "Invalid realm {}. Expected a string of printable ASCII without quotes or backslashes, such as \"admin\"",
+      value
+    );
+    return None;
+  }
+  Some(realm)
+}
+
+/// Parses a `["type/subtype", ...]` list (or a single string) of media types for `consumes` and
+/// `produces`. Only `consumes` may use wildcards such as `text/*`
+pub fn parse_media_types(key: &str, value: &str) -> Option<Vec<String>> {
+  let items = match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+    Some(items) => split_top_level(items),
+    None => vec![value],
+  };
+  let mut media_types = Vec::with_capacity(items.len());
+  for item in items {
+    if !item.starts_with('"') {
+      emit_call_site_error!(
+        "Invalid {} entry '{}'. Expected a list of media types (e.g., {} = [\"application/json\"])",
+        key,
+        item,
+        key
+      );
+      return None;
+    }
+    let media_type = string_value(item).to_ascii_lowercase();
+    if !validate_media_type(key, &media_type) {
+      return None;
+    }
+    if key == "produces" && media_type.contains('*') {
+      emit_call_site_error!(
+        "Invalid produces entry '{}'. Produced media types must be concrete, without wildcards",
+        media_type
+      );
+      return None;
+    }
+    media_types.push(media_type);
+  }
+  if media_types.is_empty() {
+    emit_call_site_error!("Empty {} list", key);
+    return None;
+  }
+  Some(media_types)
+}
@@ -0,0 +1,53 @@
+//! `#[ws(...)]` WebSocket route configuration
+
+use proc_macro_error::emit_call_site_error;
+
+use super::utils::{named_arg, parse_byte_size, split_top_level, string_value};
+
+/// A parsed `#[ws(...)]` route
+#[derive(Debug)]
+pub struct WsConfig {
+  /// Subprotocols the server speaks, in order of preference
+  pub protocols: Vec<String>,
+  /// Largest message accepted from the client, in bytes
+  pub max_message_size: Option<usize>,
+}
+
+/// Parses `protocols = [...]` and `max_message_size = "64KB"` from the arguments of a
+/// `#[ws(...)]` attribute
+pub fn parse_ws(args: &[&str]) -> WsConfig {
+  let protocols = named_arg(args, "protocols")
+    .map(|value| {
+      let items = match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        Some(items) => split_top_level(items),
+        None => vec![value],
+      };
+      items
+        .into_iter()
+        .filter_map(|item| {
+          let protocol = string_value(item);
+          // Subprotocol names are HTTP tokens
+          let valid = item.starts_with('"')
+            && !protocol.is_empty()
+            && protocol
+              .bytes()
+              .all(|b| b.is_ascii_graphic() && !b"()<>@,;:\\\"/[]?={}".contains(&b));
+          if !valid {
+            emit_call_site_error!(
+              "Invalid WebSocket subprotocol {}. Expected a string such as \"chat.v1\"",
+              item
+            );
+          }
+          valid.then_some(protocol)
+        })
+        .collect()
+    })
+    .unwrap_or_default();
+  let config = WsConfig {
+    protocols,
+    max_message_size: named_arg(args, "max_message_size")
+      .and_then(|value| parse_byte_size("max_message_size", value)),
+  };
+  log_verbose!("Parsed ws: [{:?}]", config);
+  config
+}
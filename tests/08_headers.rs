@@ -32,6 +32,11 @@ impl HeaderController {
   async fn content(content_type: String) -> String {
     format!("ct:{}", content_type)
   }
+
+  #[get("/optional", extract(x_request_id = HeaderParam))]
+  async fn optional(x_request_id: Option<String>) -> String {
+    format!("{:?}", x_request_id)
+  }
 }
 
 #[tokio::test]
@@ -194,3 +199,21 @@ async fn test_case_insensitive_header() {
     .unwrap();
   assert_eq!(&body[..], b"auth:Bearer case-test");
 }
+
+#[tokio::test]
+async fn test_optional_header() {
+  for (header, expected) in [(Some("abc"), "Some(\"abc\")"), (None, "None")] {
+    let mut builder = Request::builder().uri("/api/optional");
+    if let Some(value) = header {
+      builder = builder.header("x-request-id", value);
+    }
+    let response = HeaderController::router()
+      .oneshot(builder.body(Body::empty()).unwrap())
+      .await
+      .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+      .await
+      .unwrap();
+    assert_eq!(&body[..], expected.as_bytes());
+  }
+}
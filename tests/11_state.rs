@@ -311,3 +311,48 @@ async fn test_both_fields_after_modifications() {
     .unwrap();
   assert_eq!(&body[..], b"count:1,msg:Message 100");
 }
+
+#[derive(Clone)]
+struct Pair<A, B>(A, B);
+
+struct PairController;
+
+// A generic state type whose arguments are separated by a comma
+#[controller(path = "/pair", state = Pair<u8, u16>)]
+impl PairController {
+  #[get("/")]
+  async fn index() -> &'static str {
+    "ok"
+  }
+
+  #[get("/sum", extract(pair = State))]
+  async fn sum(pair: Pair<u8, u16>) -> String {
+    (pair.0 as u16 + pair.1).to_string()
+  }
+}
+
+#[tokio::test]
+async fn test_generic_state_type() {
+  let app = PairController::router().with_state(Pair(1, 2));
+
+  let response = app
+    .clone()
+    .oneshot(Request::builder().uri("/pair").body(Body::empty()).unwrap())
+    .await
+    .unwrap();
+  assert_eq!(response.status(), StatusCode::OK);
+
+  let response = app
+    .oneshot(
+      Request::builder()
+        .uri("/pair/sum")
+        .body(Body::empty())
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+  let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+    .await
+    .unwrap();
+  assert_eq!(&body[..], b"3");
+}
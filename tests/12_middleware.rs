@@ -93,8 +93,7 @@ async fn test_middleware_invalid_token() {
 
 // Test multiple middlewares
 async fn logging_middleware(request: Request<Body>, next: Next) -> Response {
-  let response = next.run(request).await;
-  response
+  next.run(request).await
 }
 
 struct MultiMiddlewareController;
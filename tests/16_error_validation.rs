@@ -214,6 +214,386 @@ mod test_invalid_middleware {
   }
 }
 
+// Test 11: Invalid success status code (should fail)
+#[cfg(feature = "test_invalid_status")]
+#[allow(dead_code, unused)]
+mod test_invalid_status {
+  use route_controller::{controller, post};
+
+  struct TestController;
+
+  // This should fail: status codes must be between 100 and 599
+  #[controller(path = "/api")]
+  impl TestController {
+    #[post("/test", status = 1000)]
+    async fn test() -> String {
+      "test".to_string()
+    }
+  }
+}
+
+// Test 12: Invalid Location placeholder (should fail)
+#[cfg(feature = "test_invalid_location")]
+#[allow(dead_code, unused)]
+mod test_invalid_location {
+  use route_controller::{controller, post};
+
+  struct TestController;
+
+  // This should fail: placeholders must be field names
+  #[controller(path = "/api")]
+  impl TestController {
+    #[post("/test", location = "/users/{user-id}")]
+    async fn test() -> String {
+      "test".to_string()
+    }
+  }
+}
+
+// Test 13: Invalid header name (should fail)
+#[cfg(feature = "test_invalid_header_name")]
+#[allow(dead_code, unused)]
+mod test_invalid_header_name {
+  use route_controller::{controller, get};
+
+  struct TestController;
+
+  // This should fail: header names may not contain spaces
+  #[controller(path = "/api")]
+  impl TestController {
+    #[get("/test", header("x api", "1"))]
+    async fn test() -> String {
+      "test".to_string()
+    }
+  }
+}
+
+// Test 14: Invalid header value (should fail)
+#[cfg(feature = "test_invalid_header_value")]
+#[allow(dead_code, unused)]
+mod test_invalid_header_value {
+  use route_controller::{controller, get};
+
+  struct TestController;
+
+  // This should fail: header values may not contain control characters
+  #[controller(path = "/api")]
+  impl TestController {
+    #[get("/test", header("x-note", "line\nbreak"))]
+    async fn test() -> String {
+      "test".to_string()
+    }
+  }
+}
+
+// Test 15: Invalid content type (should fail)
+#[cfg(feature = "test_invalid_content_type")]
+#[allow(dead_code, unused)]
+mod test_invalid_content_type {
+  use route_controller::{controller, get};
+
+  struct TestController;
+
+  // This should fail: content types must be type/subtype
+  #[controller(path = "/api")]
+  impl TestController {
+    #[get("/test", content_type("json"))]
+    async fn test() -> String {
+      "test".to_string()
+    }
+  }
+}
+
+#[cfg(feature = "test_invalid_security_headers")]
+#[allow(dead_code, unused)]
+mod test_invalid_security_headers {
+  use route_controller::{controller, get};
+
+  struct TestController;
+
+  // This should fail: 'paranoid' is not a security_headers preset
+  #[controller(path = "/api", security_headers = paranoid)]
+  impl TestController {
+    #[get("/test")]
+    async fn test() -> String {
+      "test".to_string()
+    }
+  }
+}
+
+#[cfg(feature = "test_invalid_cors")]
+#[allow(dead_code, unused)]
+mod test_invalid_cors {
+  use route_controller::{controller, get};
+
+  struct TestController;
+
+  // This should fail: credentials cannot be allowed for any origin
+  #[controller(path = "/api", cors(origins = any, credentials))]
+  impl TestController {
+    #[get("/test")]
+    async fn test() -> String {
+      "test".to_string()
+    }
+  }
+}
+
+#[cfg(feature = "test_invalid_body_limit")]
+#[allow(dead_code, unused)]
+mod test_invalid_body_limit {
+  use route_controller::{controller, post};
+
+  struct TestController;
+
+  // This should fail: 'XB' is not a size unit
+  #[controller(path = "/api")]
+  impl TestController {
+    #[post("/test", extract(data = Bytes), body_limit = "50XB")]
+    async fn test(data: Vec<u8>) -> String {
+      "test".to_string()
+    }
+  }
+}
+
+#[cfg(feature = "test_invalid_negotiate")]
+#[allow(dead_code, unused)]
+mod test_invalid_negotiate {
+  use route_controller::{controller, get};
+
+  struct TestController;
+
+  // This should fail: 'yaml' is not a response format
+  #[controller(path = "/api")]
+  impl TestController {
+    #[get("/test", negotiate(json, yaml))]
+    async fn test() -> String {
+      "test".to_string()
+    }
+
+    // This should fail: an XML element name cannot start with a digit
+    #[get("/list", negotiate(json), xml_root = "1st")]
+    async fn list() -> Vec<String> {
+      Vec::new()
+    }
+  }
+}
+
+#[cfg(feature = "test_invalid_stream")]
+#[allow(dead_code, unused)]
+mod test_invalid_stream {
+  use route_controller::{controller, get};
+
+  struct TestController;
+
+  // This should fail: 'xml' is not a stream format
+  #[controller(path = "/api")]
+  impl TestController {
+    #[get("/test", stream = xml)]
+    async fn test() -> String {
+      "test".to_string()
+    }
+  }
+}
+
+#[cfg(feature = "test_invalid_sse")]
+#[allow(dead_code, unused)]
+mod test_invalid_sse {
+  use futures_util::Stream;
+  use route_controller::{controller, sse};
+
+  struct TestController;
+
+  // This should fail: 'soon' is not a keep-alive interval
+  #[controller(path = "/api")]
+  impl TestController {
+    #[sse("/events", keep_alive = "soon")]
+    async fn events() -> impl Stream<Item = u32> {
+      futures_util::stream::iter([1])
+    }
+  }
+}
+
+#[cfg(feature = "test_invalid_ws")]
+#[allow(dead_code, unused)]
+mod test_invalid_ws {
+  use axum::extract::ws::WebSocket;
+  use route_controller::{controller, ws};
+
+  struct TestController;
+
+  // This should fail: WebSocket handshakes have no body to extract
+  #[controller(path = "/api")]
+  impl TestController {
+    #[ws("/socket", extract(data = Json))]
+    async fn socket(socket: WebSocket, data: String) {}
+  }
+}
+
+#[cfg(feature = "test_invalid_ws_return")]
+#[allow(dead_code, unused)]
+mod test_invalid_ws_return {
+  use axum::extract::ws::WebSocket;
+  use route_controller::{controller, ws};
+
+  struct TestController;
+
+  // This should fail: the handler runs after the upgrade, so its Err could not be answered
+  #[controller(path = "/api")]
+  impl TestController {
+    #[ws("/socket")]
+    async fn socket(socket: WebSocket) -> Result<(), String> {
+      Ok(())
+    }
+  }
+}
+
+#[cfg(feature = "test_invalid_rpc")]
+#[allow(dead_code, unused)]
+mod test_invalid_rpc {
+  use route_controller::{rpc, rpc_controller};
+
+  struct TestController;
+
+  // This should fail: RPC params come from the call, not from the request body
+  #[rpc_controller(path = "/rpc")]
+  impl TestController {
+    #[rpc(extract(data = Json))]
+    async fn echo(data: String) -> String {
+      data
+    }
+  }
+}
+
+#[cfg(feature = "test_rpc_requires")]
+#[allow(dead_code, unused)]
+mod test_rpc_requires {
+  use route_controller::{requires, rpc, rpc_controller};
+
+  struct TestController;
+
+  // This should fail: #[requires] is not enforced by the JSON-RPC dispatcher
+  #[rpc_controller(path = "/rpc")]
+  impl TestController {
+    #[rpc]
+    #[requires(role = "admin")]
+    async fn secret() -> String {
+      "top secret".to_string()
+    }
+  }
+}
+
+#[cfg(feature = "test_invalid_filename")]
+#[allow(dead_code, unused)]
+mod test_invalid_filename {
+  use route_controller::{controller, get};
+
+  struct TestController;
+
+  // This should fail: a filename may not contain path separators
+  #[controller(path = "/api")]
+  impl TestController {
+    #[get("/export", filename = "../export.csv")]
+    async fn export() -> &'static str {
+      "a,b"
+    }
+  }
+}
+
+#[cfg(feature = "test_invalid_body_target")]
+#[allow(dead_code, unused)]
+mod test_invalid_body_target {
+  use route_controller::{controller, post};
+
+  struct TestController;
+
+  // This should fail: Bytes fills Vec<u8>, Box<[u8]> or Bytes, not any Box
+  #[controller(path = "/api")]
+  impl TestController {
+    #[post("/upload", extract(data = Bytes))]
+    async fn upload(data: Box<Vec<u8>>) -> String {
+      data.len().to_string()
+    }
+  }
+}
+
+#[cfg(all(feature = "test_missing_body_feature", not(feature = "xml")))]
+#[allow(dead_code, unused)]
+mod test_missing_body_feature {
+  use route_controller::{controller, post};
+
+  struct TestController;
+
+  // This should fail: XmlBody needs the xml feature
+  #[controller(path = "/api")]
+  impl TestController {
+    #[post("/orders", extract(order = XmlBody))]
+    async fn create(order: String) -> String {
+      order
+    }
+  }
+}
+
+#[cfg(all(feature = "test_missing_binary_feature", not(feature = "msgpack")))]
+#[allow(dead_code, unused)]
+mod test_missing_binary_feature {
+  use route_controller::{controller, post};
+
+  struct TestController;
+
+  // This should fail: MsgPack needs the msgpack feature
+  #[controller(path = "/api")]
+  impl TestController {
+    #[post("/readings", extract(reading = MsgPack))]
+    async fn record(reading: String) -> String {
+      reading
+    }
+  }
+}
+
+// Documentation tests for error messages
+/// # Error Validation Examples
+///
+/// This test file demonstrates the various compile-time validations:
+///
+/// ## Invalid Extractor Type
+/// ```compile_fail
+/// # use route_controller::{controller, get};
+/// struct Controller;
+/// #[controller(path = "/api")]
+/// impl Controller {
+///     #[get("/test", extract(data = InvalidType))]
+///     async fn test(data: String) -> String { data }
+/// }
+/// ```
+///
+/// ## Multiple Body Extractors
+/// ```compile_fail
+/// # use route_controller::{controller, post};
+/// # #[derive(serde::Deserialize)]
+/// # struct A { x: String }
+/// # #[derive(serde::Deserialize)]
+/// # struct B { y: String }
+/// struct Controller;
+/// #[controller(path = "/api")]
+/// impl Controller {
+///     #[post("/test", extract(a = Json, b = Form))]
+///     async fn test(a: A, b: B) -> String { "ok".to_string() }
+/// }
+/// ```
+///
+/// ## Missing Path Extractor
+/// ```compile_fail
+/// # use route_controller::{controller, get};
+/// struct Controller;
+/// #[controller(path = "/api")]
+/// impl Controller {
+///     #[get("/users/{id}")]
+///     async fn test(id: u32) -> String { format!("{}", id) }
+/// }
+/// ```
+#[allow(dead_code)]
+struct ErrorDocumentation;
+
 // Working test that should compile successfully
 #[cfg(test)]
 mod valid_usage_test {
@@ -285,46 +665,41 @@ mod valid_usage_test {
   }
 }
 
-// Documentation tests for error messages
-/// # Error Validation Examples
-///
-/// This test file demonstrates the various compile-time validations:
-///
-/// ## Invalid Extractor Type
-/// ```compile_fail
-/// # use route_controller::{controller, get};
-/// struct Controller;
-/// #[controller(path = "/api")]
-/// impl Controller {
-///     #[get("/test", extract(data = InvalidType))]
-///     async fn test(data: String) -> String { data }
-/// }
-/// ```
-///
-/// ## Multiple Body Extractors
-/// ```compile_fail
-/// # use route_controller::{controller, post};
-/// # #[derive(serde::Deserialize)]
-/// # struct A { x: String }
-/// # #[derive(serde::Deserialize)]
-/// # struct B { y: String }
-/// struct Controller;
-/// #[controller(path = "/api")]
-/// impl Controller {
-///     #[post("/test", extract(a = Json, b = Form))]
-///     async fn test(a: A, b: B) -> String { "ok".to_string() }
-/// }
-/// ```
-///
-/// ## Missing Path Extractor
-/// ```compile_fail
-/// # use route_controller::{controller, get};
-/// struct Controller;
-/// #[controller(path = "/api")]
-/// impl Controller {
-///     #[get("/users/{id}")]
-///     async fn test(id: u32) -> String { format!("{}", id) }
-/// }
-/// ```
-#[allow(dead_code)]
-struct ErrorDocumentation;
+#[cfg(all(feature = "test_missing_csv_feature", not(feature = "csv")))]
+#[allow(dead_code, unused)]
+mod test_missing_csv_feature {
+  use route_controller::{controller, post};
+
+  struct TestController;
+
+  // This should fail: Csv needs the csv feature
+  #[controller(path = "/api")]
+  impl TestController {
+    #[post("/import", extract(rows = Csv))]
+    async fn import(rows: Vec<String>) -> String {
+      rows.join(",")
+    }
+  }
+}
+
+#[cfg(feature = "test_controller_with_header")]
+#[allow(dead_code, unused)]
+mod test_controller_with_header {
+  use axum::http::request::Parts;
+  use route_controller::{controller, get};
+
+  fn request_id(_parts: &Parts, _state: &()) -> String {
+    "generated".to_string()
+  }
+
+  struct TestController;
+
+  // This should fail: controller headers are layered without the router state
+  #[controller(path = "/api", header("x-request-id", with = request_id))]
+  impl TestController {
+    #[get("/test")]
+    async fn test() -> String {
+      "test".to_string()
+    }
+  }
+}
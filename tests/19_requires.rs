@@ -0,0 +1,215 @@
+//! Integration tests for role and permission requirements
+//!
+//! Tests `#[requires(...)]` on handlers and controllers
+
+use axum::body::Body;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::{Request, StatusCode};
+use route_controller::{controller, get, post, requires};
+use tower::ServiceExt;
+
+// Principal resolved from "x-user", "x-roles" and "x-permissions" headers
+struct CurrentUser {
+  roles: Vec<String>,
+  permissions: Vec<String>,
+}
+
+impl CurrentUser {
+  fn has_role(&self, role: &str) -> bool {
+    self.roles.iter().any(|r| r == role)
+  }
+
+  fn has_permission(&self, permission: &str) -> bool {
+    self.permissions.iter().any(|p| p == permission)
+  }
+}
+
+fn header_list(parts: &Parts, name: &str) -> Vec<String> {
+  parts
+    .headers
+    .get(name)
+    .and_then(|v| v.to_str().ok())
+    .map(|v| v.split(',').map(|s| s.trim().to_string()).collect())
+    .unwrap_or_default()
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for CurrentUser {
+  type Rejection = StatusCode;
+
+  async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+    if !parts.headers.contains_key("x-user") {
+      return Err(StatusCode::UNAUTHORIZED);
+    }
+    Ok(CurrentUser {
+      roles: header_list(parts, "x-roles"),
+      permissions: header_list(parts, "x-permissions"),
+    })
+  }
+}
+
+struct OrderController;
+
+#[controller(path = "/orders", principal = CurrentUser)]
+impl OrderController {
+  #[get("/public")]
+  async fn public() -> &'static str {
+    "public"
+  }
+
+  #[get("/admin")]
+  #[requires(role = "admin")]
+  async fn admin() -> &'static str {
+    "admin"
+  }
+
+  #[post("/{id}", extract(id = Path, note = Text))]
+  #[requires(role = "staff", permission = "orders:write")]
+  async fn update(id: u32, note: String) -> String {
+    format!("updated:{}:{}", id, note)
+  }
+}
+
+struct AdminController;
+
+#[controller(path = "/admin", principal = CurrentUser)]
+#[requires(role = "admin")]
+impl AdminController {
+  #[get("/dashboard")]
+  async fn dashboard() -> &'static str {
+    "dashboard"
+  }
+
+  #[get("/audit")]
+  #[requires(permission = "audit:read")]
+  async fn audit() -> &'static str {
+    "audit"
+  }
+}
+
+#[derive(Clone)]
+struct AppState {
+  name: String,
+}
+
+struct StatefulController;
+
+#[controller(path = "/stateful", principal = CurrentUser)]
+impl StatefulController {
+  #[get("/", extract(state = State))]
+  #[requires(role = "admin")]
+  async fn index(state: AppState) -> String {
+    state.name
+  }
+}
+
+async fn send(app: axum::Router, method: &str, uri: &str, headers: &[(&str, &str)]) -> StatusCode {
+  let mut builder = Request::builder()
+    .method(method)
+    .uri(uri)
+    .header("content-type", "text/plain");
+  for (name, value) in headers {
+    builder = builder.header(*name, *value);
+  }
+  app
+    .oneshot(builder.body(Body::from("note")).unwrap())
+    .await
+    .unwrap()
+    .status()
+}
+
+#[tokio::test]
+async fn test_unprotected_route_is_open() {
+  let status = send(OrderController::router(), "GET", "/orders/public", &[]).await;
+  assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_missing_principal_is_unauthorized() {
+  let status = send(OrderController::router(), "GET", "/orders/admin", &[]).await;
+  assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_missing_role_is_forbidden() {
+  let headers = [("x-user", "bob"), ("x-roles", "staff")];
+  let status = send(OrderController::router(), "GET", "/orders/admin", &headers).await;
+  assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_matching_role_is_allowed() {
+  let headers = [("x-user", "alice"), ("x-roles", "admin")];
+  let status = send(OrderController::router(), "GET", "/orders/admin", &headers).await;
+  assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_role_and_permission_both_required() {
+  let app = OrderController::router();
+
+  let role_only = [("x-user", "bob"), ("x-roles", "staff")];
+  let status = send(app.clone(), "POST", "/orders/7", &role_only).await;
+  assert_eq!(status, StatusCode::FORBIDDEN);
+
+  let both = [
+    ("x-user", "bob"),
+    ("x-roles", "staff"),
+    ("x-permissions", "orders:write"),
+  ];
+  let status = send(app, "POST", "/orders/7", &both).await;
+  assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_controller_level_requirement() {
+  let app = AdminController::router();
+
+  let staff = [("x-user", "bob"), ("x-roles", "staff")];
+  let status = send(app.clone(), "GET", "/admin/dashboard", &staff).await;
+  assert_eq!(status, StatusCode::FORBIDDEN);
+
+  let admin = [("x-user", "alice"), ("x-roles", "admin")];
+  let status = send(app.clone(), "GET", "/admin/dashboard", &admin).await;
+  assert_eq!(status, StatusCode::OK);
+
+  // Route-level requirements add to the controller-level ones
+  let status = send(app.clone(), "GET", "/admin/audit", &admin).await;
+  assert_eq!(status, StatusCode::FORBIDDEN);
+
+  let auditor = [
+    ("x-user", "alice"),
+    ("x-roles", "admin"),
+    ("x-permissions", "audit:read"),
+  ];
+  let status = send(app, "GET", "/admin/audit", &auditor).await;
+  assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_requirement_with_state() {
+  let app = StatefulController::router().with_state(AppState {
+    name: "stateful".to_string(),
+  });
+
+  let admin = [("x-user", "alice"), ("x-roles", "admin")];
+  let status = send(app, "GET", "/stateful", &admin).await;
+  assert_eq!(status, StatusCode::OK);
+}
+
+#[test]
+fn test_route_requirements_metadata() {
+  let routes = OrderController::ROUTE_REQUIREMENTS;
+  assert_eq!(routes.len(), 2);
+  assert!(routes.contains(&("GET", "/orders/admin", &["admin"][..], &[][..])));
+  assert!(routes.contains(&(
+    "POST",
+    "/orders/{id}",
+    &["staff"][..],
+    &["orders:write"][..]
+  )));
+
+  let routes = AdminController::ROUTE_REQUIREMENTS;
+  assert!(routes.contains(&("GET", "/admin/dashboard", &["admin"][..], &[][..])));
+  assert!(routes.contains(&("GET", "/admin/audit", &["admin"][..], &["audit:read"][..])));
+}
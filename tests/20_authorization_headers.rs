@@ -0,0 +1,236 @@
+//! Integration tests for BearerToken and BasicAuth extractors (requires 'headers' feature)
+//!
+//! Tests parsing of the Authorization header and WWW-Authenticate challenges
+
+#![cfg(feature = "headers")]
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use route_controller::{controller, get};
+use tower::ServiceExt;
+
+struct AuthController;
+
+#[controller(path = "/auth")]
+impl AuthController {
+  #[get("/bearer", extract(token = BearerToken))]
+  async fn bearer(token: String) -> String {
+    format!("token:{}", token)
+  }
+
+  #[get("/bearer-optional", extract(token = BearerToken))]
+  async fn bearer_optional(token: Option<String>) -> String {
+    match token {
+      Some(token) => format!("token:{}", token),
+      None => "anonymous".to_string(),
+    }
+  }
+
+  #[get("/basic", extract(creds = BasicAuth))]
+  async fn basic(creds: (String, String)) -> String {
+    format!("user:{},pass:{}", creds.0, creds.1)
+  }
+
+  #[get("/basic-optional", extract(creds = BasicAuth))]
+  async fn basic_optional(creds: Option<(String, String)>) -> String {
+    match creds {
+      Some((user, _)) => format!("user:{}", user),
+      None => "anonymous".to_string(),
+    }
+  }
+
+  #[get("/items/{id}", extract(id = Path, token = BearerToken))]
+  async fn item(id: u32, token: String) -> String {
+    format!("id:{},token:{}", id, token)
+  }
+
+  #[get("/admin", extract(creds = BasicAuth), realm = "admin area")]
+  async fn admin(creds: (String, String)) -> String {
+    creds.0
+  }
+}
+
+struct StaffController;
+
+#[controller(path = "/staff", realm = "staff")]
+impl StaffController {
+  #[get("/", extract(creds = BasicAuth))]
+  async fn index(creds: (String, String)) -> String {
+    creds.0
+  }
+}
+
+async fn call(uri: &str, authorization: Option<&str>) -> (StatusCode, Option<String>, String) {
+  let mut builder = Request::builder().uri(uri);
+  if let Some(value) = authorization {
+    builder = builder.header("authorization", value);
+  }
+  let response = AuthController::router()
+    .oneshot(builder.body(Body::empty()).unwrap())
+    .await
+    .unwrap();
+  let status = response.status();
+  let challenge = response
+    .headers()
+    .get("www-authenticate")
+    .map(|v| v.to_str().unwrap().to_string());
+  let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+    .await
+    .unwrap();
+  (status, challenge, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn test_bearer_token() {
+  let (status, _, body) = call("/auth/bearer", Some("Bearer abc.def-123")).await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "token:abc.def-123");
+}
+
+#[tokio::test]
+async fn test_bearer_scheme_is_case_insensitive() {
+  let (status, _, body) = call("/auth/bearer", Some("bearer xyz")).await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "token:xyz");
+}
+
+#[tokio::test]
+async fn test_bearer_missing() {
+  let (status, challenge, _) = call("/auth/bearer", None).await;
+  assert_eq!(status, StatusCode::UNAUTHORIZED);
+  assert_eq!(challenge.as_deref(), Some("Bearer"));
+}
+
+#[tokio::test]
+async fn test_bearer_malformed() {
+  for value in ["Bearer", "Bearer not a token", "Basic abc", "Bearer a=b"] {
+    let (status, challenge, _) = call("/auth/bearer", Some(value)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "value: {}", value);
+    assert!(challenge.unwrap().contains("error=\"invalid_token\""));
+  }
+}
+
+#[tokio::test]
+async fn test_bearer_optional() {
+  let (status, _, body) = call("/auth/bearer-optional", None).await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "anonymous");
+
+  let (status, _, body) = call("/auth/bearer-optional", Some("Bearer t0k3n")).await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "token:t0k3n");
+
+  // A malformed header is still rejected when auth is optional
+  let (status, _, _) = call("/auth/bearer-optional", Some("Bearer")).await;
+  assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_basic_auth() {
+  // "Aladdin:open sesame" from RFC 7617
+  let (status, _, body) = call("/auth/basic", Some("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==")).await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "user:Aladdin,pass:open sesame");
+}
+
+#[tokio::test]
+async fn test_basic_auth_password_with_colon() {
+  // "user:pa:ss"
+  let (status, _, body) = call("/auth/basic", Some("Basic dXNlcjpwYTpzcw==")).await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "user:user,pass:pa:ss");
+}
+
+#[tokio::test]
+async fn test_basic_auth_missing() {
+  let (status, challenge, _) = call("/auth/basic", None).await;
+  assert_eq!(status, StatusCode::UNAUTHORIZED);
+  assert!(challenge.unwrap().starts_with("Basic realm="));
+}
+
+#[tokio::test]
+async fn test_basic_auth_malformed() {
+  // Invalid base64, no colon separator ("user"), wrong scheme
+  for value in ["Basic !!!!", "Basic dXNlcg==", "Bearer abc", "Basic QQ=A"] {
+    let (status, challenge, _) = call("/auth/basic", Some(value)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "value: {}", value);
+    assert!(challenge.unwrap().starts_with("Basic realm="));
+  }
+}
+
+#[tokio::test]
+async fn test_basic_auth_padding() {
+  // "u:p", "u:pa" and "u:pas" need no, two and one padding characters
+  for (value, password) in [("dTpw", "p"), ("dTpwYQ==", "pa"), ("dTpwYXM=", "pas")] {
+    let (status, _, body) = call("/auth/basic", Some(&format!("Basic {}", value))).await;
+    assert_eq!(status, StatusCode::OK, "value: {}", value);
+    assert_eq!(body, format!("user:u,pass:{}", password));
+  }
+
+  // Missing padding, padding before the last block and a padding-only value
+  for value in [
+    "Basic dTpwYQ",
+    "Basic dTpwYQ=",
+    "Basic dQ==dTpw",
+    "Basic ====",
+  ] {
+    let (status, _, _) = call("/auth/basic", Some(value)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "value: {}", value);
+  }
+}
+
+#[tokio::test]
+async fn test_basic_auth_invalid_characters() {
+  // URL-safe alphabet, characters outside base64 and non-UTF-8 credentials
+  for value in [
+    "Basic dTpw-_==",
+    "Basic dTpw*Q==",
+    "Basic dTp w",
+    "Basic //79",
+  ] {
+    let (status, challenge, _) = call("/auth/basic", Some(value)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "value: {}", value);
+    assert!(challenge.unwrap().starts_with("Basic realm="));
+  }
+}
+
+#[tokio::test]
+async fn test_basic_auth_realm() {
+  let (_, challenge, _) = call("/auth/basic", None).await;
+  assert_eq!(challenge.unwrap(), "Basic realm=\"api\", charset=\"UTF-8\"");
+
+  let (_, challenge, _) = call("/auth/admin", None).await;
+  assert_eq!(
+    challenge.unwrap(),
+    "Basic realm=\"admin area\", charset=\"UTF-8\""
+  );
+
+  let request = Request::builder()
+    .uri("/staff")
+    .body(Body::empty())
+    .unwrap();
+  let response = StaffController::router().oneshot(request).await.unwrap();
+  assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+  assert_eq!(
+    response.headers()["www-authenticate"],
+    "Basic realm=\"staff\", charset=\"UTF-8\""
+  );
+}
+
+#[tokio::test]
+async fn test_basic_auth_optional() {
+  let (status, _, body) = call("/auth/basic-optional", None).await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "anonymous");
+
+  let (status, _, body) = call("/auth/basic-optional", Some("Basic dXNlcjpwYTpzcw==")).await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "user:user");
+}
+
+#[tokio::test]
+async fn test_bearer_with_path() {
+  let (status, _, body) = call("/auth/items/5", Some("Bearer abc")).await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "id:5,token:abc");
+}
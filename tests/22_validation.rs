@@ -0,0 +1,172 @@
+//! Integration tests for request validation
+//!
+//! Tests `validate` on Json, Form and Query extractors and the `#[validate]` route attribute
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::IntoResponse;
+use route_controller::{controller, get, post, validate};
+use serde::Deserialize;
+use tower::ServiceExt;
+use validator::Validate;
+
+#[derive(Deserialize, Validate)]
+struct NewUser {
+  #[validate(length(min = 3))]
+  name: String,
+  #[validate(email)]
+  email: String,
+}
+
+#[derive(Deserialize, Validate)]
+struct Paging {
+  #[validate(range(min = 1, max = 100))]
+  limit: u32,
+}
+
+struct UserController;
+
+#[controller(path = "/users")]
+impl UserController {
+  #[post("/", extract(user = Json(validate)))]
+  async fn create(user: NewUser) -> String {
+    format!("created:{}", user.name)
+  }
+
+  #[post("/form", extract(user = Form(validate)))]
+  async fn create_form(user: NewUser) -> String {
+    format!("created:{}", user.email)
+  }
+
+  #[post("/unchecked", extract(user = Json))]
+  async fn create_unchecked(user: NewUser) -> String {
+    format!("created:{}", user.name)
+  }
+
+  #[get("/{id}/friends", extract(id = Path, paging = Query))]
+  #[validate]
+  async fn friends(id: u32, paging: Paging) -> String {
+    format!("id:{},limit:{}", id, paging.limit)
+  }
+}
+
+// Custom error body format for the whole controller
+fn validation_response(errors: validator::ValidationErrors) -> impl IntoResponse {
+  let mut fields: Vec<_> = errors
+    .field_errors()
+    .keys()
+    .map(|k| k.to_string())
+    .collect();
+  fields.sort();
+  (
+    StatusCode::BAD_REQUEST,
+    format!("invalid fields: {}", fields.join(",")),
+  )
+}
+
+struct CustomErrorController;
+
+#[controller(path = "/custom", validation_error = validation_response)]
+impl CustomErrorController {
+  #[post("/", extract(user = Json(validate)))]
+  async fn create(user: NewUser) -> String {
+    format!("created:{}", user.name)
+  }
+}
+
+async fn send(app: axum::Router, request: Request<Body>) -> (StatusCode, String) {
+  let response = app.oneshot(request).await.unwrap();
+  let status = response.status();
+  let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+    .await
+    .unwrap();
+  (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+fn json_post(uri: &str, body: &str) -> Request<Body> {
+  Request::builder()
+    .method("POST")
+    .uri(uri)
+    .header("content-type", "application/json")
+    .body(Body::from(body.to_string()))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_valid_json() {
+  let (status, body) = send(
+    UserController::router(),
+    json_post("/users", r#"{"name":"alice","email":"alice@example.com"}"#),
+  )
+  .await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "created:alice");
+}
+
+#[tokio::test]
+async fn test_invalid_json_reports_fields() {
+  let (status, body) = send(
+    UserController::router(),
+    json_post("/users", r#"{"name":"al","email":"not-an-email"}"#),
+  )
+  .await;
+  assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+  let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+  assert_eq!(body["name"][0]["code"], "length");
+  assert_eq!(body["email"][0]["code"], "email");
+}
+
+#[tokio::test]
+async fn test_unvalidated_json_passes_through() {
+  let (status, body) = send(
+    UserController::router(),
+    json_post("/users/unchecked", r#"{"name":"al","email":"x"}"#),
+  )
+  .await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "created:al");
+}
+
+#[tokio::test]
+async fn test_invalid_form() {
+  let request = Request::builder()
+    .method("POST")
+    .uri("/users/form")
+    .header("content-type", "application/x-www-form-urlencoded")
+    .body(Body::from("name=alice&email=nope"))
+    .unwrap();
+  let (status, body) = send(UserController::router(), request).await;
+  assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+  assert!(body.contains("email"));
+}
+
+#[tokio::test]
+async fn test_validate_attribute_on_query() {
+  let request = Request::builder()
+    .uri("/users/5/friends?limit=10")
+    .body(Body::empty())
+    .unwrap();
+  let (status, body) = send(UserController::router(), request).await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "id:5,limit:10");
+
+  let request = Request::builder()
+    .uri("/users/5/friends?limit=500")
+    .body(Body::empty())
+    .unwrap();
+  let (status, body) = send(UserController::router(), request).await;
+  assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+  assert!(body.contains("limit"));
+}
+
+#[tokio::test]
+async fn test_custom_validation_error() {
+  let (status, body) = send(
+    CustomErrorController::router(),
+    json_post("/custom", r#"{"name":"al","email":"bad"}"#),
+  )
+  .await;
+  assert_eq!(status, StatusCode::BAD_REQUEST);
+  assert_eq!(body, "invalid fields: email,name");
+}
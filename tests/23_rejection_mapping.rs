@@ -0,0 +1,258 @@
+//! Integration tests for custom rejection mapping
+//!
+//! Tests `rejection = ...` on controllers and routes, and `#[catch]` methods
+
+use axum::body::Body;
+use axum::extract::rejection::{FormRejection, JsonRejection, PathRejection, QueryRejection};
+use axum::http::{Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use route_controller::{catch, controller, get, post};
+use serde::Deserialize;
+use tower::ServiceExt;
+
+// JSON error envelope required by the API contract
+struct ApiError {
+  status: StatusCode,
+  kind: &'static str,
+}
+
+impl IntoResponse for ApiError {
+  fn into_response(self) -> Response {
+    (
+      self.status,
+      [("content-type", "application/json")],
+      format!(r#"{{"error":{{"kind":"{}"}}}}"#, self.kind),
+    )
+      .into_response()
+  }
+}
+
+impl From<JsonRejection> for ApiError {
+  fn from(rejection: JsonRejection) -> Self {
+    ApiError {
+      status: rejection.status(),
+      kind: "json",
+    }
+  }
+}
+
+impl From<QueryRejection> for ApiError {
+  fn from(rejection: QueryRejection) -> Self {
+    ApiError {
+      status: rejection.status(),
+      kind: "query",
+    }
+  }
+}
+
+impl From<PathRejection> for ApiError {
+  fn from(rejection: PathRejection) -> Self {
+    ApiError {
+      status: rejection.status(),
+      kind: "path",
+    }
+  }
+}
+
+impl From<FormRejection> for ApiError {
+  fn from(rejection: FormRejection) -> Self {
+    ApiError {
+      status: rejection.status(),
+      kind: "form",
+    }
+  }
+}
+
+// Alternative error type used as a per-route override
+struct TeapotError;
+
+impl IntoResponse for TeapotError {
+  fn into_response(self) -> Response {
+    (StatusCode::IM_A_TEAPOT, "teapot").into_response()
+  }
+}
+
+impl From<JsonRejection> for TeapotError {
+  fn from(_: JsonRejection) -> Self {
+    TeapotError
+  }
+}
+
+#[derive(Deserialize)]
+struct Item {
+  name: String,
+}
+
+#[derive(Deserialize)]
+struct Search {
+  page: u32,
+}
+
+struct ItemController;
+
+#[controller(path = "/items", rejection = ApiError)]
+impl ItemController {
+  #[post("/", extract(item = Json))]
+  async fn create(item: Item) -> String {
+    format!("created:{}", item.name)
+  }
+
+  #[post("/form", extract(item = Form))]
+  async fn create_form(item: Item) -> String {
+    format!("created:{}", item.name)
+  }
+
+  #[get("/{id}", extract(id = Path, search = Query))]
+  async fn show(id: u32, search: Search) -> String {
+    format!("id:{},page:{}", id, search.page)
+  }
+
+  #[get("/{a}/{b}", extract(a = Path, b = Path))]
+  async fn pair(a: u32, b: u32) -> String {
+    format!("sum:{}", a + b)
+  }
+
+  #[post("/teapot", extract(item = Json), rejection = TeapotError)]
+  async fn teapot(item: Item) -> String {
+    format!("created:{}", item.name)
+  }
+}
+
+struct CatchController;
+
+#[controller(path = "/catch")]
+impl CatchController {
+  #[post("/", extract(item = Json))]
+  async fn create(item: Item) -> String {
+    format!("created:{}", item.name)
+  }
+
+  #[catch]
+  fn on_rejection(status: StatusCode, message: String) -> impl IntoResponse {
+    (status, format!("caught:{}", message.is_empty()))
+  }
+}
+
+async fn send(app: axum::Router, request: Request<Body>) -> (StatusCode, String) {
+  let response = app.oneshot(request).await.unwrap();
+  let status = response.status();
+  let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+    .await
+    .unwrap();
+  (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+fn post_json(uri: &str, body: &str) -> Request<Body> {
+  Request::builder()
+    .method("POST")
+    .uri(uri)
+    .header("content-type", "application/json")
+    .body(Body::from(body.to_string()))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_success_is_unaffected() {
+  let (status, body) = send(
+    ItemController::router(),
+    post_json("/items", r#"{"name":"pen"}"#),
+  )
+  .await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "created:pen");
+}
+
+#[tokio::test]
+async fn test_json_rejection_is_mapped() {
+  let (status, body) = send(ItemController::router(), post_json("/items", "{not json")).await;
+  assert_eq!(status, StatusCode::BAD_REQUEST);
+  assert_eq!(body, r#"{"error":{"kind":"json"}}"#);
+
+  // Missing content type keeps the original status but uses the envelope
+  let request = Request::builder()
+    .method("POST")
+    .uri("/items")
+    .body(Body::from(r#"{"name":"pen"}"#))
+    .unwrap();
+  let (status, body) = send(ItemController::router(), request).await;
+  assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+  assert_eq!(body, r#"{"error":{"kind":"json"}}"#);
+}
+
+#[tokio::test]
+async fn test_form_rejection_is_mapped() {
+  let request = Request::builder()
+    .method("POST")
+    .uri("/items/form")
+    .header("content-type", "application/x-www-form-urlencoded")
+    .body(Body::from("other=1"))
+    .unwrap();
+  let (status, body) = send(ItemController::router(), request).await;
+  assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+  assert_eq!(body, r#"{"error":{"kind":"form"}}"#);
+}
+
+#[tokio::test]
+async fn test_path_and_query_rejections_are_mapped() {
+  let request = Request::builder()
+    .uri("/items/abc?page=1")
+    .body(Body::empty())
+    .unwrap();
+  let (_, body) = send(ItemController::router(), request).await;
+  assert_eq!(body, r#"{"error":{"kind":"path"}}"#);
+
+  let request = Request::builder()
+    .uri("/items/1?page=first")
+    .body(Body::empty())
+    .unwrap();
+  let (status, body) = send(ItemController::router(), request).await;
+  assert_eq!(status, StatusCode::BAD_REQUEST);
+  assert_eq!(body, r#"{"error":{"kind":"query"}}"#);
+
+  let request = Request::builder()
+    .uri("/items/1?page=2")
+    .body(Body::empty())
+    .unwrap();
+  let (status, body) = send(ItemController::router(), request).await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "id:1,page:2");
+}
+
+#[tokio::test]
+async fn test_multiple_path_params() {
+  let request = Request::builder()
+    .uri("/items/2/3")
+    .body(Body::empty())
+    .unwrap();
+  let (_, body) = send(ItemController::router(), request).await;
+  assert_eq!(body, "sum:5");
+
+  let request = Request::builder()
+    .uri("/items/2/x")
+    .body(Body::empty())
+    .unwrap();
+  let (_, body) = send(ItemController::router(), request).await;
+  assert_eq!(body, r#"{"error":{"kind":"path"}}"#);
+}
+
+#[tokio::test]
+async fn test_route_level_override() {
+  let (status, body) = send(ItemController::router(), post_json("/items/teapot", "nope")).await;
+  assert_eq!(status, StatusCode::IM_A_TEAPOT);
+  assert_eq!(body, "teapot");
+}
+
+#[tokio::test]
+async fn test_catch_method() {
+  let (status, body) = send(CatchController::router(), post_json("/catch", "nope")).await;
+  assert_eq!(status, StatusCode::BAD_REQUEST);
+  assert_eq!(body, "caught:false");
+
+  let (status, body) = send(
+    CatchController::router(),
+    post_json("/catch", r#"{"name":"a"}"#),
+  )
+  .await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "created:a");
+}
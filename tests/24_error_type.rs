@@ -0,0 +1,217 @@
+//! Integration tests for Result-returning handlers
+//!
+//! Tests the controller `error = ...` type, `error_header(...)` and `error_status`, and that
+//! route-level success headers are not attached to error responses
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use route_controller::{controller, get, post};
+use serde::Deserialize;
+use tower::ServiceExt;
+
+// Application-wide error returned to clients
+struct ApiError {
+  status: StatusCode,
+  message: String,
+}
+
+impl IntoResponse for ApiError {
+  fn into_response(self) -> Response {
+    (self.status, format!("error:{}", self.message)).into_response()
+  }
+}
+
+// Domain errors that convert into ApiError
+struct NotFound(u32);
+
+impl From<NotFound> for ApiError {
+  fn from(err: NotFound) -> Self {
+    ApiError {
+      status: StatusCode::NOT_FOUND,
+      message: format!("missing {}", err.0),
+    }
+  }
+}
+
+struct Conflict;
+
+impl From<Conflict> for ApiError {
+  fn from(_: Conflict) -> Self {
+    ApiError {
+      status: StatusCode::CONFLICT,
+      message: "conflict".to_string(),
+    }
+  }
+}
+
+// Per-route override of the controller error type
+struct PlainError(&'static str);
+
+impl From<NotFound> for PlainError {
+  fn from(_: NotFound) -> Self {
+    PlainError("plain")
+  }
+}
+
+impl IntoResponse for PlainError {
+  fn into_response(self) -> Response {
+    (StatusCode::BAD_REQUEST, self.0).into_response()
+  }
+}
+
+#[derive(Deserialize)]
+struct NewItem {
+  name: String,
+}
+
+struct ItemController;
+
+#[controller(
+  path = "/items",
+  error = ApiError,
+  header("x-api-version", "1"),
+  error_header("cache-control", "no-store")
+)]
+impl ItemController {
+  #[get("/{id}", extract(id = Path), header("x-item-cache", "hit"))]
+  async fn show(id: u32) -> Result<String, NotFound> {
+    if id == 1 {
+      Ok("item:1".to_string())
+    } else {
+      Err(NotFound(id))
+    }
+  }
+
+  #[post("/", extract(item = Json))]
+  async fn create(item: NewItem) -> Result<String, Conflict> {
+    if item.name == "taken" {
+      Err(Conflict)
+    } else {
+      Ok(format!("created:{}", item.name))
+    }
+  }
+
+  #[get("/health")]
+  async fn health() -> Result<&'static str, ApiError> {
+    Err(ApiError {
+      status: StatusCode::INTERNAL_SERVER_ERROR,
+      message: "down".to_string(),
+    })
+  }
+
+  #[get("/busy", error_status = 503, error_header("retry-after", "30"))]
+  async fn busy() -> Result<&'static str, Conflict> {
+    Err(Conflict)
+  }
+
+  #[get("/plain/{id}", extract(id = Path), error = PlainError)]
+  async fn plain(id: u32) -> Result<String, NotFound> {
+    Err(NotFound(id))
+  }
+}
+
+struct HeaderOnlyController;
+
+#[controller(path = "/plain")]
+impl HeaderOnlyController {
+  #[get("/{id}", extract(id = Path), header("x-api-version", "2"))]
+  async fn show(id: u32) -> Result<String, StatusCode> {
+    if id == 1 {
+      Ok("ok".to_string())
+    } else {
+      Err(StatusCode::NOT_FOUND)
+    }
+  }
+}
+
+async fn send(app: axum::Router, request: Request<Body>) -> (Response, String) {
+  let response = app.oneshot(request).await.unwrap();
+  let (parts, body) = response.into_parts();
+  let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+  (
+    Response::from_parts(parts, Body::empty()),
+    String::from_utf8(body.to_vec()).unwrap(),
+  )
+}
+
+fn get_request(uri: &str) -> Request<Body> {
+  Request::builder().uri(uri).body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn test_success_gets_success_headers_only() {
+  let (response, body) = send(ItemController::router(), get_request("/items/1")).await;
+  assert_eq!(response.status(), StatusCode::OK);
+  assert_eq!(body, "item:1");
+  assert_eq!(response.headers()["x-api-version"], "1");
+  assert_eq!(response.headers()["x-item-cache"], "hit");
+  assert!(response.headers().get("cache-control").is_none());
+}
+
+#[tokio::test]
+async fn test_error_is_converted_with_error_headers() {
+  let (response, body) = send(ItemController::router(), get_request("/items/7")).await;
+  assert_eq!(response.status(), StatusCode::NOT_FOUND);
+  assert_eq!(body, "error:missing 7");
+  assert_eq!(response.headers()["cache-control"], "no-store");
+  assert!(response.headers().get("x-item-cache").is_none());
+  // Controller-level headers are applied by a layer to every response
+  assert_eq!(response.headers()["x-api-version"], "1");
+}
+
+#[tokio::test]
+async fn test_json_handler_error() {
+  let request = Request::builder()
+    .method("POST")
+    .uri("/items")
+    .header("content-type", "application/json")
+    .body(Body::from(r#"{"name":"taken"}"#))
+    .unwrap();
+  let (response, body) = send(ItemController::router(), request).await;
+  assert_eq!(response.status(), StatusCode::CONFLICT);
+  assert_eq!(body, "error:conflict");
+
+  let request = Request::builder()
+    .method("POST")
+    .uri("/items")
+    .header("content-type", "application/json")
+    .body(Body::from(r#"{"name":"pen"}"#))
+    .unwrap();
+  let (response, body) = send(ItemController::router(), request).await;
+  assert_eq!(response.status(), StatusCode::OK);
+  assert_eq!(body, "created:pen");
+}
+
+#[tokio::test]
+async fn test_handler_returning_error_type_directly() {
+  let (response, body) = send(ItemController::router(), get_request("/items/health")).await;
+  assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+  assert_eq!(body, "error:down");
+}
+
+#[tokio::test]
+async fn test_route_error_status_and_headers() {
+  let (response, body) = send(ItemController::router(), get_request("/items/busy")).await;
+  assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+  assert_eq!(body, "error:conflict");
+  assert_eq!(response.headers()["retry-after"], "30");
+  assert_eq!(response.headers()["cache-control"], "no-store");
+}
+
+#[tokio::test]
+async fn test_route_error_type_override() {
+  let (response, body) = send(ItemController::router(), get_request("/items/plain/3")).await;
+  assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+  assert_eq!(body, "plain");
+}
+
+#[tokio::test]
+async fn test_static_headers_skip_errors_without_error_type() {
+  let (response, _) = send(HeaderOnlyController::router(), get_request("/plain/1")).await;
+  assert_eq!(response.headers()["x-api-version"], "2");
+
+  let (response, _) = send(HeaderOnlyController::router(), get_request("/plain/2")).await;
+  assert_eq!(response.status(), StatusCode::NOT_FOUND);
+  assert!(response.headers().get("x-api-version").is_none());
+}
@@ -0,0 +1,197 @@
+//! Integration tests for declarative success status codes and Location headers
+//!
+//! Tests `status = ...` and `location = "..."` on routes, including Result handlers
+
+use axum::Json;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use route_controller::{controller, delete, get, post, put};
+use serde::{Deserialize, Serialize};
+use tower::ServiceExt;
+
+#[derive(Deserialize)]
+struct NewUser {
+  name: String,
+}
+
+#[derive(Serialize)]
+struct User {
+  id: u32,
+  name: String,
+}
+
+#[derive(Serialize)]
+struct Membership {
+  team: Team,
+  user_id: u32,
+}
+
+#[derive(Serialize)]
+struct Team {
+  slug: String,
+}
+
+struct UserController;
+
+#[controller(path = "/users", header("x-api-version", "1"))]
+impl UserController {
+  #[post("/", extract(user = Json), location = "/users/{id}")]
+  async fn create(user: NewUser) -> Json<User> {
+    Json(User {
+      id: 42,
+      name: user.name,
+    })
+  }
+
+  #[post("/ids", location = "/users/{id}")]
+  async fn create_id() -> u64 {
+    7
+  }
+
+  #[post(
+    "/memberships",
+    status = 200,
+    location = "/teams/{team.slug}/members/{user_id}"
+  )]
+  async fn join() -> Json<Membership> {
+    Json(Membership {
+      team: Team {
+        slug: "core".to_string(),
+      },
+      user_id: 3,
+    })
+  }
+
+  #[post("/teams", extract(user = Json), location = "/teams/{slug}")]
+  async fn create_team(user: NewUser) -> Json<Team> {
+    Json(Team { slug: user.name })
+  }
+
+  #[put("/{id}", extract(id = Path), status = 202)]
+  async fn update(id: u32) -> String {
+    format!("queued:{}", id)
+  }
+
+  #[delete("/{id}", extract(id = Path), status = 204)]
+  async fn remove(id: u32) {
+    let _ = id;
+  }
+
+  #[post("/checked", extract(user = Json), status = 201, location = "/users/{id}")]
+  async fn create_checked(user: NewUser) -> Result<Json<User>, StatusCode> {
+    if user.name.is_empty() {
+      return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(Json(User {
+      id: 9,
+      name: user.name,
+    }))
+  }
+
+  #[get("/ping", status = 200)]
+  async fn ping() -> &'static str {
+    "pong"
+  }
+}
+
+async fn send(request: Request<Body>) -> (StatusCode, axum::http::HeaderMap, String) {
+  let response = UserController::router().oneshot(request).await.unwrap();
+  let status = response.status();
+  let headers = response.headers().clone();
+  let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+    .await
+    .unwrap();
+  (status, headers, String::from_utf8(body.to_vec()).unwrap())
+}
+
+fn post_json(uri: &str, body: &str) -> Request<Body> {
+  Request::builder()
+    .method("POST")
+    .uri(uri)
+    .header("content-type", "application/json")
+    .body(Body::from(body.to_string()))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_location_from_field_defaults_to_created() {
+  let (status, headers, body) = send(post_json("/users", r#"{"name":"ada"}"#)).await;
+  assert_eq!(status, StatusCode::CREATED);
+  assert_eq!(headers["location"], "/users/42");
+  assert_eq!(headers["x-api-version"], "1");
+  assert_eq!(body, r#"{"id":42,"name":"ada"}"#);
+}
+
+#[tokio::test]
+async fn test_location_from_returned_id() {
+  let request = Request::builder()
+    .method("POST")
+    .uri("/users/ids")
+    .body(Body::empty())
+    .unwrap();
+  let (status, headers, body) = send(request).await;
+  assert_eq!(status, StatusCode::CREATED);
+  assert_eq!(headers["location"], "/users/7");
+  assert_eq!(body, "7");
+}
+
+#[tokio::test]
+async fn test_location_values_are_percent_encoded() {
+  let (status, headers, _) = send(post_json("/users/teams", r#"{"name":"a/b?c#d é"}"#)).await;
+  assert_eq!(status, StatusCode::CREATED);
+  assert_eq!(headers["location"], "/teams/a%2Fb%3Fc%23d%20%C3%A9");
+}
+
+#[tokio::test]
+async fn test_location_with_nested_fields_and_explicit_status() {
+  let request = Request::builder()
+    .method("POST")
+    .uri("/users/memberships")
+    .body(Body::empty())
+    .unwrap();
+  let (status, headers, _) = send(request).await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(headers["location"], "/teams/core/members/3");
+}
+
+#[tokio::test]
+async fn test_status_only() {
+  let request = Request::builder()
+    .method("PUT")
+    .uri("/users/5")
+    .body(Body::empty())
+    .unwrap();
+  let (status, headers, body) = send(request).await;
+  assert_eq!(status, StatusCode::ACCEPTED);
+  assert_eq!(body, "queued:5");
+  assert!(headers.get("location").is_none());
+
+  let request = Request::builder()
+    .method("DELETE")
+    .uri("/users/5")
+    .body(Body::empty())
+    .unwrap();
+  let (status, _, body) = send(request).await;
+  assert_eq!(status, StatusCode::NO_CONTENT);
+  assert!(body.is_empty());
+
+  let request = Request::builder()
+    .uri("/users/ping")
+    .body(Body::empty())
+    .unwrap();
+  let (status, _, body) = send(request).await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "pong");
+}
+
+#[tokio::test]
+async fn test_result_handler_applies_status_to_success_only() {
+  let (status, headers, _) = send(post_json("/users/checked", r#"{"name":"bo"}"#)).await;
+  assert_eq!(status, StatusCode::CREATED);
+  assert_eq!(headers["location"], "/users/9");
+
+  let (status, headers, _) = send(post_json("/users/checked", r#"{"name":""}"#)).await;
+  assert_eq!(status, StatusCode::BAD_REQUEST);
+  assert!(headers.get("location").is_none());
+  assert_eq!(headers["x-api-version"], "1");
+}
@@ -0,0 +1,69 @@
+//! Integration tests for compile-time header and content type validation
+//!
+//! Tests that mixed-case header names are lowercased and that escaped values and media type
+//! parameters survive parsing. Invalid names, values and media types are compile errors
+//! (see 16_error_validation.rs)
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use route_controller::{controller, get};
+use tower::ServiceExt;
+
+struct HeaderController;
+
+#[controller(path = "/headers", header("X-Service-Name", "catalog"))]
+impl HeaderController {
+  #[get(
+    "/mixed",
+    header("X-Api-Version", "1"),
+    header("Cache-Control", "max-age=60")
+  )]
+  async fn mixed() -> &'static str {
+    "mixed"
+  }
+
+  #[get(
+    "/quoted",
+    header("etag", "\"v1\""),
+    content_type("text/plain; charset=\"utf-8\"")
+  )]
+  async fn quoted() -> &'static str {
+    "quoted"
+  }
+
+  #[get("/override", header("x-service-name", "search"))]
+  async fn overridden() -> &'static str {
+    "override"
+  }
+}
+
+async fn get_headers(uri: &str) -> axum::http::HeaderMap {
+  let response = HeaderController::router()
+    .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+    .await
+    .unwrap();
+  assert_eq!(response.status(), StatusCode::OK);
+  response.headers().clone()
+}
+
+#[tokio::test]
+async fn test_mixed_case_names_are_lowercased() {
+  let headers = get_headers("/headers/mixed").await;
+  assert_eq!(headers["x-api-version"], "1");
+  assert_eq!(headers["cache-control"], "max-age=60");
+  assert_eq!(headers["x-service-name"], "catalog");
+}
+
+#[tokio::test]
+async fn test_escaped_quotes_are_kept() {
+  let headers = get_headers("/headers/quoted").await;
+  assert_eq!(headers["etag"], "\"v1\"");
+  assert_eq!(headers["content-type"], "text/plain; charset=\"utf-8\"");
+}
+
+#[tokio::test]
+async fn test_lowercased_controller_header_can_be_overridden() {
+  let headers = get_headers("/headers/override").await;
+  assert_eq!(headers["x-service-name"], "search");
+  assert_eq!(headers.get_all("x-service-name").iter().count(), 1);
+}
@@ -0,0 +1,221 @@
+//! Integration tests for dynamic response header values
+//!
+//! Tests `header(...)` values given as constants, expressions and `with = fn` functions over
+//! the request parts and router state
+
+use axum::body::Body;
+use axum::http::request::Parts;
+use axum::http::{HeaderValue, Request, StatusCode};
+use route_controller::{controller, get};
+use tower::ServiceExt;
+
+const API_VERSION: &str = "2024-06";
+
+#[derive(Clone)]
+struct AppState {
+  region: &'static str,
+}
+
+// Echoes the caller's request id, or a fixed fallback
+fn request_id(parts: &Parts, _state: &AppState) -> HeaderValue {
+  parts
+    .headers
+    .get("x-request-id")
+    .cloned()
+    .unwrap_or(HeaderValue::from_static("generated"))
+}
+
+fn served_by(_parts: &Parts, state: &AppState) -> String {
+  format!("{}-1", state.region)
+}
+
+fn broken(_parts: &Parts, _state: &AppState) -> String {
+  "bad\nvalue".to_string()
+}
+
+struct InfoController;
+
+#[controller(
+  path = "/info",
+  header("x-api-version", API_VERSION),
+  header("x-request-id", "unset")
+)]
+impl InfoController {
+  #[get(
+    "/",
+    header("x-crate-version", env!("CARGO_PKG_VERSION")),
+    header("x-request-id", with = request_id)
+  )]
+  async fn index() -> &'static str {
+    "info"
+  }
+
+  #[get("/region", extract(state = State), header("x-served-by", with = served_by))]
+  async fn region(state: AppState) -> String {
+    state.region.to_string()
+  }
+
+  #[get("/fixed", header("x-request-id", "fixed"))]
+  async fn fixed() -> &'static str {
+    "fixed"
+  }
+
+  #[get("/broken", header("x-broken", with = broken))]
+  async fn broken() -> &'static str {
+    "unreachable"
+  }
+
+  #[get(
+    "/fail",
+    error_header("x-error-version", API_VERSION),
+    error_header("x-error-request-id", with = request_id)
+  )]
+  async fn fail() -> Result<&'static str, StatusCode> {
+    Err(StatusCode::CONFLICT)
+  }
+}
+
+// Functions over a stateless router take `&()`
+fn trace_id(parts: &Parts, _state: &()) -> String {
+  format!("trace:{}", parts.method)
+}
+
+struct StatelessController;
+
+#[controller(path = "/plain")]
+impl StatelessController {
+  #[get("/", header("x-trace-id", with = trace_id))]
+  async fn index() -> &'static str {
+    "plain"
+  }
+}
+
+async fn send(app: axum::Router, request: Request<Body>) -> axum::response::Response {
+  app.oneshot(request).await.unwrap()
+}
+
+fn app() -> axum::Router {
+  InfoController::router().with_state(AppState { region: "eu" })
+}
+
+#[tokio::test]
+async fn test_constant_and_env_values() {
+  let response = send(
+    app(),
+    Request::builder().uri("/info").body(Body::empty()).unwrap(),
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::OK);
+  assert_eq!(response.headers()["x-api-version"], API_VERSION);
+  assert_eq!(
+    response.headers()["x-crate-version"],
+    env!("CARGO_PKG_VERSION")
+  );
+}
+
+#[tokio::test]
+async fn test_with_function_reads_request_parts() {
+  let response = send(
+    app(),
+    Request::builder()
+      .uri("/info")
+      .header("x-request-id", "abc-123")
+      .body(Body::empty())
+      .unwrap(),
+  )
+  .await;
+  assert_eq!(response.headers()["x-request-id"], "abc-123");
+
+  let response = send(
+    app(),
+    Request::builder().uri("/info").body(Body::empty()).unwrap(),
+  )
+  .await;
+  assert_eq!(response.headers()["x-request-id"], "generated");
+}
+
+#[tokio::test]
+async fn test_with_function_reads_state() {
+  let response = send(
+    app(),
+    Request::builder()
+      .uri("/info/region")
+      .body(Body::empty())
+      .unwrap(),
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::OK);
+  assert_eq!(response.headers()["x-served-by"], "eu-1");
+}
+
+#[tokio::test]
+async fn test_route_value_overrides_controller_value() {
+  let response = send(
+    app(),
+    Request::builder()
+      .uri("/info/fixed")
+      .header("x-request-id", "abc-123")
+      .body(Body::empty())
+      .unwrap(),
+  )
+  .await;
+  assert_eq!(response.headers()["x-request-id"], "fixed");
+  assert_eq!(response.headers().get_all("x-request-id").iter().count(), 1);
+
+  let response = send(
+    app(),
+    Request::builder()
+      .uri("/info")
+      .header("x-request-id", "abc-123")
+      .body(Body::empty())
+      .unwrap(),
+  )
+  .await;
+  assert_eq!(response.headers()["x-request-id"], "abc-123");
+  assert_eq!(response.headers().get_all("x-request-id").iter().count(), 1);
+}
+
+#[tokio::test]
+async fn test_invalid_computed_value_is_server_error() {
+  let response = send(
+    app(),
+    Request::builder()
+      .uri("/info/broken")
+      .body(Body::empty())
+      .unwrap(),
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test]
+async fn test_dynamic_error_headers() {
+  let response = send(
+    app(),
+    Request::builder()
+      .uri("/info/fail")
+      .header("x-request-id", "r-9")
+      .body(Body::empty())
+      .unwrap(),
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::CONFLICT);
+  assert_eq!(response.headers()["x-error-version"], API_VERSION);
+  assert_eq!(response.headers()["x-error-request-id"], "r-9");
+  // Layered controller headers reach errors
+  assert_eq!(response.headers()["x-api-version"], API_VERSION);
+  assert_eq!(response.headers()["x-request-id"], "unset");
+}
+
+#[tokio::test]
+async fn test_stateless_controller_function() {
+  let response = send(
+    StatelessController::router(),
+    Request::builder()
+      .uri("/plain")
+      .body(Body::empty())
+      .unwrap(),
+  )
+  .await;
+  assert_eq!(response.headers()["x-trace-id"], "trace:GET");
+}
@@ -0,0 +1,204 @@
+//! Integration tests for controller-level headers applied as a router layer
+//!
+//! Tests that controller headers and content types reach rejections, 404/405 responses and
+//! middleware short-circuits, and the `override`/`if_absent` header policies
+
+use axum::body::Body;
+use axum::extract::Request as AxumRequest;
+use axum::http::{HeaderMap, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use route_controller::{controller, get, post};
+use serde::Deserialize;
+use tower::ServiceExt;
+
+#[derive(Deserialize)]
+struct Item {
+  name: String,
+}
+
+async fn require_key(request: AxumRequest, next: Next) -> Response {
+  if request.headers().contains_key("x-key") {
+    next.run(request).await
+  } else {
+    StatusCode::UNAUTHORIZED.into_response()
+  }
+}
+
+struct LayeredController;
+
+#[controller(
+  path = "/layered",
+  middleware = require_key,
+  header("x-service", "catalog"),
+  header("cache-control", "no-cache", override),
+  header("x-frame-options", "DENY", if_absent)
+)]
+impl LayeredController {
+  #[get("/")]
+  async fn index() -> &'static str {
+    "index"
+  }
+
+  #[post("/items", extract(item = Json))]
+  async fn create(item: Item) -> String {
+    item.name
+  }
+
+  // The handler's own values: kept for if_absent headers, replaced for override headers
+  #[get("/own")]
+  async fn own() -> impl IntoResponse {
+    (
+      [
+        ("x-service", "handler"),
+        ("cache-control", "max-age=60"),
+        ("x-frame-options", "SAMEORIGIN"),
+      ],
+      "own",
+    )
+  }
+
+  #[get("/route", header("x-service", "route"))]
+  async fn route_header() -> &'static str {
+    "route"
+  }
+}
+
+struct JsonController;
+
+#[controller(path = "/json", middleware = require_key, content_type("application/json"))]
+impl JsonController {
+  #[get("/")]
+  async fn index() -> String {
+    r#"{"ok":true}"#.to_string()
+  }
+
+  #[get("/text", content_type("text/plain"))]
+  async fn text() -> String {
+    "plain".to_string()
+  }
+}
+
+struct SoftJsonController;
+
+#[controller(path = "/soft", content_type("application/json", if_absent))]
+impl SoftJsonController {
+  #[get("/")]
+  async fn index() -> String {
+    "text".to_string()
+  }
+
+  #[get("/bytes")]
+  async fn bytes() -> Response {
+    Response::new(Body::from("{}"))
+  }
+}
+
+async fn send(app: axum::Router, method: &str, uri: &str, body: &str) -> (StatusCode, HeaderMap) {
+  let request = Request::builder()
+    .method(method)
+    .uri(uri)
+    .header("x-key", "1")
+    .header("content-type", "application/json")
+    .body(Body::from(body.to_string()))
+    .unwrap();
+  let response = app.oneshot(request).await.unwrap();
+  (response.status(), response.headers().clone())
+}
+
+#[tokio::test]
+async fn test_headers_on_success() {
+  let (status, headers) = send(LayeredController::router(), "GET", "/layered", "").await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(headers["x-service"], "catalog");
+  assert_eq!(headers["cache-control"], "no-cache");
+  assert_eq!(headers["x-frame-options"], "DENY");
+}
+
+#[tokio::test]
+async fn test_headers_on_rejection() {
+  let (status, headers) = send(
+    LayeredController::router(),
+    "POST",
+    "/layered/items",
+    "not json",
+  )
+  .await;
+  assert_eq!(status, StatusCode::BAD_REQUEST);
+  assert_eq!(headers["x-service"], "catalog");
+}
+
+#[tokio::test]
+async fn test_headers_on_method_not_allowed_and_not_found() {
+  let (status, headers) = send(LayeredController::router(), "DELETE", "/layered", "").await;
+  assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+  assert_eq!(headers["x-service"], "catalog");
+
+  let (status, headers) = send(LayeredController::router(), "GET", "/layered/missing", "").await;
+  assert_eq!(status, StatusCode::NOT_FOUND);
+  assert_eq!(headers["x-service"], "catalog");
+}
+
+#[tokio::test]
+async fn test_headers_on_middleware_short_circuit() {
+  let request = Request::builder()
+    .uri("/layered")
+    .body(Body::empty())
+    .unwrap();
+  let response = LayeredController::router().oneshot(request).await.unwrap();
+  assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+  assert_eq!(response.headers()["x-service"], "catalog");
+}
+
+#[tokio::test]
+async fn test_header_policies() {
+  let (_, headers) = send(LayeredController::router(), "GET", "/layered/own", "").await;
+  assert_eq!(headers["x-service"], "handler");
+  assert_eq!(headers["cache-control"], "no-cache");
+  assert_eq!(headers["x-frame-options"], "SAMEORIGIN");
+  assert_eq!(headers.get_all("cache-control").iter().count(), 1);
+}
+
+#[tokio::test]
+async fn test_route_header_wins_over_if_absent_controller_header() {
+  let (_, headers) = send(LayeredController::router(), "GET", "/layered/route", "").await;
+  assert_eq!(headers["x-service"], "route");
+  assert_eq!(headers.get_all("x-service").iter().count(), 1);
+}
+
+#[tokio::test]
+async fn test_controller_content_type() {
+  let (_, headers) = send(JsonController::router(), "GET", "/json", "").await;
+  assert_eq!(headers["content-type"], "application/json");
+
+  let (_, headers) = send(JsonController::router(), "GET", "/json/text", "").await;
+  assert_eq!(headers["content-type"], "text/plain");
+}
+
+#[tokio::test]
+async fn test_controller_content_type_outside_routes() {
+  // 405s of routes with their own content type get the controller's
+  for (method, uri) in [
+    ("DELETE", "/json"),
+    ("DELETE", "/json/text"),
+    ("GET", "/json/missing"),
+  ] {
+    let (status, headers) = send(JsonController::router(), method, uri, "").await;
+    assert!(status.is_client_error());
+    assert_eq!(headers["content-type"], "application/json");
+  }
+
+  let request = Request::builder().uri("/json").body(Body::empty()).unwrap();
+  let response = JsonController::router().oneshot(request).await.unwrap();
+  assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+  assert_eq!(response.headers()["content-type"], "application/json");
+}
+
+#[tokio::test]
+async fn test_if_absent_content_type() {
+  let (_, headers) = send(SoftJsonController::router(), "GET", "/soft", "").await;
+  assert_eq!(headers["content-type"], "text/plain; charset=utf-8");
+
+  let (_, headers) = send(SoftJsonController::router(), "GET", "/soft/bytes", "").await;
+  assert_eq!(headers["content-type"], "application/json");
+}
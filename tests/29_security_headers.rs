@@ -0,0 +1,118 @@
+//! Integration tests for `security_headers` presets
+//!
+//! Tests the `strict` and `basic` presets, controller-level customization, and routes that
+//! replace or disable single entries
+
+use axum::body::Body;
+use axum::http::{HeaderMap, Request, StatusCode};
+use route_controller::{controller, get};
+use tower::ServiceExt;
+
+struct StrictController;
+
+#[controller(path = "/strict", security_headers = strict)]
+impl StrictController {
+  #[get("/")]
+  async fn index() -> &'static str {
+    "strict"
+  }
+
+  // Relaxed for pages embedded by partners
+  #[get(
+    "/embed",
+    security_headers(csp = "frame-ancestors https://partner.example", frame_options = false)
+  )]
+  async fn embed() -> &'static str {
+    "embed"
+  }
+
+  #[get("/bare", security_headers = none)]
+  async fn bare() -> &'static str {
+    "bare"
+  }
+
+  #[get("/{id}", extract(id = Path))]
+  async fn show(id: u32) -> String {
+    id.to_string()
+  }
+}
+
+struct CustomController;
+
+#[controller(
+  path = "/custom",
+  security_headers(basic, hsts = false, referrer_policy = "same-origin"),
+  header("x-frame-options", "DENY")
+)]
+impl CustomController {
+  #[get("/")]
+  async fn index() -> &'static str {
+    "custom"
+  }
+}
+
+async fn fetch(app: axum::Router, uri: &str) -> (StatusCode, HeaderMap) {
+  let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+  let response = app.oneshot(request).await.unwrap();
+  (response.status(), response.headers().clone())
+}
+
+#[tokio::test]
+async fn test_strict_preset() {
+  let (status, headers) = fetch(StrictController::router(), "/strict").await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(
+    headers["strict-transport-security"],
+    "max-age=63072000; includeSubDomains"
+  );
+  assert_eq!(headers["x-content-type-options"], "nosniff");
+  assert_eq!(headers["x-frame-options"], "DENY");
+  assert_eq!(headers["referrer-policy"], "no-referrer");
+  assert!(
+    headers["content-security-policy"]
+      .to_str()
+      .unwrap()
+      .starts_with("default-src 'none'")
+  );
+}
+
+#[tokio::test]
+async fn test_route_replaces_and_disables_entries() {
+  let (status, headers) = fetch(StrictController::router(), "/strict/embed").await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(
+    headers["content-security-policy"],
+    "frame-ancestors https://partner.example"
+  );
+  assert!(headers.get("x-frame-options").is_none());
+  assert_eq!(headers["x-content-type-options"], "nosniff");
+}
+
+#[tokio::test]
+async fn test_route_disables_all_entries() {
+  let (_, headers) = fetch(StrictController::router(), "/strict/bare").await;
+  assert!(headers.get("strict-transport-security").is_none());
+  assert!(headers.get("content-security-policy").is_none());
+}
+
+#[tokio::test]
+async fn test_preset_applies_to_rejections_and_not_found() {
+  let (status, headers) = fetch(StrictController::router(), "/strict/abc").await;
+  assert_eq!(status, StatusCode::BAD_REQUEST);
+  assert_eq!(headers["x-frame-options"], "DENY");
+
+  let (status, headers) = fetch(StrictController::router(), "/strict/a/b").await;
+  assert_eq!(status, StatusCode::NOT_FOUND);
+  assert_eq!(headers["x-content-type-options"], "nosniff");
+}
+
+#[tokio::test]
+async fn test_customized_preset() {
+  let (_, headers) = fetch(CustomController::router(), "/custom").await;
+  assert!(headers.get("strict-transport-security").is_none());
+  assert_eq!(headers["referrer-policy"], "same-origin");
+  assert_eq!(headers["content-security-policy"], "frame-ancestors 'self'");
+  // Explicit header(...) calls replace preset entries
+  assert_eq!(headers["x-frame-options"], "DENY");
+  assert_eq!(headers.get_all("x-frame-options").iter().count(), 1);
+}
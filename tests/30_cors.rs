@@ -0,0 +1,215 @@
+//! Integration tests for controller-level CORS
+//!
+//! Tests preflight handling ahead of controller middleware, `methods = auto`, and the CORS
+//! headers added to actual cross-origin responses
+
+use axum::body::Body;
+use axum::extract::Request as AxumRequest;
+use axum::http::{Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use route_controller::{controller, delete, get, post};
+use tower::ServiceExt;
+
+async fn require_auth(request: AxumRequest, next: Next) -> Response {
+  if request.headers().contains_key("authorization") {
+    next.run(request).await
+  } else {
+    StatusCode::UNAUTHORIZED.into_response()
+  }
+}
+
+struct NotesController;
+
+#[controller(
+  path = "/notes",
+  middleware = require_auth,
+  cors(
+    origins = ["https://app.example", "https://admin.example"],
+    methods = auto,
+    headers = ["Content-Type", "authorization"],
+    expose = ["x-request-id"],
+    credentials,
+    max_age = 600
+  )
+)]
+impl NotesController {
+  #[get("/")]
+  async fn list() -> &'static str {
+    "notes"
+  }
+
+  #[post("/")]
+  async fn create() -> &'static str {
+    "created"
+  }
+
+  #[delete("/{id}", extract(id = Path))]
+  async fn remove(id: u32) -> String {
+    id.to_string()
+  }
+}
+
+struct PublicController;
+
+#[controller(path = "/public", cors(origins = any, methods = ["GET"], headers = any))]
+impl PublicController {
+  #[get("/")]
+  async fn index() -> &'static str {
+    "public"
+  }
+}
+
+async fn send(app: axum::Router, request: Request<Body>) -> Response {
+  app.oneshot(request).await.unwrap()
+}
+
+fn preflight(uri: &str, origin: &str) -> Request<Body> {
+  Request::builder()
+    .method("OPTIONS")
+    .uri(uri)
+    .header("origin", origin)
+    .header("access-control-request-method", "POST")
+    .header("access-control-request-headers", "content-type, x-custom")
+    .body(Body::empty())
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_preflight_answered_before_middleware() {
+  let response = send(
+    NotesController::router(),
+    preflight("/notes", "https://app.example"),
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::NO_CONTENT);
+  let headers = response.headers();
+  assert_eq!(
+    headers["access-control-allow-origin"],
+    "https://app.example"
+  );
+  assert_eq!(headers["access-control-allow-methods"], "GET, POST, DELETE");
+  assert_eq!(
+    headers["access-control-allow-headers"],
+    "content-type, authorization"
+  );
+  assert_eq!(headers["access-control-allow-credentials"], "true");
+  assert_eq!(headers["access-control-max-age"], "600");
+  assert_eq!(headers["vary"], "origin");
+}
+
+#[tokio::test]
+async fn test_preflight_on_parameterized_route() {
+  let response = send(
+    NotesController::router(),
+    preflight("/notes/7", "https://admin.example"),
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::NO_CONTENT);
+  assert_eq!(
+    response.headers()["access-control-allow-origin"],
+    "https://admin.example"
+  );
+}
+
+#[tokio::test]
+async fn test_preflight_from_unknown_origin() {
+  let response = send(
+    NotesController::router(),
+    preflight("/notes", "https://evil.example"),
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::FORBIDDEN);
+  assert!(
+    response
+      .headers()
+      .get("access-control-allow-origin")
+      .is_none()
+  );
+}
+
+#[tokio::test]
+async fn test_actual_request_headers() {
+  let request = Request::builder()
+    .uri("/notes")
+    .header("origin", "https://app.example")
+    .header("authorization", "Bearer token")
+    .body(Body::empty())
+    .unwrap();
+  let response = send(NotesController::router(), request).await;
+  assert_eq!(response.status(), StatusCode::OK);
+  let headers = response.headers();
+  assert_eq!(
+    headers["access-control-allow-origin"],
+    "https://app.example"
+  );
+  assert_eq!(headers["access-control-allow-credentials"], "true");
+  assert_eq!(headers["access-control-expose-headers"], "x-request-id");
+  assert!(headers.get("access-control-allow-methods").is_none());
+
+  // Middleware rejections still carry CORS headers, so browsers can read them
+  let request = Request::builder()
+    .uri("/notes")
+    .header("origin", "https://app.example")
+    .body(Body::empty())
+    .unwrap();
+  let response = send(NotesController::router(), request).await;
+  assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+  assert_eq!(
+    response.headers()["access-control-allow-origin"],
+    "https://app.example"
+  );
+}
+
+#[tokio::test]
+async fn test_actual_request_from_unknown_origin() {
+  let request = Request::builder()
+    .uri("/notes")
+    .header("origin", "https://evil.example")
+    .header("authorization", "Bearer token")
+    .body(Body::empty())
+    .unwrap();
+  let response = send(NotesController::router(), request).await;
+  assert_eq!(response.status(), StatusCode::OK);
+  assert!(
+    response
+      .headers()
+      .get("access-control-allow-origin")
+      .is_none()
+  );
+  assert_eq!(response.headers()["vary"], "origin");
+}
+
+#[tokio::test]
+async fn test_any_origin_mirrors_requested_headers() {
+  let response = send(
+    PublicController::router(),
+    preflight("/public", "https://anywhere.example"),
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::NO_CONTENT);
+  let headers = response.headers();
+  assert_eq!(headers["access-control-allow-origin"], "*");
+  assert_eq!(headers["access-control-allow-methods"], "GET");
+  assert_eq!(
+    headers["access-control-allow-headers"],
+    "content-type, x-custom"
+  );
+  assert!(headers.get("vary").is_none());
+}
+
+#[tokio::test]
+async fn test_same_origin_request_untouched() {
+  let request = Request::builder()
+    .uri("/public")
+    .body(Body::empty())
+    .unwrap();
+  let response = send(PublicController::router(), request).await;
+  assert_eq!(response.status(), StatusCode::OK);
+  assert!(
+    response
+      .headers()
+      .get("access-control-allow-origin")
+      .is_none()
+  );
+}
@@ -0,0 +1,107 @@
+//! Integration tests for request body size limits
+//!
+//! Tests route-level `body_limit`, the controller-level default and axum's own default
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use route_controller::{controller, post};
+use serde::Deserialize;
+use tower::ServiceExt;
+
+#[derive(Deserialize)]
+struct Note {
+  text: String,
+}
+
+struct UploadController;
+
+#[controller(path = "/files", body_limit = "64B")]
+impl UploadController {
+  #[post("/upload", extract(data = Bytes), body_limit = "1KB")]
+  async fn upload(data: Vec<u8>) -> String {
+    data.len().to_string()
+  }
+
+  #[post("/tiny", extract(data = Bytes), body_limit = 16)]
+  async fn tiny(data: Vec<u8>) -> String {
+    data.len().to_string()
+  }
+
+  #[post("/notes", extract(note = Json))]
+  async fn note(note: Note) -> String {
+    note.text
+  }
+}
+
+struct DefaultController;
+
+#[controller(path = "/default")]
+impl DefaultController {
+  #[post("/", extract(data = Bytes))]
+  async fn upload(data: Vec<u8>) -> String {
+    data.len().to_string()
+  }
+}
+
+async fn post_body(app: axum::Router, uri: &str, body: String) -> StatusCode {
+  let request = Request::builder()
+    .method("POST")
+    .uri(uri)
+    .header("content-type", "application/json")
+    .body(Body::from(body))
+    .unwrap();
+  app.oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn test_route_limit_raises_controller_default() {
+  let status = post_body(
+    UploadController::router(),
+    "/files/upload",
+    "x".repeat(1024),
+  )
+  .await;
+  assert_eq!(status, StatusCode::OK);
+
+  let status = post_body(
+    UploadController::router(),
+    "/files/upload",
+    "x".repeat(1025),
+  )
+  .await;
+  assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_route_limit_in_bytes() {
+  let status = post_body(UploadController::router(), "/files/tiny", "x".repeat(16)).await;
+  assert_eq!(status, StatusCode::OK);
+
+  let status = post_body(UploadController::router(), "/files/tiny", "x".repeat(17)).await;
+  assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_controller_default_limit() {
+  let small = r#"{"text":"hello"}"#.to_string();
+  let status = post_body(UploadController::router(), "/files/notes", small).await;
+  assert_eq!(status, StatusCode::OK);
+
+  let large = format!(r#"{{"text":"{}"}}"#, "x".repeat(64));
+  let status = post_body(UploadController::router(), "/files/notes", large).await;
+  assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_axum_default_without_limits() {
+  let status = post_body(DefaultController::router(), "/default", "x".repeat(4096)).await;
+  assert_eq!(status, StatusCode::OK);
+
+  let status = post_body(
+    DefaultController::router(),
+    "/default",
+    "x".repeat(2 * 1024 * 1024 + 1),
+  )
+  .await;
+  assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+}
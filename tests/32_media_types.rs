@@ -0,0 +1,260 @@
+//! Integration tests for `consumes`/`produces` media type constraints
+//!
+//! Tests 415 answers for unsupported request media types, 406 answers for unsatisfiable
+//! `Accept` headers, and the media types implied by the text extractors
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use route_controller::{controller, get, post};
+use serde::{Deserialize, Serialize};
+use tower::ServiceExt;
+
+#[derive(Deserialize, Serialize)]
+struct Report {
+  title: String,
+}
+
+struct ReportController;
+
+#[controller(
+  path = "/reports",
+  consumes = ["application/json"],
+  produces = ["application/json"]
+)]
+impl ReportController {
+  #[get("/")]
+  async fn list() -> &'static str {
+    "[]"
+  }
+
+  #[post("/", extract(report = Json))]
+  async fn create(report: Report) -> axum::Json<Report> {
+    axum::Json(report)
+  }
+
+  #[post(
+    "/import",
+    extract(data = Bytes),
+    consumes = ["application/xml", "text/*"],
+    produces = ["application/json", "text/csv"]
+  )]
+  async fn import(data: Vec<u8>) -> String {
+    data.len().to_string()
+  }
+
+  #[post(
+    "/notes",
+    extract(data = Bytes),
+    consumes = ["text/plain; charset=utf-8"],
+    produces = ["text/plain; charset=utf-8"]
+  )]
+  async fn notes(data: Vec<u8>) -> String {
+    data.len().to_string()
+  }
+}
+
+struct TextController;
+
+#[controller(path = "/text")]
+impl TextController {
+  #[post("/plain", extract(content = Text))]
+  async fn plain(content: String) -> String {
+    content
+  }
+
+  #[post("/xml", extract(xml = Xml))]
+  async fn xml(xml: String) -> String {
+    xml
+  }
+
+  #[post("/raw", extract(content = Text(any_content_type)))]
+  async fn raw(content: String) -> String {
+    content
+  }
+}
+
+async fn send(
+  app: axum::Router,
+  method: &str,
+  uri: &str,
+  content_type: Option<&str>,
+  accept: Option<&str>,
+  body: &str,
+) -> StatusCode {
+  let mut builder = Request::builder().method(method).uri(uri);
+  if let Some(content_type) = content_type {
+    builder = builder.header("content-type", content_type);
+  }
+  if let Some(accept) = accept {
+    builder = builder.header("accept", accept);
+  }
+  app
+    .oneshot(builder.body(Body::from(body.to_string())).unwrap())
+    .await
+    .unwrap()
+    .status()
+}
+
+const REPORT: &str = r#"{"title":"q3"}"#;
+
+#[tokio::test]
+async fn test_controller_consumes() {
+  let app = ReportController::router();
+  let json = Some("application/json; charset=utf-8");
+  let status = send(app.clone(), "POST", "/reports", json, None, REPORT).await;
+  assert_eq!(status, StatusCode::OK);
+
+  let status = send(
+    app.clone(),
+    "POST",
+    "/reports",
+    Some("text/plain"),
+    None,
+    REPORT,
+  )
+  .await;
+  assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+  // Routes without a body extractor are not checked
+  let status = send(app, "GET", "/reports", None, None, "").await;
+  assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_route_consumes_overrides_controller() {
+  let app = ReportController::router();
+  for content_type in ["application/xml", "text/csv", "TEXT/PLAIN"] {
+    let status = send(
+      app.clone(),
+      "POST",
+      "/reports/import",
+      Some(content_type),
+      None,
+      "<a/>",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", content_type);
+  }
+
+  let json = Some("application/json");
+  let status = send(app.clone(), "POST", "/reports/import", json, None, "{}").await;
+  assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+  let status = send(app, "POST", "/reports/import", None, None, "").await;
+  assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[tokio::test]
+async fn test_produces() {
+  let app = ReportController::router();
+  let xml = Some("application/xml");
+  for accept in [
+    None,
+    Some("text/csv"),
+    Some("*/*"),
+    Some("text/html, application/*;q=0.5"),
+    Some("text/*;q=0, application/json"),
+  ] {
+    let status = send(app.clone(), "POST", "/reports/import", xml, accept, "<a/>").await;
+    assert_eq!(status, StatusCode::OK, "{:?}", accept);
+  }
+
+  for accept in ["text/html", "text/csv;q=0", "*/*;q=0"] {
+    let status = send(
+      app.clone(),
+      "POST",
+      "/reports/import",
+      xml,
+      Some(accept),
+      "<a/>",
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_ACCEPTABLE, "{}", accept);
+  }
+
+  // Controller-level produces
+  let status = send(app, "GET", "/reports", None, Some("text/html"), "").await;
+  assert_eq!(status, StatusCode::NOT_ACCEPTABLE);
+}
+
+#[tokio::test]
+async fn test_media_type_parameters_are_ignored() {
+  let app = ReportController::router();
+  for (content_type, accept) in [
+    ("text/plain; charset=utf-8", "text/plain; charset=utf-8"),
+    ("text/plain", "text/plain"),
+    ("Text/Plain;charset=UTF-8", "text/*"),
+  ] {
+    let status = send(
+      app.clone(),
+      "POST",
+      "/reports/notes",
+      Some(content_type),
+      Some(accept),
+      "note",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", content_type);
+  }
+
+  let json = Some("application/json");
+  let status = send(app.clone(), "POST", "/reports/notes", json, None, "{}").await;
+  assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+  let text = Some("text/plain");
+  let status = send(app, "POST", "/reports/notes", text, json, "note").await;
+  assert_eq!(status, StatusCode::NOT_ACCEPTABLE);
+}
+
+#[tokio::test]
+async fn test_method_not_allowed_is_unchanged() {
+  let status = send(
+    ReportController::router(),
+    "PUT",
+    "/reports/import",
+    Some("application/json"),
+    Some("text/html"),
+    "",
+  )
+  .await;
+  assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+}
+
+#[tokio::test]
+async fn test_text_extractors_imply_media_type() {
+  let app = TextController::router();
+  let plain = Some("text/plain; charset=utf-8");
+  let status = send(app.clone(), "POST", "/text/plain", plain, None, "hi").await;
+  assert_eq!(status, StatusCode::OK);
+
+  let json = Some("application/json");
+  let status = send(app.clone(), "POST", "/text/plain", json, None, "hi").await;
+  assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+  for content_type in ["application/xml", "text/xml"] {
+    let status = send(
+      app.clone(),
+      "POST",
+      "/text/xml",
+      Some(content_type),
+      None,
+      "<a/>",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+  }
+
+  let status = send(app.clone(), "POST", "/text/xml", plain, None, "<a/>").await;
+  assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[tokio::test]
+async fn test_any_content_type_opt_out() {
+  let app = TextController::router();
+  let json = Some("application/json");
+  let status = send(app.clone(), "POST", "/text/raw", json, None, "hi").await;
+  assert_eq!(status, StatusCode::OK);
+
+  let status = send(app, "POST", "/text/raw", None, None, "hi").await;
+  assert_eq!(status, StatusCode::OK);
+}
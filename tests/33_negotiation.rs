@@ -0,0 +1,306 @@
+//! Integration tests for Accept-based response negotiation
+//!
+//! Tests `negotiate` on routes and controllers, format selection by quality, `Vary: Accept`
+//! and 406 answers. XML, MessagePack and CSV require the 'xml', 'msgpack' and 'csv' features
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::Response;
+use route_controller::{controller, get, post};
+use serde::{Deserialize, Serialize};
+use tower::ServiceExt;
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct Book {
+  id: u32,
+  title: String,
+}
+
+fn book(id: u32) -> Book {
+  Book {
+    id,
+    title: format!("Book {}", id),
+  }
+}
+
+struct BookController;
+
+#[controller(path = "/books", content_type("text/plain"))]
+impl BookController {
+  #[get("/{id}", extract(id = Path), negotiate)]
+  async fn show(id: u32) -> Result<Book, StatusCode> {
+    if id == 0 {
+      return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(book(id))
+  }
+
+  #[post("/", extract(new_book = Json), negotiate(json), status = 201)]
+  async fn create(new_book: Book) -> Book {
+    new_book
+  }
+
+  #[get("/plain")]
+  async fn plain() -> &'static str {
+    "plain"
+  }
+}
+
+struct ApiController;
+
+#[controller(path = "/api", negotiate(json))]
+impl ApiController {
+  #[get("/book")]
+  async fn book() -> Book {
+    book(1)
+  }
+
+  #[get("/health", negotiate = false)]
+  async fn health() -> StatusCode {
+    StatusCode::NO_CONTENT
+  }
+}
+
+async fn send(app: axum::Router, method: &str, uri: &str, accept: Option<&str>) -> Response {
+  let mut builder = Request::builder()
+    .method(method)
+    .uri(uri)
+    .header("content-type", "application/json");
+  if let Some(accept) = accept {
+    builder = builder.header("accept", accept);
+  }
+  let body = serde_json::to_vec(&book(9)).unwrap();
+  app
+    .oneshot(builder.body(Body::from(body)).unwrap())
+    .await
+    .unwrap()
+}
+
+async fn body_bytes(response: Response) -> Vec<u8> {
+  axum::body::to_bytes(response.into_body(), usize::MAX)
+    .await
+    .unwrap()
+    .to_vec()
+}
+
+#[tokio::test]
+async fn test_json_by_default() {
+  for accept in [None, Some("*/*"), Some("application/json")] {
+    let response = send(BookController::router(), "GET", "/books/3", accept).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/json");
+    assert_eq!(response.headers()["vary"], "accept");
+    let body: Book = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(body, book(3));
+  }
+}
+
+#[tokio::test]
+async fn test_not_acceptable() {
+  let response = send(
+    BookController::router(),
+    "GET",
+    "/books/3",
+    Some("text/html"),
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
+
+  let accept = Some("application/json;q=0, */*");
+  let response = send(ApiController::router(), "GET", "/api/book", accept).await;
+  assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
+}
+
+#[tokio::test]
+async fn test_errors_pass_through() {
+  let response = send(BookController::router(), "GET", "/books/0", None).await;
+  assert_eq!(response.status(), StatusCode::NOT_FOUND);
+  assert!(response.headers().get("vary").is_none());
+}
+
+#[tokio::test]
+async fn test_status_and_controller_content_type() {
+  let response = send(BookController::router(), "POST", "/books", None).await;
+  assert_eq!(response.status(), StatusCode::CREATED);
+  // The controller content type does not replace the negotiated one
+  assert_eq!(response.headers()["content-type"], "application/json");
+
+  let response = send(BookController::router(), "GET", "/books/plain", None).await;
+  assert_eq!(response.headers()["content-type"], "text/plain");
+}
+
+#[tokio::test]
+async fn test_controller_level_negotiation() {
+  let response = send(ApiController::router(), "GET", "/api/book", None).await;
+  assert_eq!(response.headers()["content-type"], "application/json");
+  assert_eq!(response.headers()["vary"], "accept");
+
+  let response = send(
+    ApiController::router(),
+    "GET",
+    "/api/health",
+    Some("text/html"),
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::NO_CONTENT);
+}
+
+#[cfg(all(feature = "xml", feature = "msgpack", feature = "csv"))]
+mod formats {
+  use super::*;
+
+  struct ReportController;
+
+  #[controller(path = "/reports")]
+  impl ReportController {
+    #[get("/book", negotiate)]
+    async fn book() -> Book {
+      book(1)
+    }
+
+    #[get("/books", negotiate(csv, json))]
+    async fn books() -> Vec<Book> {
+      vec![book(1), book(2)]
+    }
+
+    #[get("/list", negotiate(xml, csv))]
+    async fn list() -> Vec<Book> {
+      vec![book(1), book(2)]
+    }
+
+    #[get("/catalog", respond = xml, xml_root = "catalog")]
+    async fn catalog() -> Vec<Book> {
+      vec![book(1)]
+    }
+
+    // CSV cannot write the nested book
+    #[get("/shelves", negotiate(csv, xml))]
+    async fn shelves() -> Vec<Shelf> {
+      vec![shelf()]
+    }
+
+    #[get("/shelves.csv", respond = csv)]
+    async fn shelves_csv() -> Vec<Shelf> {
+      vec![shelf()]
+    }
+  }
+
+  #[derive(Serialize)]
+  struct Shelf {
+    name: String,
+    book: Book,
+  }
+
+  fn shelf() -> Shelf {
+    Shelf {
+      name: "Fiction".to_string(),
+      book: book(1),
+    }
+  }
+
+  #[tokio::test]
+  async fn test_xml() {
+    for accept in ["application/xml", "text/xml"] {
+      let response = send(
+        ReportController::router(),
+        "GET",
+        "/reports/book",
+        Some(accept),
+      )
+      .await;
+      assert_eq!(response.status(), StatusCode::OK);
+      assert_eq!(response.headers()["content-type"], accept);
+      let body = String::from_utf8(body_bytes(response).await).unwrap();
+      assert_eq!(body, "<Book><id>1</id><title>Book 1</title></Book>");
+    }
+  }
+
+  #[tokio::test]
+  async fn test_msgpack() {
+    let accept = Some("application/msgpack");
+    let response = send(ReportController::router(), "GET", "/reports/book", accept).await;
+    assert_eq!(response.headers()["content-type"], "application/msgpack");
+    let body: Book = rmp_serde::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(body, book(1));
+  }
+
+  #[tokio::test]
+  async fn test_quality_ordering() {
+    let accept = Some("application/json;q=0.5, application/xml;q=0.8, */*;q=0.1");
+    let response = send(ReportController::router(), "GET", "/reports/book", accept).await;
+    assert_eq!(response.headers()["content-type"], "application/xml");
+
+    // Equal quality goes to the first listed format
+    let accept = Some("application/msgpack, application/json");
+    let response = send(ReportController::router(), "GET", "/reports/book", accept).await;
+    assert_eq!(response.headers()["content-type"], "application/json");
+  }
+
+  #[tokio::test]
+  async fn test_csv() {
+    let response = send(ReportController::router(), "GET", "/reports/books", None).await;
+    assert_eq!(response.headers()["content-type"], "text/csv");
+    let body = String::from_utf8(body_bytes(response).await).unwrap();
+    assert_eq!(body, "id,title\n1,Book 1\n2,Book 2\n");
+
+    let accept = Some("application/json");
+    let response = send(ReportController::router(), "GET", "/reports/books", accept).await;
+    assert_eq!(response.headers()["content-type"], "application/json");
+  }
+
+  #[tokio::test]
+  async fn test_xml_sequences() {
+    let list = "<items><item><id>1</id><title>Book 1</title></item>\
+                <item><id>2</id><title>Book 2</title></item></items>";
+    for accept in [None, Some("application/json;q=0, */*;q=0.1")] {
+      let response = send(ReportController::router(), "GET", "/reports/list", accept).await;
+      assert_eq!(response.status(), StatusCode::OK);
+      assert_eq!(response.headers()["content-type"], "application/xml");
+      let body = String::from_utf8(body_bytes(response).await).unwrap();
+      assert_eq!(body, list);
+    }
+
+    let accept = Some("text/csv");
+    let response = send(ReportController::router(), "GET", "/reports/list", accept).await;
+    assert_eq!(response.headers()["content-type"], "text/csv");
+
+    let response = send(ReportController::router(), "GET", "/reports/catalog", None).await;
+    let body = String::from_utf8(body_bytes(response).await).unwrap();
+    assert_eq!(
+      body,
+      "<catalog><item><id>1</id><title>Book 1</title></item></catalog>"
+    );
+  }
+
+  #[tokio::test]
+  async fn test_serialization_failure() {
+    // A format that fails to serialize gives way to the next acceptable one
+    let response = send(ReportController::router(), "GET", "/reports/shelves", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/xml");
+
+    let accept = Some("text/csv");
+    let response = send(
+      ReportController::router(),
+      "GET",
+      "/reports/shelves",
+      accept,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(response.headers()["vary"], "accept");
+    let body = String::from_utf8(body_bytes(response).await).unwrap();
+    assert_eq!(body, "failed to serialize response as text/csv");
+
+    let response = send(
+      ReportController::router(),
+      "GET",
+      "/reports/shelves.csv",
+      None,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let body = String::from_utf8(body_bytes(response).await).unwrap();
+    assert_eq!(body, "failed to serialize response as text/csv");
+  }
+}
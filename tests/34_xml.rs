@@ -0,0 +1,185 @@
+//! Integration tests for typed XML bodies (requires 'xml' feature)
+//!
+//! Tests the `XmlBody` extractor, its 400/415 rejections and `respond = xml`
+
+#![cfg(feature = "xml")]
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use route_controller::{catch, controller, get, post};
+use serde::{Deserialize, Serialize};
+use tower::ServiceExt;
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct Order {
+  id: u32,
+  item: String,
+}
+
+struct OrderController;
+
+#[controller(path = "/orders")]
+impl OrderController {
+  #[post("/", extract(order = XmlBody), respond = xml)]
+  async fn create(order: Order) -> Order {
+    Order {
+      id: order.id + 1,
+      item: order.item,
+    }
+  }
+
+  #[post("/raw", extract(xml = Xml))]
+  async fn raw(xml: String) -> String {
+    xml
+  }
+
+  #[get("/{id}", extract(id = Path), respond = xml)]
+  async fn show(id: u32) -> Result<Order, StatusCode> {
+    match id {
+      0 => Err(StatusCode::NOT_FOUND),
+      id => Ok(Order {
+        id,
+        item: "book".to_string(),
+      }),
+    }
+  }
+}
+
+struct CatchController;
+
+#[controller(path = "/catch")]
+impl CatchController {
+  #[post("/", extract(order = XmlBody))]
+  async fn create(order: Order) -> String {
+    order.item
+  }
+
+  #[catch]
+  fn on_rejection(status: StatusCode, message: String) -> impl IntoResponse {
+    (status, format!("caught:{}", !message.is_empty()))
+  }
+}
+
+async fn send(
+  app: axum::Router,
+  method: &str,
+  uri: &str,
+  content_type: &str,
+  body: &str,
+) -> Response {
+  let request = Request::builder()
+    .method(method)
+    .uri(uri)
+    .header("content-type", content_type)
+    .body(Body::from(body.to_string()))
+    .unwrap();
+  app.oneshot(request).await.unwrap()
+}
+
+async fn body_text(response: Response) -> String {
+  let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+    .await
+    .unwrap();
+  String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+const ORDER: &str = "<Order><id>7</id><item>book</item></Order>";
+
+#[tokio::test]
+async fn test_xml_body_and_response() {
+  for content_type in ["application/xml", "text/xml; charset=utf-8"] {
+    let response = send(
+      OrderController::router(),
+      "POST",
+      "/orders",
+      content_type,
+      ORDER,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/xml");
+    assert_eq!(
+      body_text(response).await,
+      "<Order><id>8</id><item>book</item></Order>"
+    );
+  }
+}
+
+#[tokio::test]
+async fn test_invalid_xml_is_bad_request() {
+  let response = send(
+    OrderController::router(),
+    "POST",
+    "/orders",
+    "application/xml",
+    "<Order><id>seven</id></Order>",
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+  assert_eq!(response.headers()["content-type"], "application/json");
+  let body: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+  assert_eq!(body["error"], "invalid_body");
+  assert_eq!(body["format"], "xml");
+  assert!(body["message"].as_str().is_some_and(|m| !m.is_empty()));
+}
+
+#[tokio::test]
+async fn test_wrong_content_type_is_unsupported() {
+  let response = send(
+    OrderController::router(),
+    "POST",
+    "/orders",
+    "application/json",
+    ORDER,
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[tokio::test]
+async fn test_string_xml_extractor_unchanged() {
+  let response = send(
+    OrderController::router(),
+    "POST",
+    "/orders/raw",
+    "text/xml",
+    "<not-an-order/>",
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::OK);
+  assert_eq!(body_text(response).await, "<not-an-order/>");
+}
+
+#[tokio::test]
+async fn test_respond_ignores_accept_and_errors() {
+  let request = Request::builder()
+    .uri("/orders/3")
+    .header("accept", "application/json")
+    .body(Body::empty())
+    .unwrap();
+  let response = OrderController::router().oneshot(request).await.unwrap();
+  assert_eq!(response.status(), StatusCode::OK);
+  assert_eq!(response.headers()["content-type"], "application/xml");
+
+  let request = Request::builder()
+    .uri("/orders/0")
+    .body(Body::empty())
+    .unwrap();
+  let response = OrderController::router().oneshot(request).await.unwrap();
+  assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_catch_maps_invalid_xml() {
+  let response = send(
+    CatchController::router(),
+    "POST",
+    "/catch",
+    "application/xml",
+    "<Order>",
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+  assert_eq!(body_text(response).await, "caught:true");
+}
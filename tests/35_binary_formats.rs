@@ -0,0 +1,141 @@
+//! Integration tests for MessagePack and CBOR bodies (requires 'msgpack' and 'cbor' features)
+//!
+//! Tests the `MsgPack` and `Cbor` extractors, their 400/415 rejections and `respond = ...`
+
+#![cfg(all(feature = "msgpack", feature = "cbor"))]
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::Response;
+use route_controller::{controller, post};
+use serde::{Deserialize, Serialize};
+use tower::ServiceExt;
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct Reading {
+  device: String,
+  value: f64,
+}
+
+struct TelemetryController;
+
+#[controller(path = "/telemetry")]
+impl TelemetryController {
+  #[post("/msgpack", extract(reading = MsgPack), respond = msgpack)]
+  async fn msgpack(reading: Reading) -> Reading {
+    Reading {
+      value: reading.value * 2.0,
+      ..reading
+    }
+  }
+
+  #[post("/cbor", extract(reading = Cbor), respond = cbor)]
+  async fn cbor(reading: Reading) -> Reading {
+    Reading {
+      value: reading.value * 2.0,
+      ..reading
+    }
+  }
+
+  #[post("/any", extract(reading = MsgPack(any_content_type)))]
+  async fn any(reading: Reading) -> String {
+    reading.device
+  }
+}
+
+fn reading() -> Reading {
+  Reading {
+    device: "sensor-1".to_string(),
+    value: 21.5,
+  }
+}
+
+fn cbor_bytes(value: &Reading) -> Vec<u8> {
+  let mut buffer = Vec::new();
+  ciborium::into_writer(value, &mut buffer).unwrap();
+  buffer
+}
+
+async fn send(uri: &str, content_type: &str, body: Vec<u8>) -> Response {
+  let request = Request::builder()
+    .method("POST")
+    .uri(uri)
+    .header("content-type", content_type)
+    .body(Body::from(body))
+    .unwrap();
+  TelemetryController::router()
+    .oneshot(request)
+    .await
+    .unwrap()
+}
+
+async fn body_bytes(response: Response) -> Vec<u8> {
+  axum::body::to_bytes(response.into_body(), usize::MAX)
+    .await
+    .unwrap()
+    .to_vec()
+}
+
+#[tokio::test]
+async fn test_msgpack_round_trip() {
+  for content_type in ["application/msgpack", "application/x-msgpack"] {
+    let body = rmp_serde::to_vec_named(&reading()).unwrap();
+    let response = send("/telemetry/msgpack", content_type, body).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/msgpack");
+    let echoed: Reading = rmp_serde::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(echoed.value, 43.0);
+  }
+}
+
+#[tokio::test]
+async fn test_cbor_round_trip() {
+  let response = send(
+    "/telemetry/cbor",
+    "application/cbor",
+    cbor_bytes(&reading()),
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::OK);
+  assert_eq!(response.headers()["content-type"], "application/cbor");
+  let echoed: Reading = ciborium::from_reader(&body_bytes(response).await[..]).unwrap();
+  assert_eq!(echoed.device, "sensor-1");
+  assert_eq!(echoed.value, 43.0);
+}
+
+#[tokio::test]
+async fn test_invalid_bodies_are_bad_request() {
+  for (uri, content_type, format) in [
+    ("/telemetry/msgpack", "application/msgpack", "msgpack"),
+    ("/telemetry/cbor", "application/cbor", "cbor"),
+  ] {
+    let response = send(uri, content_type, vec![0xc1, 0xff, 0x00]).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", uri);
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(body["error"], "invalid_body");
+    assert_eq!(body["format"], format);
+  }
+}
+
+#[tokio::test]
+async fn test_wrong_content_type_is_unsupported() {
+  let body = rmp_serde::to_vec_named(&reading()).unwrap();
+  let response = send("/telemetry/msgpack", "application/cbor", body).await;
+  assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+  let response = send(
+    "/telemetry/cbor",
+    "application/json",
+    cbor_bytes(&reading()),
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[tokio::test]
+async fn test_any_content_type() {
+  let body = rmp_serde::to_vec_named(&reading()).unwrap();
+  let response = send("/telemetry/any", "application/octet-stream", body).await;
+  assert_eq!(response.status(), StatusCode::OK);
+  assert_eq!(body_bytes(response).await, b"sensor-1");
+}
@@ -0,0 +1,111 @@
+//! Integration tests for streaming request bodies
+//!
+//! Tests the `BodyStream` extractor against route body limits and axum's default, and `Bytes`
+//! parameters typed as `axum::body::Bytes`
+
+use axum::body::{Body, BodyDataStream, Bytes};
+use axum::http::{Request, StatusCode};
+use futures_util::StreamExt;
+use route_controller::{controller, post};
+use tower::ServiceExt;
+
+struct UploadController;
+
+#[controller(path = "/uploads")]
+impl UploadController {
+  #[post("/stream", extract(body = BodyStream), body_limit = "1KB")]
+  async fn stream(mut body: BodyDataStream) -> Result<String, StatusCode> {
+    let mut total = 0;
+    while let Some(chunk) = body.next().await {
+      total += chunk.map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?.len();
+    }
+    Ok(total.to_string())
+  }
+
+  #[post("/body", extract(body = BodyStream))]
+  async fn body(body: Body) -> String {
+    let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+    bytes.len().to_string()
+  }
+
+  // Without body_limit, axum's 2 MB default applies
+  #[post("/default", extract(body = BodyStream))]
+  async fn default(mut body: BodyDataStream) -> Result<String, StatusCode> {
+    let mut total = 0;
+    while let Some(chunk) = body.next().await {
+      total += chunk.map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?.len();
+    }
+    Ok(total.to_string())
+  }
+
+  #[post("/bytes", extract(data = Bytes))]
+  async fn bytes(data: Bytes) -> Bytes {
+    data
+  }
+
+  #[post("/vec", extract(data = Bytes))]
+  async fn vec(data: Vec<u8>) -> String {
+    data.len().to_string()
+  }
+}
+
+async fn post(uri: &str, body: impl Into<Body>) -> (StatusCode, String) {
+  let request = Request::builder()
+    .method("POST")
+    .uri(uri)
+    .body(body.into())
+    .unwrap();
+  let response = UploadController::router().oneshot(request).await.unwrap();
+  let status = response.status();
+  let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+    .await
+    .unwrap();
+  (status, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn test_stream_within_limit() {
+  let chunks = futures_util::stream::iter(
+    (0..4).map(|_| Ok::<_, std::io::Error>(Bytes::from(vec![b'x'; 256]))),
+  );
+  let (status, body) = post("/uploads/stream", Body::from_stream(chunks)).await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "1024");
+}
+
+#[tokio::test]
+async fn test_stream_over_limit() {
+  let chunks = futures_util::stream::iter(
+    (0..5).map(|_| Ok::<_, std::io::Error>(Bytes::from(vec![b'x'; 256]))),
+  );
+  let (status, _) = post("/uploads/stream", Body::from_stream(chunks)).await;
+  assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_stream_default_limit() {
+  const DEFAULT_LIMIT: usize = 2 * 1024 * 1024;
+  let (status, body) = post("/uploads/default", vec![b'x'; DEFAULT_LIMIT]).await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, DEFAULT_LIMIT.to_string());
+
+  let (status, _) = post("/uploads/default", vec![b'x'; DEFAULT_LIMIT + 1]).await;
+  assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_body_parameter() {
+  let (status, body) = post("/uploads/body", "hello").await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "5");
+}
+
+#[tokio::test]
+async fn test_bytes_parameter_types() {
+  let (status, body) = post("/uploads/bytes", "raw bytes").await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "raw bytes");
+
+  let (_, body) = post("/uploads/vec", "raw bytes").await;
+  assert_eq!(body, "9");
+}
@@ -0,0 +1,139 @@
+//! Integration tests for optional and alternative body parameter types
+//!
+//! Tests `Option<T>` bodies for `Json`, `Form` and `Text`, `Box<[u8]>` for `Bytes`, and
+//! `Cow<str>`/`Arc<str>` for the text extractors
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::Response;
+use route_controller::{controller, post};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::sync::Arc;
+use tower::ServiceExt;
+use validator::Validate;
+
+#[derive(Deserialize, Validate)]
+struct Filter {
+  #[validate(length(min = 1))]
+  name: String,
+}
+
+struct SearchController;
+
+#[controller(path = "/search")]
+impl SearchController {
+  #[post("/json", extract(filter = Json(validate)))]
+  async fn json(filter: Option<Filter>) -> String {
+    filter.map_or("all".to_string(), |filter| filter.name)
+  }
+
+  #[post("/form", extract(filter = Form))]
+  async fn form(filter: Option<Filter>) -> String {
+    filter.map_or("all".to_string(), |filter| filter.name)
+  }
+
+  #[post("/text", extract(note = Text))]
+  async fn text(note: Option<String>) -> String {
+    note.unwrap_or_else(|| "none".to_string())
+  }
+
+  #[post("/cow", extract(note = Text))]
+  async fn cow(note: Cow<'static, str>) -> String {
+    note.into_owned()
+  }
+
+  #[post("/arc", extract(note = Html))]
+  async fn arc(note: Option<Arc<str>>) -> String {
+    note.as_deref().unwrap_or("none").to_string()
+  }
+
+  #[post("/boxed", extract(data = Bytes))]
+  async fn boxed(data: Box<[u8]>) -> String {
+    data.len().to_string()
+  }
+}
+
+async fn send(uri: &str, content_type: Option<&str>, body: &'static str) -> Response {
+  let mut builder = Request::builder().method("POST").uri(uri);
+  if let Some(content_type) = content_type {
+    builder = builder.header("content-type", content_type);
+  }
+  SearchController::router()
+    .oneshot(builder.body(Body::from(body)).unwrap())
+    .await
+    .unwrap()
+}
+
+async fn body_text(response: Response) -> String {
+  let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+    .await
+    .unwrap();
+  String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_optional_json() {
+  let response = send("/search/json", None, "").await;
+  assert_eq!(response.status(), StatusCode::OK);
+  assert_eq!(body_text(response).await, "all");
+
+  let response = send(
+    "/search/json",
+    Some("application/json"),
+    r#"{"name":"rust"}"#,
+  )
+  .await;
+  assert_eq!(body_text(response).await, "rust");
+
+  // A present body is still checked
+  let response = send("/search/json", None, r#"{"name":"rust"}"#).await;
+  assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+  let response = send("/search/json", Some("application/json"), "{").await;
+  assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+  let response = send("/search/json", Some("application/json"), r#"{"name":""}"#).await;
+  assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_optional_form() {
+  let response = send("/search/form", None, "").await;
+  assert_eq!(body_text(response).await, "all");
+
+  let content_type = Some("application/x-www-form-urlencoded");
+  let response = send("/search/form", content_type, "name=axum").await;
+  assert_eq!(body_text(response).await, "axum");
+}
+
+#[tokio::test]
+async fn test_optional_text() {
+  let response = send("/search/text", None, "").await;
+  assert_eq!(response.status(), StatusCode::OK);
+  assert_eq!(body_text(response).await, "none");
+
+  let response = send("/search/text", Some("text/plain"), "hello").await;
+  assert_eq!(body_text(response).await, "hello");
+
+  // A Content-Type, when sent, must still match
+  let response = send("/search/text", Some("application/json"), "hello").await;
+  assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[tokio::test]
+async fn test_shared_text_types() {
+  let response = send("/search/cow", Some("text/plain"), "borrowed").await;
+  assert_eq!(body_text(response).await, "borrowed");
+
+  let response = send("/search/arc", Some("text/html"), "<p>shared</p>").await;
+  assert_eq!(body_text(response).await, "<p>shared</p>");
+
+  let response = send("/search/arc", None, "").await;
+  assert_eq!(body_text(response).await, "none");
+}
+
+#[tokio::test]
+async fn test_boxed_bytes() {
+  let response = send("/search/boxed", None, "12345").await;
+  assert_eq!(response.status(), StatusCode::OK);
+  assert_eq!(body_text(response).await, "5");
+}
@@ -0,0 +1,121 @@
+//! Integration tests for streaming responses
+//!
+//! Tests `stream = ndjson`, `stream = json_array` and `stream = csv` (requires 'csv' feature)
+//! on handlers returning `impl Stream`
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::Response;
+use futures_util::{Stream, StreamExt, stream};
+use route_controller::{controller, get};
+use serde::{Deserialize, Serialize};
+use tower::ServiceExt;
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct Row {
+  id: u32,
+  name: String,
+}
+
+fn rows(count: u32) -> impl Stream<Item = Row> {
+  stream::iter(1..=count).map(|id| Row {
+    id,
+    name: format!("row-{}", id),
+  })
+}
+
+struct ExportController;
+
+#[controller(path = "/export")]
+impl ExportController {
+  #[get("/ndjson", stream = ndjson)]
+  async fn ndjson() -> impl Stream<Item = Row> {
+    rows(3)
+  }
+
+  #[get("/array/{count}", extract(count = Path), stream = json_array)]
+  async fn array(count: u32) -> impl Stream<Item = Row> {
+    rows(count)
+  }
+
+  #[get("/checked/{count}", extract(count = Path), stream = ndjson, status = 202)]
+  async fn checked(count: u32) -> Result<impl Stream<Item = Row>, StatusCode> {
+    if count == 0 {
+      return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(rows(count))
+  }
+}
+
+async fn get(app: axum::Router, uri: &str) -> Response {
+  let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+  app.oneshot(request).await.unwrap()
+}
+
+/// Collects the body chunk by chunk, as the client receives it
+async fn chunks(response: Response) -> Vec<String> {
+  response
+    .into_body()
+    .into_data_stream()
+    .map(|chunk| String::from_utf8(chunk.unwrap().to_vec()).unwrap())
+    .collect()
+    .await
+}
+
+#[tokio::test]
+async fn test_ndjson() {
+  let response = get(ExportController::router(), "/export/ndjson").await;
+  assert_eq!(response.status(), StatusCode::OK);
+  assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+  let chunks = chunks(response).await;
+  assert_eq!(chunks.len(), 3);
+  assert_eq!(chunks[0], "{\"id\":1,\"name\":\"row-1\"}\n");
+  let row: Row = serde_json::from_str(&chunks[2]).unwrap();
+  assert_eq!(row.id, 3);
+}
+
+#[tokio::test]
+async fn test_json_array() {
+  let response = get(ExportController::router(), "/export/array/2").await;
+  assert_eq!(response.headers()["content-type"], "application/json");
+  let body = chunks(response).await.concat();
+  let rows: Vec<Row> = serde_json::from_str(&body).unwrap();
+  assert_eq!(rows.len(), 2);
+  assert_eq!(rows[1].name, "row-2");
+
+  let response = get(ExportController::router(), "/export/array/0").await;
+  assert_eq!(chunks(response).await.concat(), "[]");
+}
+
+#[tokio::test]
+async fn test_result_and_status() {
+  let response = get(ExportController::router(), "/export/checked/2").await;
+  assert_eq!(response.status(), StatusCode::ACCEPTED);
+  assert_eq!(chunks(response).await.len(), 2);
+
+  let response = get(ExportController::router(), "/export/checked/0").await;
+  assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[cfg(feature = "csv")]
+mod csv_stream {
+  use super::*;
+
+  struct CsvController;
+
+  #[controller(path = "/csv")]
+  impl CsvController {
+    #[get("/", stream = csv)]
+    async fn export() -> impl Stream<Item = Row> {
+      rows(2)
+    }
+  }
+
+  #[tokio::test]
+  async fn test_csv() {
+    let response = get(CsvController::router(), "/csv").await;
+    assert_eq!(response.headers()["content-type"], "text/csv");
+    let chunks = chunks(response).await;
+    assert_eq!(chunks, ["id,name\n1,row-1\n", "2,row-2\n"]);
+  }
+}
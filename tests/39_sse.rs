@@ -0,0 +1,166 @@
+//! Integration tests for Server-Sent Events routes
+//!
+//! Tests `#[sse]` with serialized items, `event`/`id` mapping, `Event` items, keep-alive
+//! settings and the `LastEventId` extractor alongside `Path` and `State`
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::Response;
+use axum::response::sse::Event;
+use futures_util::{Stream, stream};
+use route_controller::{controller, get, sse};
+use serde::Serialize;
+use tower::ServiceExt;
+
+#[derive(Serialize)]
+struct Message {
+  seq: u32,
+  kind: &'static str,
+  text: String,
+}
+
+#[derive(Clone)]
+struct Feed {
+  prefix: &'static str,
+}
+
+struct EventController;
+
+#[controller(path = "/events", state = Feed)]
+impl EventController {
+  #[sse(
+    "/rooms/{room}",
+    extract(room = Path, feed = State, last_id = LastEventId),
+    event = kind,
+    id = seq,
+    keep_alive = "30s"
+  )]
+  async fn room(room: String, feed: Feed, last_id: Option<String>) -> impl Stream<Item = Message> {
+    let start = last_id
+      .and_then(|id| id.parse::<u32>().ok())
+      .map_or(1, |id| id + 1);
+    stream::iter((start..=3).map(move |seq| Message {
+      seq,
+      kind: if seq == 3 { "done" } else { "message" },
+      text: format!("{}{}:{}", feed.prefix, room, seq),
+    }))
+  }
+
+  #[sse("/ticks", event = "tick", keep_alive = false)]
+  async fn ticks() -> impl Stream<Item = u32> {
+    stream::iter([1, 2])
+  }
+
+  #[sse("/raw")]
+  async fn raw() -> impl Stream<Item = Event> {
+    stream::iter([Event::default().event("hello").data("world")])
+  }
+
+  #[sse("/unsafe", event = kind, id = text)]
+  async fn unsafe_values() -> impl Stream<Item = Message> {
+    stream::iter([("ok", "1"), ("a\nb", "2")].map(|(kind, id)| Message {
+      seq: 0,
+      kind,
+      text: id.to_string(),
+    }))
+  }
+
+  #[sse("/unsafe_id", id = text)]
+  async fn unsafe_id() -> impl Stream<Item = Message> {
+    stream::iter([Message {
+      seq: 0,
+      kind: "message",
+      text: "1\u{0}2".to_string(),
+    }])
+  }
+
+  #[get("/plain")]
+  async fn plain() -> &'static str {
+    "plain"
+  }
+}
+
+async fn send(uri: &str, last_event_id: Option<&str>) -> Response {
+  let mut builder = Request::builder().uri(uri);
+  if let Some(id) = last_event_id {
+    builder = builder.header("last-event-id", id);
+  }
+  EventController::router()
+    .with_state(Feed { prefix: "#" })
+    .oneshot(builder.body(Body::empty()).unwrap())
+    .await
+    .unwrap()
+}
+
+async fn body_text(response: Response) -> String {
+  let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+    .await
+    .unwrap();
+  String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_serialized_events() {
+  let response = send("/events/rooms/lobby", None).await;
+  assert_eq!(response.status(), StatusCode::OK);
+  assert_eq!(response.headers()["content-type"], "text/event-stream");
+  let body = body_text(response).await;
+  assert!(body.starts_with(
+    "event: message\nid: 1\ndata: {\"seq\":1,\"kind\":\"message\",\"text\":\"#lobby:1\"}\n\n"
+  ));
+  assert!(body.contains("event: done\nid: 3\n"));
+}
+
+#[tokio::test]
+async fn test_last_event_id_resumes() {
+  let response = send("/events/rooms/lobby", Some("2")).await;
+  let body = body_text(response).await;
+  assert!(!body.contains("id: 2\n"));
+  assert_eq!(body.matches("data: ").count(), 1);
+}
+
+#[tokio::test]
+async fn test_constant_event_name() {
+  let body = body_text(send("/events/ticks", None).await).await;
+  assert_eq!(body, "event: tick\ndata: 1\n\nevent: tick\ndata: 2\n\n");
+}
+
+#[tokio::test]
+async fn test_event_items() {
+  let body = body_text(send("/events/raw", None).await).await;
+  assert_eq!(body, "event: hello\ndata: world\n\n");
+}
+
+#[tokio::test]
+async fn test_line_breaks_in_item_values_end_the_stream() {
+  use futures_util::StreamExt;
+
+  // The first event is sent, then the line break ends the stream instead of panicking
+  let mut chunks = send("/events/unsafe", None)
+    .await
+    .into_body()
+    .into_data_stream();
+  let first = chunks.next().await.unwrap().unwrap();
+  assert!(first.starts_with(b"event: ok\nid: 1\n"));
+  assert!(chunks.next().await.unwrap().is_err());
+
+  let body = send("/events/unsafe_id", None).await.into_body();
+  assert!(axum::body::to_bytes(body, usize::MAX).await.is_err());
+}
+
+#[tokio::test]
+async fn test_sse_routes_answer_get_only() {
+  let request = Request::builder()
+    .method("POST")
+    .uri("/events/ticks")
+    .body(Body::empty())
+    .unwrap();
+  let response = EventController::router()
+    .with_state(Feed { prefix: "#" })
+    .oneshot(request)
+    .await
+    .unwrap();
+  assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+  assert_eq!(body_text(send("/events/plain", None).await).await, "plain");
+}
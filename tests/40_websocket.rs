@@ -0,0 +1,137 @@
+//! Integration tests for WebSocket routes
+//!
+//! Tests `#[ws]` upgrades with `Path`, `Query` and `State` extractors, subprotocol selection,
+//! `max_message_size` and rejections answered before the handshake
+
+use axum::body::Body;
+use axum::extract::ws::{Message, WebSocket};
+use axum::http::{Request, StatusCode};
+use futures_util::{SinkExt, StreamExt};
+use route_controller::{controller, ws};
+use serde::Deserialize;
+use tokio_tungstenite::tungstenite;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tower::ServiceExt;
+
+#[derive(Clone)]
+struct Greeting(&'static str);
+
+#[derive(Deserialize)]
+struct Join {
+  name: String,
+}
+
+struct ChatController;
+
+#[controller(path = "/chat", state = Greeting)]
+impl ChatController {
+  #[ws(
+    "/{room}",
+    extract(room = Path, join = Query, greeting = State),
+    protocols = ["chat.v2", "chat.v1"],
+    max_message_size = "1KB"
+  )]
+  async fn chat(mut socket: WebSocket, room: u32, join: Join, greeting: Greeting) {
+    let protocol = socket
+      .protocol()
+      .and_then(|protocol| protocol.to_str().ok())
+      .unwrap_or("none")
+      .to_string();
+    let welcome = format!("{} {} in {} ({})", greeting.0, join.name, room, protocol);
+    if socket.send(Message::text(welcome)).await.is_err() {
+      return;
+    }
+    while let Some(Ok(message)) = socket.recv().await {
+      if let Message::Text(text) = message {
+        if socket
+          .send(Message::text(text.to_uppercase()))
+          .await
+          .is_err()
+        {
+          return;
+        }
+      }
+    }
+  }
+}
+
+async fn serve() -> std::net::SocketAddr {
+  let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let addr = listener.local_addr().unwrap();
+  let app = ChatController::router().with_state(Greeting("hello"));
+  tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+  addr
+}
+
+fn text(message: tungstenite::Message) -> String {
+  message.into_text().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_upgrade_with_extractors() {
+  let addr = serve().await;
+  let mut request = format!("ws://{}/chat/7?name=ada", addr)
+    .into_client_request()
+    .unwrap();
+  request
+    .headers_mut()
+    .insert("sec-websocket-protocol", "chat.v1".parse().unwrap());
+  let (mut socket, response) = tokio_tungstenite::connect_async(request).await.unwrap();
+  assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+  assert_eq!(response.headers()["sec-websocket-protocol"], "chat.v1");
+
+  let welcome = text(socket.next().await.unwrap().unwrap());
+  assert_eq!(welcome, "hello ada in 7 (chat.v1)");
+
+  socket
+    .send(tungstenite::Message::text("ping"))
+    .await
+    .unwrap();
+  assert_eq!(text(socket.next().await.unwrap().unwrap()), "PING");
+}
+
+#[tokio::test]
+async fn test_max_message_size() {
+  let addr = serve().await;
+  let url = format!("ws://{}/chat/1?name=bob", addr);
+  let (mut socket, response) = tokio_tungstenite::connect_async(url).await.unwrap();
+  assert!(response.headers().get("sec-websocket-protocol").is_none());
+  assert_eq!(
+    text(socket.next().await.unwrap().unwrap()),
+    "hello bob in 1 (none)"
+  );
+
+  // The server closes the connection on a message over 1 KB
+  socket
+    .send(tungstenite::Message::text("x".repeat(4096)))
+    .await
+    .unwrap();
+  let next = socket.next().await;
+  assert!(!matches!(next, Some(Ok(tungstenite::Message::Text(_)))));
+}
+
+#[tokio::test]
+async fn test_extractors_run_before_upgrade() {
+  let addr = serve().await;
+  let url = format!("ws://{}/chat/lobby?name=ada", addr);
+  match tokio_tungstenite::connect_async(url).await {
+    Err(tungstenite::Error::Http(response)) => {
+      assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+    other => panic!("expected a rejected handshake, got {:?}", other.map(|_| ())),
+  }
+}
+
+#[tokio::test]
+async fn test_plain_get_is_rejected() {
+  let request = Request::builder()
+    .uri("/chat/1?name=ada")
+    .body(Body::empty())
+    .unwrap();
+  let response = ChatController::router()
+    .with_state(Greeting("hello"))
+    .oneshot(request)
+    .await
+    .unwrap();
+  assert!(response.status().is_client_error());
+}
@@ -0,0 +1,285 @@
+//! Integration tests for JSON-RPC controllers
+//!
+//! Tests `#[rpc_controller]` dispatch by name with positional and named params, batches,
+//! notifications, the error objects of JSON-RPC 2.0 and `State`, `HeaderParam` (requires
+//! 'headers' feature) and `CookieParam` (requires 'cookies' feature) extractors
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::Response;
+use route_controller::{rpc, rpc_controller};
+use serde::Serialize;
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+#[derive(Clone)]
+struct Ledger {
+  currency: &'static str,
+}
+
+#[derive(Serialize)]
+struct Balance {
+  amount: i64,
+  currency: &'static str,
+}
+
+#[derive(Serialize)]
+struct RpcError {
+  code: i64,
+  message: String,
+}
+
+struct LedgerRpc;
+
+#[rpc_controller(path = "/rpc", state = Ledger)]
+impl LedgerRpc {
+  #[rpc]
+  async fn add(a: i64, b: i64) -> i64 {
+    a + b
+  }
+
+  #[rpc(name = "ledger.balance", extract(ledger = State))]
+  async fn balance(ledger: Ledger, account: String, offset: Option<i64>) -> Balance {
+    Balance {
+      amount: account.len() as i64 * 100 + offset.unwrap_or(0),
+      currency: ledger.currency,
+    }
+  }
+
+  #[rpc(name = "ledger.withdraw")]
+  fn withdraw(amount: i64) -> Result<i64, RpcError> {
+    if amount > 100 {
+      return Err(RpcError {
+        code: 1001,
+        message: "insufficient funds".to_string(),
+      });
+    }
+    Ok(100 - amount)
+  }
+
+  #[rpc]
+  async fn fail() -> Result<(), String> {
+    Err("unavailable".to_string())
+  }
+}
+
+async fn call(body: &str) -> Response {
+  let request = Request::builder()
+    .method("POST")
+    .uri("/rpc")
+    .header("content-type", "application/json")
+    .body(Body::from(body.to_string()))
+    .unwrap();
+  LedgerRpc::router()
+    .with_state(Ledger { currency: "EUR" })
+    .oneshot(request)
+    .await
+    .unwrap()
+}
+
+async fn reply(body: &str) -> Value {
+  let response = call(body).await;
+  assert_eq!(response.status(), StatusCode::OK);
+  let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+    .await
+    .unwrap();
+  serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn test_positional_and_named_params() {
+  let value = reply(r#"{"jsonrpc":"2.0","method":"add","params":[2,3],"id":1}"#).await;
+  assert_eq!(value, json!({ "jsonrpc": "2.0", "result": 5, "id": 1 }));
+
+  let value = reply(r#"{"jsonrpc":"2.0","method":"add","params":{"b":1,"a":4},"id":"x"}"#).await;
+  assert_eq!(value, json!({ "jsonrpc": "2.0", "result": 5, "id": "x" }));
+}
+
+#[tokio::test]
+async fn test_state_and_optional_params() {
+  let value = reply(r#"{"jsonrpc":"2.0","method":"ledger.balance","params":["abc"],"id":2}"#).await;
+  assert_eq!(value["result"], json!({ "amount": 300, "currency": "EUR" }));
+
+  let value = reply(
+    r#"{"jsonrpc":"2.0","method":"ledger.balance","params":{"account":"a","offset":5},"id":3}"#,
+  )
+  .await;
+  assert_eq!(value["result"]["amount"], 105);
+}
+
+#[tokio::test]
+async fn test_handler_errors() {
+  let value = reply(r#"{"jsonrpc":"2.0","method":"ledger.withdraw","params":[40],"id":4}"#).await;
+  assert_eq!(value["result"], 60);
+
+  let value = reply(r#"{"jsonrpc":"2.0","method":"ledger.withdraw","params":[400],"id":5}"#).await;
+  assert_eq!(
+    value["error"],
+    json!({ "code": 1001, "message": "insufficient funds" })
+  );
+
+  // Errors without a code and message are sent as the data of a server error
+  let value = reply(r#"{"jsonrpc":"2.0","method":"fail","id":6}"#).await;
+  assert_eq!(
+    value["error"],
+    json!({ "code": -32000, "message": "Server error", "data": "unavailable" })
+  );
+}
+
+#[tokio::test]
+async fn test_protocol_errors() {
+  let value = reply(r#"{"jsonrpc":"2.0","method":"add","#).await;
+  assert_eq!(value["error"]["code"], -32700);
+  assert_eq!(value["id"], Value::Null);
+
+  let value = reply(r#"{"jsonrpc":"1.0","method":"add","id":7}"#).await;
+  assert_eq!(value["error"]["code"], -32600);
+  assert_eq!(value["id"], 7);
+
+  let value = reply(r#"{"jsonrpc":"2.0","method":"subtract","id":8}"#).await;
+  assert_eq!(value["error"]["code"], -32601);
+
+  let value = reply(r#"{"jsonrpc":"2.0","method":"add","params":["two",3],"id":9}"#).await;
+  assert_eq!(value["error"]["code"], -32602);
+  assert_eq!(value["error"]["message"], "Invalid params");
+
+  let value = reply(r#"{"jsonrpc":"2.0","method":"add","params":[1,2,3],"id":10}"#).await;
+  assert_eq!(value["error"]["code"], -32602);
+
+  let value = reply(r#"{"jsonrpc":"2.0","method":"add","params":{"a":1,"c":2},"id":11}"#).await;
+  assert_eq!(value["error"]["code"], -32602);
+
+  let value = reply("[]").await;
+  assert_eq!(value["error"]["code"], -32600);
+}
+
+#[tokio::test]
+async fn test_batch_with_notifications() {
+  let value = reply(
+    r#"[
+      {"jsonrpc":"2.0","method":"add","params":[1,1],"id":1},
+      {"jsonrpc":"2.0","method":"add","params":[5,5]},
+      {"jsonrpc":"2.0","method":"missing","id":2},
+      {"foo":"bar"},
+      1
+    ]"#,
+  )
+  .await;
+  let replies = value.as_array().unwrap();
+  assert_eq!(replies.len(), 4);
+  assert_eq!(
+    replies[0],
+    json!({ "jsonrpc": "2.0", "result": 2, "id": 1 })
+  );
+  assert_eq!(replies[1]["error"]["code"], -32601);
+  assert_eq!(replies[2]["error"]["code"], -32600);
+  assert_eq!(replies[3]["id"], Value::Null);
+}
+
+#[tokio::test]
+async fn test_notifications_only() {
+  let response = call(r#"{"jsonrpc":"2.0","method":"add","params":[1,2]}"#).await;
+  assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+  // Failed notifications are not answered either
+  let response =
+    call(r#"[{"jsonrpc":"2.0","method":"missing"},{"jsonrpc":"2.0","method":"fail"}]"#).await;
+  assert_eq!(response.status(), StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn test_post_only() {
+  let request = Request::builder().uri("/rpc").body(Body::empty()).unwrap();
+  let response = LedgerRpc::router()
+    .with_state(Ledger { currency: "EUR" })
+    .oneshot(request)
+    .await
+    .unwrap();
+  assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+}
+
+#[cfg(feature = "headers")]
+mod header_params {
+  use super::*;
+
+  struct TenantRpc;
+
+  #[rpc_controller(path = "/tenants")]
+  impl TenantRpc {
+    #[rpc(name = "whoami", extract(x_tenant = HeaderParam))]
+    async fn whoami(x_tenant: String, greeting: Option<String>) -> String {
+      format!("{} {}", greeting.as_deref().unwrap_or("hello"), x_tenant)
+    }
+
+    // Optional headers read as on routes: None when absent
+    #[rpc(name = "region", extract(x_region = HeaderParam))]
+    async fn region(x_region: Option<String>) -> String {
+      x_region.unwrap_or_else(|| "none".to_string())
+    }
+  }
+
+  #[tokio::test]
+  async fn test_header_param() {
+    let request = Request::builder()
+      .method("POST")
+      .uri("/tenants")
+      .header("x-tenant", "acme")
+      .body(Body::from(
+        r#"[{"jsonrpc":"2.0","method":"whoami","id":1},{"jsonrpc":"2.0","method":"whoami","params":["hi"],"id":2}]"#,
+      ))
+      .unwrap();
+    let response = TenantRpc::router().oneshot(request).await.unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+      .await
+      .unwrap();
+    let value: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(value[0]["result"], "hello acme");
+    assert_eq!(value[1]["result"], "hi acme");
+  }
+
+  #[tokio::test]
+  async fn test_optional_header_param() {
+    let request = Request::builder()
+      .method("POST")
+      .uri("/tenants")
+      .body(Body::from(r#"{"jsonrpc":"2.0","method":"region","id":1}"#))
+      .unwrap();
+    let response = TenantRpc::router().oneshot(request).await.unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+      .await
+      .unwrap();
+    let value: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(value["result"], "none");
+  }
+}
+
+#[cfg(feature = "cookies")]
+mod cookie_params {
+  use super::*;
+
+  struct PrefsRpc;
+
+  #[rpc_controller(path = "/prefs")]
+  impl PrefsRpc {
+    #[rpc(name = "theme", extract(theme = CookieParam))]
+    async fn theme(theme: Option<String>) -> String {
+      theme.unwrap_or_else(|| "light".to_string())
+    }
+  }
+
+  #[tokio::test]
+  async fn test_cookie_param() {
+    let request = Request::builder()
+      .method("POST")
+      .uri("/prefs")
+      .header("cookie", "theme=dark")
+      .body(Body::from(r#"{"jsonrpc":"2.0","method":"theme","id":1}"#))
+      .unwrap();
+    let response = PrefsRpc::router().oneshot(request).await.unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+      .await
+      .unwrap();
+    let value: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(value["result"], "dark");
+  }
+}
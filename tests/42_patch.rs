@@ -0,0 +1,183 @@
+//! Integration tests for patch document extractors
+//!
+//! Tests `MergePatch` (RFC 7386) and `JsonPatch` (RFC 6902, requires 'json_patch' feature)
+//! with their `Content-Type` checks and the generated `apply_merge_patch` and
+//! `apply_json_patch` helpers
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::Response;
+use route_controller::{controller, patch};
+use serde::{Deserialize, Serialize};
+use tower::ServiceExt;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct Profile {
+  name: String,
+  email: Option<String>,
+  tags: Vec<String>,
+}
+
+fn stored() -> Profile {
+  Profile {
+    name: "Ada".to_string(),
+    email: Some("ada@example.com".to_string()),
+    tags: vec!["admin".to_string()],
+  }
+}
+
+struct ProfileController;
+
+#[controller(path = "/profiles")]
+impl ProfileController {
+  #[patch("/{id}", extract(id = Path, changes = MergePatch))]
+  async fn update(
+    id: u32,
+    changes: serde_json::Value,
+  ) -> Result<axum::Json<Profile>, (StatusCode, String)> {
+    assert_eq!(id, 1);
+    Self::apply_merge_patch(&stored(), &changes).map(axum::Json)
+  }
+}
+
+async fn send(app: axum::Router, uri: &str, content_type: &str, body: &str) -> Response {
+  let request = Request::builder()
+    .method("PATCH")
+    .uri(uri)
+    .header("content-type", content_type)
+    .body(Body::from(body.to_string()))
+    .unwrap();
+  app.oneshot(request).await.unwrap()
+}
+
+async fn profile(response: Response) -> Profile {
+  let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+    .await
+    .unwrap();
+  serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn test_merge_patch() {
+  let response = send(
+    ProfileController::router(),
+    "/profiles/1",
+    "application/merge-patch+json",
+    r#"{"name":"Ada L.","email":null}"#,
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::OK);
+  assert_eq!(
+    profile(response).await,
+    Profile {
+      name: "Ada L.".to_string(),
+      email: None,
+      tags: vec!["admin".to_string()],
+    }
+  );
+}
+
+#[tokio::test]
+async fn test_merge_patch_errors() {
+  // Plain JSON is not a merge patch
+  let response = send(
+    ProfileController::router(),
+    "/profiles/1",
+    "application/json",
+    r#"{"name":"x"}"#,
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+  let response = send(
+    ProfileController::router(),
+    "/profiles/1",
+    "application/merge-patch+json",
+    "{",
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+  // Removing a required field leaves a document that is no longer a Profile
+  let response = send(
+    ProfileController::router(),
+    "/profiles/1",
+    "application/merge-patch+json",
+    r#"{"name":null}"#,
+  )
+  .await;
+  assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[cfg(feature = "json_patch")]
+mod json_patch_ops {
+  use super::*;
+
+  struct OpsController;
+
+  #[controller(path = "/ops")]
+  impl OpsController {
+    #[patch("/", extract(ops = JsonPatch))]
+    async fn update(ops: json_patch::Patch) -> Result<axum::Json<Profile>, (StatusCode, String)> {
+      Self::apply_json_patch(&stored(), &ops).map(axum::Json)
+    }
+  }
+
+  #[tokio::test]
+  async fn test_json_patch() {
+    let response = send(
+      OpsController::router(),
+      "/ops",
+      "application/json-patch+json",
+      r#"[
+        {"op":"test","path":"/name","value":"Ada"},
+        {"op":"add","path":"/tags/-","value":"ops"},
+        {"op":"remove","path":"/email"}
+      ]"#,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let patched = profile(response).await;
+    assert_eq!(patched.tags, ["admin", "ops"]);
+    assert_eq!(patched.email, None);
+  }
+
+  #[tokio::test]
+  async fn test_json_patch_errors() {
+    let response = send(
+      OpsController::router(),
+      "/ops",
+      "application/json-patch+json",
+      r#"[{"op":"test","path":"/name","value":"Bob"}]"#,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = send(
+      OpsController::router(),
+      "/ops",
+      "application/json-patch+json",
+      r#"[{"op":"remove","path":"/missing"}]"#,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = send(
+      OpsController::router(),
+      "/ops",
+      "application/json-patch+json",
+      r#"[{"op":"frobnicate","path":"/name"}]"#,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = send(
+      OpsController::router(),
+      "/ops",
+      "application/merge-patch+json",
+      "[]",
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+  }
+}
@@ -0,0 +1,130 @@
+//! Integration tests for CSV bodies (requires 'csv' feature)
+//!
+//! Tests the `Csv` extractor with row and column errors, `respond = csv` with a
+//! `Content-Disposition` filename and `stream = csv` downloads
+
+#![cfg(feature = "csv")]
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::Response;
+use futures_util::{Stream, stream};
+use route_controller::{controller, get, post};
+use serde::{Deserialize, Serialize};
+use tower::ServiceExt;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct Product {
+  sku: String,
+  price: u32,
+}
+
+fn catalog() -> Vec<Product> {
+  vec![
+    Product {
+      sku: "A-1".to_string(),
+      price: 10,
+    },
+    Product {
+      sku: "B-2".to_string(),
+      price: 25,
+    },
+  ]
+}
+
+struct ImportController;
+
+#[controller(path = "/products")]
+impl ImportController {
+  #[post("/import", extract(rows = Csv))]
+  async fn import(rows: Vec<Product>) -> String {
+    let total: u32 = rows.iter().map(|row| row.price).sum();
+    format!("{} rows, total {}", rows.len(), total)
+  }
+
+  #[get("/export", respond = csv, filename = "products.csv")]
+  async fn export() -> Vec<Product> {
+    catalog()
+  }
+
+  #[get("/stream", stream = csv, filename = "products.csv")]
+  async fn stream() -> impl Stream<Item = Product> {
+    stream::iter(catalog())
+  }
+}
+
+async fn send(request: Request<Body>) -> Response {
+  ImportController::router().oneshot(request).await.unwrap()
+}
+
+fn import(content_type: &str, body: &str) -> Request<Body> {
+  Request::builder()
+    .method("POST")
+    .uri("/products/import")
+    .header("content-type", content_type)
+    .body(Body::from(body.to_string()))
+    .unwrap()
+}
+
+async fn body_text(response: Response) -> String {
+  let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+    .await
+    .unwrap();
+  String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_csv_import() {
+  let response = send(import("text/csv", "sku,price\nA-1,10\nB-2,25\n")).await;
+  assert_eq!(response.status(), StatusCode::OK);
+  assert_eq!(body_text(response).await, "2 rows, total 35");
+
+  let response = send(import("text/csv; charset=utf-8", "sku,price\n")).await;
+  assert_eq!(body_text(response).await, "0 rows, total 0");
+}
+
+#[tokio::test]
+async fn test_csv_errors_name_row_and_column() {
+  let response = send(import("text/csv", "sku,price\nA-1,10\nB-2,cheap\n")).await;
+  assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+  let error: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+  assert_eq!(error["format"], "csv");
+  assert_eq!(error["row"], 2);
+  assert_eq!(error["line"], 3);
+  assert_eq!(error["column"], "price");
+
+  // Records with a different number of fields have no single failing column
+  let response = send(import("text/csv", "sku,price\nA-1\n")).await;
+  let error: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+  assert_eq!(error["row"], 1);
+  assert_eq!(error["column"], serde_json::Value::Null);
+
+  let response = send(import("application/json", "[]")).await;
+  assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[tokio::test]
+async fn test_csv_download() {
+  let request = Request::builder()
+    .uri("/products/export")
+    .body(Body::empty())
+    .unwrap();
+  let response = send(request).await;
+  assert_eq!(response.headers()["content-type"], "text/csv");
+  assert_eq!(
+    response.headers()["content-disposition"],
+    "attachment; filename=\"products.csv\""
+  );
+  assert_eq!(body_text(response).await, "sku,price\nA-1,10\nB-2,25\n");
+
+  let request = Request::builder()
+    .uri("/products/stream")
+    .body(Body::empty())
+    .unwrap();
+  let response = send(request).await;
+  assert_eq!(
+    response.headers()["content-disposition"],
+    "attachment; filename=\"products.csv\""
+  );
+  assert_eq!(body_text(response).await, "sku,price\nA-1,10\nB-2,25\n");
+}